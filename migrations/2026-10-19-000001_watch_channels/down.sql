@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS sync_states;
+DROP TABLE IF EXISTS watch_channels;
@@ -0,0 +1,14 @@
+CREATE TABLE watch_channels (
+  id TEXT PRIMARY KEY NOT NULL,
+  calendar_id TEXT NOT NULL,
+  resource_id TEXT NOT NULL,
+  resource_uri TEXT,
+  token TEXT NOT NULL,
+  expiration DATETIME NOT NULL,
+  created_at DATETIME NOT NULL
+);
+CREATE TABLE sync_states (
+  calendar_id TEXT PRIMARY KEY NOT NULL,
+  sync_token TEXT,
+  updated_at DATETIME NOT NULL
+);
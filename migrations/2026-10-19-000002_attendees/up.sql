@@ -0,0 +1,9 @@
+CREATE TABLE attendees (
+  event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+  email TEXT NOT NULL,
+  display_name TEXT,
+  organizer BOOLEAN NOT NULL DEFAULT FALSE,
+  is_self BOOLEAN NOT NULL DEFAULT FALSE,
+  response_status TEXT,
+  PRIMARY KEY (event_id, email)
+);
@@ -0,0 +1,10 @@
+CREATE TABLE conference_entry_points (
+  event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+  position INTEGER NOT NULL,
+  entry_point_type TEXT NOT NULL,
+  uri TEXT NOT NULL,
+  label TEXT,
+  pin TEXT,
+  region_code TEXT,
+  PRIMARY KEY (event_id, position)
+);
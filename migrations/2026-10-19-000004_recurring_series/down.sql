@@ -0,0 +1,4 @@
+DROP TABLE IF EXISTS series_notification_settings;
+DROP INDEX IF EXISTS events_recurring_event_id;
+ALTER TABLE events DROP COLUMN original_start_time;
+ALTER TABLE events DROP COLUMN recurring_event_id;
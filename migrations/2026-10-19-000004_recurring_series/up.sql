@@ -0,0 +1,9 @@
+ALTER TABLE events ADD COLUMN recurring_event_id TEXT;
+ALTER TABLE events ADD COLUMN original_start_time DATETIME;
+CREATE INDEX events_recurring_event_id ON events (recurring_event_id);
+CREATE TABLE series_notification_settings (
+  recurring_event_id TEXT PRIMARY KEY NOT NULL,
+  notification_enabled BOOLEAN NOT NULL,
+  notification_sec_from_start INTEGER NOT NULL,
+  updated_at DATETIME NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Google が返したオフセット付きの文字列を、UTC の秒までの表記に揃える
+UPDATE events SET
+  start_datetime = strftime('%Y-%m-%dT%H:%M:%SZ', start_datetime),
+  end_datetime = strftime('%Y-%m-%dT%H:%M:%SZ', end_datetime),
+  original_start_time = strftime('%Y-%m-%dT%H:%M:%SZ', original_start_time);
+ALTER TABLE events ADD COLUMN all_day BOOLEAN NOT NULL DEFAULT FALSE;
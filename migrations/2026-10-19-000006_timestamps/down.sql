@@ -0,0 +1,12 @@
+UPDATE events SET
+  start_datetime = strftime('%Y-%m-%dT%H:%M:%SZ', start_datetime),
+  end_datetime = strftime('%Y-%m-%dT%H:%M:%SZ', end_datetime),
+  original_start_time = strftime('%Y-%m-%dT%H:%M:%SZ', original_start_time);
+UPDATE oauth_tokens SET
+  created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at),
+  updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', updated_at);
+UPDATE watch_channels SET
+  expiration = strftime('%Y-%m-%dT%H:%M:%SZ', expiration),
+  created_at = strftime('%Y-%m-%dT%H:%M:%SZ', created_at);
+UPDATE sync_states SET updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', updated_at);
+UPDATE series_notification_settings SET updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', updated_at);
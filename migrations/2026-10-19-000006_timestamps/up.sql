@@ -0,0 +1,14 @@
+-- Diesel が書き込む UTC の "YYYY-MM-DD HH:MM:SS+00:00" に揃え、文字列の比較でも時刻順になるようにする
+UPDATE events SET
+  start_datetime = strftime('%Y-%m-%d %H:%M:%S+00:00', start_datetime),
+  end_datetime = strftime('%Y-%m-%d %H:%M:%S+00:00', end_datetime),
+  original_start_time = strftime('%Y-%m-%d %H:%M:%S+00:00', original_start_time);
+-- oauth_tokens.expires_in は DATETIME(NUMERIC 型親和性)の列のため、秒数は整数のまま保存されている
+UPDATE oauth_tokens SET
+  created_at = strftime('%Y-%m-%d %H:%M:%S+00:00', created_at),
+  updated_at = strftime('%Y-%m-%d %H:%M:%S+00:00', updated_at);
+UPDATE watch_channels SET
+  expiration = strftime('%Y-%m-%d %H:%M:%S+00:00', expiration),
+  created_at = strftime('%Y-%m-%d %H:%M:%S+00:00', created_at);
+UPDATE sync_states SET updated_at = strftime('%Y-%m-%d %H:%M:%S+00:00', updated_at);
+UPDATE series_notification_settings SET updated_at = strftime('%Y-%m-%d %H:%M:%S+00:00', updated_at);
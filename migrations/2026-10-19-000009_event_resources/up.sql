@@ -0,0 +1,9 @@
+CREATE TABLE event_resources (
+  event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+  position INTEGER NOT NULL,
+  source TEXT NOT NULL,
+  url TEXT NOT NULL,
+  title TEXT,
+  mime_type TEXT,
+  PRIMARY KEY (event_id, position)
+);
@@ -0,0 +1 @@
+ALTER TABLE events ADD COLUMN provider TEXT NOT NULL DEFAULT 'google';
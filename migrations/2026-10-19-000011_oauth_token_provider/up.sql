@@ -0,0 +1 @@
+ALTER TABLE oauth_tokens ADD COLUMN provider TEXT NOT NULL DEFAULT 'google';
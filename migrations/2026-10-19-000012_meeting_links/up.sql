@@ -0,0 +1,9 @@
+CREATE TABLE meeting_links (
+  event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+  position INTEGER NOT NULL,
+  provider TEXT NOT NULL,
+  url TEXT NOT NULL,
+  meeting_id TEXT,
+  passcode TEXT,
+  PRIMARY KEY (event_id, position)
+);
@@ -0,0 +1 @@
+DROP TABLE IF EXISTS dial_ins;
@@ -0,0 +1,9 @@
+CREATE TABLE dial_ins (
+  event_id TEXT NOT NULL REFERENCES events(id) ON DELETE CASCADE,
+  position INTEGER NOT NULL,
+  number TEXT NOT NULL,
+  country TEXT,
+  meeting_id TEXT NOT NULL,
+  passcode TEXT,
+  PRIMARY KEY (event_id, position)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE events DROP COLUMN end_date;
+ALTER TABLE events DROP COLUMN start_date;
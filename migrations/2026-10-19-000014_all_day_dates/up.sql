@@ -0,0 +1,3 @@
+-- 終日の予定のカレンダー上の日付。UTC の日時から表示タイムゾーンで求めると日付がずれるため保存する
+ALTER TABLE events ADD COLUMN start_date DATE;
+ALTER TABLE events ADD COLUMN end_date DATE;
@@ -0,0 +1,429 @@
+use std::collections::{BTreeMap, HashSet};
+
+use async_trait::async_trait;
+use chrono_tz::Tz;
+use reqwest::{header::CONTENT_TYPE, Method, StatusCode, Url};
+
+use crate::{
+    env::Env,
+    ical,
+    provider::{self, CalendarEvent, CalendarProvider, EventBatch, SyncWindow},
+    repository,
+};
+
+pub mod multistatus;
+use self::multistatus::{DavResponse, MultiStatus};
+
+/// events.provider に保存する名前
+pub const CALDAV_PROVIDER_NAME: &str = "caldav";
+/// CalDAV のイベント ID の接頭辞。Google Calendar の ID と重ならないようにする
+const EVENT_ID_PREFIX: &str = "caldav:";
+
+#[derive(Debug)]
+pub enum Error {
+    Reqwest(reqwest::Error),
+    Unauthorized,
+    /// sync-collection の sync-token が失効している
+    InvalidSyncToken,
+    Http {
+        status: StatusCode,
+        body: String,
+    },
+    Parse(String),
+}
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Reqwest(e)
+    }
+}
+
+/**
+ * Nextcloud や Fastmail などの CalDAV サーバー。
+ * ユーザーのすべてのカレンダーを同期し、差分同期のトークンはカレンダーごとの sync-token をまとめて保存する
+ */
+pub struct CalDavProvider {
+    url: String,
+    username: String,
+    /// パスワード、またはアプリパスワード
+    password: String,
+    /// 終日の予定や、タイムゾーンのない日時を読むタイムゾーン
+    default_time_zone: Option<Tz>,
+}
+impl CalDavProvider {
+    /// CALDAV_URL が設定されていれば使う
+    pub fn from_env(env: &Env) -> Option<Self> {
+        Some(Self {
+            url: env.caldav_url.clone()?,
+            username: env.caldav_username.clone().unwrap_or_default(),
+            password: env.caldav_password.clone().unwrap_or_default(),
+            default_time_zone: env.display_timezone,
+        })
+    }
+
+    fn base_url(&self) -> Result<Url, Error> {
+        Url::parse(&self.url).map_err(|e| Error::Parse(e.to_string()))
+    }
+
+    async fn send(
+        &self,
+        method: &str,
+        url: &Url,
+        depth: &str,
+        body: String,
+    ) -> Result<MultiStatus, Error> {
+        let method =
+            Method::from_bytes(method.as_bytes()).map_err(|e| Error::Parse(e.to_string()))?;
+        let response = reqwest::Client::new()
+            .request(method, url.clone())
+            .basic_auth(&self.username, Some(&self.password))
+            .header("Depth", depth)
+            .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let body = response.text().await?;
+        match status {
+            status if status.is_success() => multistatus::parse(&body).map_err(Error::Parse),
+            StatusCode::UNAUTHORIZED => Err(Error::Unauthorized),
+            StatusCode::FORBIDDEN | StatusCode::CONFLICT if body.contains("valid-sync-token") => {
+                Err(Error::InvalidSyncToken)
+            }
+            status => Err(Error::Http { status, body }),
+        }
+    }
+
+    /// PROPFIND で 1 つの値を取得する。レスポンスがなければ None
+    async fn propfind_one<T>(
+        &self,
+        url: &Url,
+        prop: &str,
+        pick: impl Fn(DavResponse) -> Option<T>,
+    ) -> Result<Option<T>, Error> {
+        let multistatus = self.send("PROPFIND", url, "0", propfind_body(prop)).await?;
+        Ok(multistatus.responses.into_iter().find_map(pick))
+    }
+
+    /**
+     * current-user-principal → calendar-home-set → 配下のカレンダーの順に辿り、予定を扱えるカレンダーの URL を返す
+     */
+    pub async fn discover_calendars(&self) -> Result<Vec<Url>, Error> {
+        let base_url = self.base_url()?;
+        let principal = match self
+            .propfind_one(&base_url, "<d:current-user-principal/>", |response| {
+                response.current_user_principal
+            })
+            .await?
+        {
+            Some(href) => join(&base_url, &href)?,
+            None => base_url.clone(),
+        };
+        let home = match self
+            .propfind_one(&principal, "<c:calendar-home-set/>", |response| {
+                response.calendar_home_set
+            })
+            .await?
+        {
+            Some(href) => join(&base_url, &href)?,
+            None => principal,
+        };
+
+        let multistatus = self
+            .send(
+                "PROPFIND",
+                &home,
+                "1",
+                propfind_body(
+                    "<d:resourcetype/><d:displayname/><c:supported-calendar-component-set/>",
+                ),
+            )
+            .await?;
+        multistatus
+            .responses
+            .into_iter()
+            .filter(|response| response.is_calendar && response.supports_events)
+            .map(|response| join(&base_url, &response.href))
+            .collect()
+    }
+
+    async fn sync_token_of(&self, calendar: &Url) -> Result<Option<String>, Error> {
+        self.propfind_one(calendar, "<d:sync-token/>", |response| response.sync_token)
+            .await
+    }
+
+    /**
+     * calendar-query で期間内のイベントを取得する。繰り返し予定はサーバーで期間内のインスタンスに展開する
+     */
+    async fn query_events(
+        &self,
+        calendar: &Url,
+        window: SyncWindow,
+    ) -> Result<Vec<CalendarEvent>, Error> {
+        let (start, end) = time_range(window);
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><d:getetag/>{}</d:prop>
+  <c:filter>
+    <c:comp-filter name="VCALENDAR">
+      <c:comp-filter name="VEVENT"><c:time-range start="{}" end="{}"/></c:comp-filter>
+    </c:comp-filter>
+  </c:filter>
+</c:calendar-query>"#,
+            calendar_data_prop(&start, &end),
+            start,
+            end
+        );
+        let multistatus = self.send("REPORT", calendar, "1", body).await?;
+        Ok(self.events_of(multistatus.responses))
+    }
+
+    /**
+     * calendar-multiget で指定したリソースのイベントを取得する
+     */
+    async fn multiget_events(
+        &self,
+        calendar: &Url,
+        hrefs: &[String],
+        window: SyncWindow,
+    ) -> Result<Vec<CalendarEvent>, Error> {
+        if hrefs.is_empty() {
+            return Ok(vec![]);
+        }
+        let (start, end) = time_range(window);
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-multiget xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><d:getetag/>{}</d:prop>
+  {}
+</c:calendar-multiget>"#,
+            calendar_data_prop(&start, &end),
+            hrefs
+                .iter()
+                .map(|href| format!("<d:href>{}</d:href>", escape_xml(href)))
+                .collect::<Vec<_>>()
+                .join("\n  ")
+        );
+        let multistatus = self.send("REPORT", calendar, "1", body).await?;
+        Ok(self.events_of(multistatus.responses))
+    }
+
+    /**
+     * sync-collection で sync_token 以降に変更・削除されたリソースを取得する
+     */
+    async fn sync_collection(
+        &self,
+        calendar: &Url,
+        sync_token: &str,
+    ) -> Result<MultiStatus, Error> {
+        let body = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<d:sync-collection xmlns:d="DAV:">
+  <d:sync-token>{}</d:sync-token>
+  <d:sync-level>1</d:sync-level>
+  <d:prop><d:getetag/></d:prop>
+</d:sync-collection>"#,
+            escape_xml(sync_token)
+        );
+        self.send("REPORT", calendar, "1", body).await
+    }
+
+    /// calendar-data の VEVENT をイベントにする。展開されたインスタンスは RECURRENCE-ID ごとに別のイベントにする
+    fn events_of(&self, responses: Vec<DavResponse>) -> Vec<CalendarEvent> {
+        let self_email = Some(self.username.as_str()).filter(|username| username.contains('@'));
+        let mut events = vec![];
+        for response in responses {
+            let Some(calendar_data) = response.calendar_data else {
+                continue;
+            };
+            let calendars = match ical::parse(&calendar_data) {
+                Ok(calendars) => calendars,
+                Err(e) => {
+                    println!("Skip invalid calendar data {}: {}", response.href, e);
+                    continue;
+                }
+            };
+            let resource_id = event_id_of(&response.href);
+            let vevents = calendars
+                .iter()
+                .flat_map(|calendar| calendar.components_named("VEVENT"));
+            for vevent in vevents {
+                let recurrence_id = vevent.property("RECURRENCE-ID").and_then(|recurrence_id| {
+                    ical::parse_date_time(recurrence_id, self.default_time_zone)
+                });
+                let (id, recurring_event_id) = match recurrence_id {
+                    Some((recurrence_id, _)) => (
+                        format!("{}#{}", resource_id, recurrence_id.format("%Y%m%dT%H%M%SZ")),
+                        Some(resource_id.clone()),
+                    ),
+                    None => (resource_id.clone(), None),
+                };
+                match ical::to_calendar_event(
+                    vevent,
+                    id,
+                    recurring_event_id,
+                    self.default_time_zone,
+                    self_email,
+                ) {
+                    Some(event) => events.push(event),
+                    None => println!("Skip event in {} without valid start", response.href),
+                }
+            }
+        }
+        events
+    }
+}
+
+/// 差分同期のトークン。カレンダーの URL ごとの sync-token
+type SyncTokens = BTreeMap<String, String>;
+
+#[async_trait]
+impl CalendarProvider for CalDavProvider {
+    fn name(&self) -> &'static str {
+        CALDAV_PROVIDER_NAME
+    }
+
+    fn calendar_id(&self) -> String {
+        format!("{}{}", EVENT_ID_PREFIX, self.url)
+    }
+
+    async fn list_events(&self, window: SyncWindow) -> Result<EventBatch, provider::Error> {
+        let calendars = self.discover_calendars().await?;
+        let mut events = vec![];
+        let mut sync_tokens = SyncTokens::new();
+        for calendar in &calendars {
+            // 取得中の変更は次の差分同期で拾えるよう、先に sync-token を取得する
+            if let Some(sync_token) = self.sync_token_of(calendar).await? {
+                sync_tokens.insert(calendar.to_string(), sync_token);
+            }
+            events.append(&mut self.query_events(calendar, window).await?);
+        }
+
+        Ok(EventBatch {
+            events,
+            deleted_ids: vec![],
+            // sync-collection に対応していないカレンダーがあれば、次回も全件同期する
+            next_sync_token: (sync_tokens.len() == calendars.len())
+                .then(|| serde_json::to_string(&sync_tokens).ok())
+                .flatten(),
+        })
+    }
+
+    async fn list_changes(
+        &self,
+        sync_token: &str,
+        window: SyncWindow,
+    ) -> Result<EventBatch, provider::Error> {
+        let sync_tokens: SyncTokens =
+            serde_json::from_str(sync_token).map_err(|_| provider::Error::SyncTokenExpired)?;
+        let calendars = self.discover_calendars().await?;
+        // カレンダーが増減した場合は全件同期し直す
+        let calendar_urls: HashSet<String> = calendars.iter().map(Url::to_string).collect();
+        if calendar_urls != sync_tokens.keys().cloned().collect() {
+            return Err(provider::Error::SyncTokenExpired);
+        }
+
+        let mut batch = EventBatch::default();
+        let mut next_sync_tokens = SyncTokens::new();
+        for calendar in &calendars {
+            let multistatus = match self
+                .sync_collection(calendar, &sync_tokens[&calendar.to_string()])
+                .await
+            {
+                Err(Error::InvalidSyncToken) => return Err(provider::Error::SyncTokenExpired),
+                result => result?,
+            };
+            let Some(next_sync_token) = multistatus.sync_token else {
+                return Err(Error::Parse("sync-collection has no sync-token".to_string()).into());
+            };
+            next_sync_tokens.insert(calendar.to_string(), next_sync_token);
+
+            let (deleted, changed): (Vec<_>, Vec<_>) = multistatus
+                .responses
+                .into_iter()
+                // コレクション自身のレスポンスは除く
+                .filter(|response| join(calendar, &response.href).ok().as_ref() != Some(calendar))
+                .partition(|response| response.not_found);
+            let changed_hrefs: Vec<String> =
+                changed.into_iter().map(|response| response.href).collect();
+            let mut events = self
+                .multiget_events(calendar, &changed_hrefs, window)
+                .await?;
+
+            // 削除されたリソースと、変更で無くなったインスタンスのイベントを削除する
+            let kept_ids: HashSet<&str> = events.iter().map(|event| event.id.as_str()).collect();
+            for href in deleted
+                .iter()
+                .map(|response| &response.href)
+                .chain(&changed_hrefs)
+            {
+                let stored_ids = stored_event_ids_of(&event_id_of(href))?;
+                batch.deleted_ids.extend(
+                    stored_ids
+                        .into_iter()
+                        .filter(|id| !kept_ids.contains(id.as_str())),
+                );
+            }
+            batch.events.append(&mut events);
+        }
+
+        batch.next_sync_token = Some(
+            serde_json::to_string(&next_sync_tokens).map_err(|e| Error::Parse(e.to_string()))?,
+        );
+        Ok(batch)
+    }
+}
+
+/// リソースのイベント ID。繰り返し予定のインスタンスは、この後ろに #RECURRENCE-ID が付く
+fn event_id_of(href: &str) -> String {
+    format!("{}{}", EVENT_ID_PREFIX, href)
+}
+
+/// 保存済みの、リソースのイベントとそのインスタンスの ID
+fn stored_event_ids_of(resource_id: &str) -> Result<Vec<String>, provider::Error> {
+    let instance_prefix = format!("{}#", resource_id);
+    let ids = repository::event::find_ids_starting_with(resource_id)
+        .map_err(|e| provider::Error::Repository(e.to_string()))?;
+    Ok(ids
+        .into_iter()
+        .filter(|id| id == resource_id || id.starts_with(&instance_prefix))
+        .collect())
+}
+
+fn join(base_url: &Url, href: &str) -> Result<Url, Error> {
+    base_url.join(href).map_err(|e| Error::Parse(e.to_string()))
+}
+
+fn time_range(window: SyncWindow) -> (String, String) {
+    let (start, end) = window.time_range(chrono::Utc::now());
+    (
+        start.format("%Y%m%dT%H%M%SZ").to_string(),
+        end.format("%Y%m%dT%H%M%SZ").to_string(),
+    )
+}
+
+fn propfind_body(prop: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop>{}</d:prop>
+</d:propfind>"#,
+        prop
+    )
+}
+
+fn calendar_data_prop(start: &str, end: &str) -> String {
+    format!(
+        r#"<c:calendar-data><c:expand start="{}" end="{}"/></c:calendar-data>"#,
+        start, end
+    )
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,209 @@
+use roxmltree::{Document, Node};
+
+const DAV: &str = "DAV:";
+const CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
+
+/**
+ * PROPFIND / REPORT の 207 Multi-Status のうち、このアプリで使う値
+ */
+#[derive(Debug, Default, PartialEq)]
+pub struct MultiStatus {
+    pub responses: Vec<DavResponse>,
+    /// sync-collection の結果に含まれる新しい sync-token
+    pub sync_token: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct DavResponse {
+    pub href: String,
+    /// リソースが存在しない(sync-collection で削除されたもの)
+    pub not_found: bool,
+    pub etag: Option<String>,
+    pub calendar_data: Option<String>,
+    pub current_user_principal: Option<String>,
+    pub calendar_home_set: Option<String>,
+    pub sync_token: Option<String>,
+    pub display_name: Option<String>,
+    /// resourcetype に calendar を含む
+    pub is_calendar: bool,
+    /// supported-calendar-component-set に VEVENT を含む。指定がない場合はすべての種類に対応している
+    pub supports_events: bool,
+}
+
+fn is(node: &Node, namespace: &str, name: &str) -> bool {
+    node.is_element()
+        && node.tag_name().namespace() == Some(namespace)
+        && node.tag_name().name() == name
+}
+
+fn child<'a, 'input>(
+    node: &Node<'a, 'input>,
+    namespace: &str,
+    name: &str,
+) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| is(child, namespace, name))
+}
+
+fn text_of(node: Option<Node>) -> Option<String> {
+    node.and_then(|node| node.text())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+}
+
+/// "HTTP/1.1 404 Not Found" のようなステータス行が成功か
+fn is_success_status(node: Option<Node>) -> bool {
+    match text_of(node) {
+        Some(status) => status
+            .split_whitespace()
+            .nth(1)
+            .is_some_and(|code| code.starts_with('2')),
+        None => true,
+    }
+}
+
+/**
+ * Multi-Status の XML を読む。値は 2xx の propstat のものだけを使う
+ *
+ * ```
+ * use calendar_notice::caldav::multistatus::parse;
+ *
+ * let xml = r#"<d:multistatus xmlns:d="DAV:">
+ *   <d:response>
+ *     <d:href>/dav/calendars/me/work/a.ics</d:href>
+ *     <d:propstat><d:prop><d:getetag>"1"</d:getetag></d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat>
+ *   </d:response>
+ *   <d:response>
+ *     <d:href>/dav/calendars/me/work/b.ics</d:href>
+ *     <d:status>HTTP/1.1 404 Not Found</d:status>
+ *   </d:response>
+ *   <d:sync-token>http://example.com/sync/2</d:sync-token>
+ * </d:multistatus>"#;
+ * let multistatus = parse(xml).unwrap();
+ * assert_eq!(multistatus.responses[0].etag.as_deref(), Some("\"1\""));
+ * assert!(!multistatus.responses[0].not_found);
+ * assert!(multistatus.responses[1].not_found);
+ * assert_eq!(multistatus.sync_token.as_deref(), Some("http://example.com/sync/2"));
+ * ```
+ */
+pub fn parse(xml: &str) -> Result<MultiStatus, String> {
+    let document = Document::parse(xml).map_err(|e| e.to_string())?;
+    let root = document.root_element();
+    if !is(&root, DAV, "multistatus") {
+        return Err(format!(
+            "Unexpected root element: {}",
+            root.tag_name().name()
+        ));
+    }
+
+    let responses = root
+        .children()
+        .filter(|node| is(node, DAV, "response"))
+        .filter_map(|response| {
+            let href = text_of(child(&response, DAV, "href"))?;
+            let mut dav_response = DavResponse {
+                href,
+                not_found: !is_success_status(child(&response, DAV, "status")),
+                supports_events: true,
+                ..Default::default()
+            };
+            let props = response
+                .children()
+                .filter(|node| is(node, DAV, "propstat"))
+                .filter(|propstat| is_success_status(child(propstat, DAV, "status")))
+                .filter_map(|propstat| child(&propstat, DAV, "prop"));
+            for prop in props {
+                read_prop(&prop, &mut dav_response);
+            }
+            Some(dav_response)
+        })
+        .collect();
+
+    Ok(MultiStatus {
+        responses,
+        sync_token: text_of(child(&root, DAV, "sync-token")),
+    })
+}
+
+fn read_prop(prop: &Node, dav_response: &mut DavResponse) {
+    if let Some(etag) = text_of(child(prop, DAV, "getetag")) {
+        dav_response.etag = Some(etag);
+    }
+    if let Some(calendar_data) = child(prop, CALDAV, "calendar-data").and_then(|node| node.text()) {
+        dav_response.calendar_data = Some(calendar_data.to_string());
+    }
+    if let Some(principal) = child(prop, DAV, "current-user-principal") {
+        dav_response.current_user_principal = text_of(child(&principal, DAV, "href"));
+    }
+    if let Some(home) = child(prop, CALDAV, "calendar-home-set") {
+        dav_response.calendar_home_set = text_of(child(&home, DAV, "href"));
+    }
+    if let Some(sync_token) = text_of(child(prop, DAV, "sync-token")) {
+        dav_response.sync_token = Some(sync_token);
+    }
+    if let Some(display_name) = text_of(child(prop, DAV, "displayname")) {
+        dav_response.display_name = Some(display_name);
+    }
+    if let Some(resource_type) = child(prop, DAV, "resourcetype") {
+        dav_response.is_calendar = child(&resource_type, CALDAV, "calendar").is_some();
+    }
+    if let Some(component_set) = child(prop, CALDAV, "supported-calendar-component-set") {
+        dav_response.supports_events = component_set
+            .children()
+            .filter(|node| is(node, CALDAV, "comp"))
+            .any(|comp| comp.attribute("name") == Some("VEVENT"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_calendars() {
+        let xml = r#"<?xml version="1.0"?>
+<multistatus xmlns="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <response>
+    <href>/me/</href>
+    <propstat><prop><resourcetype><collection/></resourcetype></prop><status>HTTP/1.1 200 OK</status></propstat>
+  </response>
+  <response>
+    <href>/me/work/</href>
+    <propstat>
+      <prop>
+        <resourcetype><collection/><C:calendar/></resourcetype>
+        <displayname>Work</displayname>
+        <C:supported-calendar-component-set><C:comp name="VEVENT"/></C:supported-calendar-component-set>
+      </prop>
+      <status>HTTP/1.1 200 OK</status>
+    </propstat>
+    <propstat><prop><getetag/></prop><status>HTTP/1.1 404 Not Found</status></propstat>
+  </response>
+  <response>
+    <href>/me/tasks/</href>
+    <propstat>
+      <prop>
+        <resourcetype><collection/><C:calendar/></resourcetype>
+        <C:supported-calendar-component-set><C:comp name="VTODO"/></C:supported-calendar-component-set>
+      </prop>
+      <status>HTTP/1.1 200 OK</status>
+    </propstat>
+  </response>
+</multistatus>"#;
+        let calendars: Vec<(String, bool, bool)> = parse(xml)
+            .unwrap()
+            .responses
+            .into_iter()
+            .map(|r| (r.href, r.is_calendar, r.supports_events))
+            .collect();
+        assert_eq!(
+            calendars,
+            vec![
+                ("/me/".to_string(), false, true),
+                ("/me/work/".to_string(), true, true),
+                ("/me/tasks/".to_string(), true, false),
+            ]
+        );
+
+        assert!(parse("<html/>").is_err());
+    }
+}
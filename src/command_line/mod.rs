@@ -1,3 +1,337 @@
+use clap::{Parser, Subcommand};
+
+use crate::{
+    env::Env,
+    export::{self, ExportFormat},
+    google_calendar::{create::NewEvent, rsvp::ResponseStatus},
+    notification::{apply_notification_settings, NotificationSettings},
+    provider::{self, SyncWindow},
+    repository::{self, models::EventFindMany},
+    sync::{request_and_wait, spawn_sync_loop, SyncCommand, SyncStatus},
+    timezone::{self, DisplayTimezone},
+};
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// 設定値とは別の期間を指定して、今すぐ同期する
+    Sync {
+        /// 同期対象とする未来の期間(日)。未指定の場合は SYNC_LOOK_AHEAD_DAYS
+        #[arg(long)]
+        days: Option<i64>,
+        /// 同期対象とする過去の期間(秒)。未指定の場合は SYNC_LOOK_BACK_SEC
+        #[arg(long)]
+        look_back_sec: Option<i64>,
+    },
+    /// イベントの通知設定を変更する
+    Notification {
+        /// 対象のイベント ID
+        event_id: String,
+        /// 通知しない
+        #[arg(long, conflicts_with = "unmute")]
+        mute: bool,
+        /// 通知する
+        #[arg(long)]
+        unmute: bool,
+        /// 開始の何分前に通知するか
+        #[arg(long)]
+        lead_time_min: Option<i32>,
+        /// 繰り返し予定の場合、シリーズ全体と今後追加されるインスタンスに適用する
+        #[arg(long)]
+        series: bool,
+    },
+    /// 予定への出欠を回答する。辞退した場合は通知しない
+    Rsvp {
+        /// 対象のイベント ID
+        event_id: String,
+        /// accept / decline / tentative
+        status: ResponseStatus,
+    },
+    /// 「明日 12:30 にランチ」のような文章から予定を作成する
+    QuickAdd { text: String },
+    /// タイトルと開始日時を指定して予定を作成する
+    Create {
+        title: String,
+        /// 開始日時。"2026-10-20 12:30"(DISPLAY_TIMEZONE の時刻) または RFC3339
+        #[arg(long)]
+        start: String,
+        /// 予定の長さ(分)
+        #[arg(long, default_value_t = 30)]
+        duration_min: i64,
+        /// Google Meet のリンクを発行する
+        #[arg(long)]
+        meet: bool,
+    },
+    /// 保存済みの予定を、通知設定と参加用のリンクを付けて書き出す
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Ics)]
+        format: ExportFormat,
+        /// この日時以降に始まる予定。"2026-10-20"(表示タイムゾーンの 0 時) または RFC3339
+        #[arg(long)]
+        from: Option<String>,
+        /// この日時までに終わる予定。"2026-10-27"(表示タイムゾーンの 0 時) または RFC3339
+        #[arg(long)]
+        to: Option<String>,
+        /// 書き出すファイル。未指定の場合は標準出力
+        #[arg(long, short)]
+        output: Option<std::path::PathBuf>,
+    },
+}
+
+pub async fn run(command: Command) {
+    match command {
+        Command::Sync {
+            days,
+            look_back_sec,
+        } => handle_command_sync(days, look_back_sec).await,
+        Command::Notification {
+            event_id,
+            mute,
+            unmute,
+            lead_time_min,
+            series,
+        } => handle_command_notification(event_id, mute, unmute, lead_time_min, series),
+        Command::Rsvp { event_id, status } => handle_command_rsvp(event_id, status).await,
+        Command::QuickAdd { text } => handle_command_quick_add(text).await,
+        Command::Create {
+            title,
+            start,
+            duration_min,
+            meet,
+        } => handle_command_create(title, start, duration_min, meet).await,
+        Command::Export {
+            format,
+            from,
+            to,
+            output,
+        } => handle_command_export(format, from, to, output),
+    }
+}
+
+fn handle_command_export(
+    format: ExportFormat,
+    from: Option<String>,
+    to: Option<String>,
+    output: Option<std::path::PathBuf>,
+) {
+    let display_timezone = DisplayTimezone::from_env(&Env::new());
+    let parse = |value: Option<String>| {
+        value
+            .map(|value| export::parse_bound(&value, display_timezone))
+            .transpose()
+    };
+    let (from, to) = match (parse(from), parse(to)) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    if let (Some(from), Some(to)) = (from, to) {
+        if from >= to {
+            eprintln!("--to must be after --from");
+            return;
+        }
+    }
+
+    let events = match export::find_export_events(from, to) {
+        Ok(events) => events,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    let text = match format {
+        ExportFormat::Ics => export::to_ics(&events, chrono::Utc::now(), display_timezone),
+    };
+    match output {
+        Some(path) => match std::fs::write(&path, text) {
+            Ok(_) => eprintln!(
+                "Success to export {} events to {}!",
+                events.len(),
+                path.display()
+            ),
+            Err(e) => eprintln!("Failed to write {}: {}", path.display(), e),
+        },
+        None => print!("{}", text),
+    }
+}
+
+async fn handle_command_sync(days: Option<i64>, look_back_sec: Option<i64>) {
+    let window = sync_window_with_override(&Env::new(), days, look_back_sec);
+    if let Err(e) = window {
+        eprintln!("{}", e);
+        return;
+    }
+
+    let sync_handle = spawn_sync_loop();
+    match request_and_wait(&sync_handle, SyncCommand::SyncWindow(window.unwrap())).await {
+        SyncStatus::Failed(reason) => {
+            eprintln!("Failed to sync events in handle_command_sync: {}", reason)
+        }
+        _ => println!("Success to sync events!"),
+    }
+}
+
+async fn handle_command_rsvp(event_id: String, status: ResponseStatus) {
+    match provider::respond(&event_id, status).await {
+        Ok(_) => println!("Success to respond {}!", status),
+        Err(provider::Error::InsufficientScope) => {
+            eprintln!("Permission to edit events is required. Start the app without a subcommand to authorize again")
+        }
+        Err(e) => eprintln!("Failed to respond to event {}: {:?}", event_id, e),
+    }
+}
+
+async fn handle_command_quick_add(text: String) {
+    match provider::quick_add(&text).await {
+        Ok(event) => println!("Success to create event {}!", event.id),
+        Err(e) => eprint_create_error(e),
+    }
+}
+
+async fn handle_command_create(title: String, start: String, duration_min: i64, meet: bool) {
+    let start = match parse_start(&start, DisplayTimezone::from_env(&Env::new())) {
+        Ok(start) => start,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+    if duration_min <= 0 {
+        eprintln!("--duration-min must be greater than 0");
+        return;
+    }
+
+    let new_event = NewEvent {
+        summary: title,
+        start,
+        duration: chrono::Duration::minutes(duration_min),
+        with_meet: meet,
+    };
+    match provider::create(&new_event).await {
+        Ok(event) => match event.hangout_link {
+            Some(link) => println!("Success to create event {}! Meet: {}", event.id, link),
+            None => println!("Success to create event {}!", event.id),
+        },
+        Err(e) => eprint_create_error(e),
+    }
+}
+
+fn eprint_create_error(e: provider::Error) {
+    match e {
+        provider::Error::InsufficientScope => {
+            eprintln!("Permission to edit events is required. Start the app without a subcommand to authorize again")
+        }
+        e => eprintln!("Failed to create event: {:?}", e),
+    }
+}
+
+/**
+ * 開始日時を RFC3339、または表示タイムゾーンの "%Y-%m-%d %H:%M" として読む
+ */
+fn parse_start(
+    start: &str,
+    display_timezone: DisplayTimezone,
+) -> Result<chrono::DateTime<chrono::FixedOffset>, String> {
+    if let Ok(start) = chrono::DateTime::parse_from_rfc3339(start) {
+        return Ok(start);
+    }
+    chrono::NaiveDateTime::parse_from_str(start, "%Y-%m-%d %H:%M")
+        .ok()
+        .map(|naive| {
+            display_timezone.convert(match display_timezone {
+                DisplayTimezone::System => timezone::local_to_utc(naive, &chrono::Local),
+                DisplayTimezone::Named(tz) => timezone::local_to_utc(naive, &tz),
+            })
+        })
+        .ok_or(format!(
+            "--start must be \"YYYY-MM-DD HH:MM\" or RFC3339: {}",
+            start
+        ))
+}
+
+fn handle_command_notification(
+    event_id: String,
+    mute: bool,
+    unmute: bool,
+    lead_time_min: Option<i32>,
+    series: bool,
+) {
+    let settings = match notification_settings_from_args(mute, unmute, lead_time_min) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("{}", e);
+            return;
+        }
+    };
+
+    let event = repository::event::find_many(EventFindMany {
+        ids_in: Some(vec![event_id.clone()]),
+        ..Default::default()
+    })
+    .map(|mut events| events.pop());
+    match event {
+        Ok(Some(event)) => match apply_notification_settings(&event, settings, series) {
+            Ok(_) => println!("Success to update notification settings!"),
+            Err(e) => eprintln!("{}", e),
+        },
+        Ok(None) => eprintln!("Event {} is not found", event_id),
+        Err(e) => eprintln!("Failed to get event: {:?}", e),
+    }
+}
+
+fn notification_settings_from_args(
+    mute: bool,
+    unmute: bool,
+    lead_time_min: Option<i32>,
+) -> Result<NotificationSettings, String> {
+    if let Some(lead_time_min) = lead_time_min {
+        if lead_time_min < 0 {
+            return Err("--lead-time-min must not be negative".to_string());
+        }
+    }
+    let settings = NotificationSettings {
+        enabled: match (mute, unmute) {
+            (true, _) => Some(false),
+            (_, true) => Some(true),
+            _ => None,
+        },
+        sec_from_start: lead_time_min.map(|min| min * 60),
+    };
+    if settings == NotificationSettings::default() {
+        return Err("Specify --mute, --unmute or --lead-time-min".to_string());
+    }
+    Ok(settings)
+}
+
+fn sync_window_with_override(
+    env: &Env,
+    days: Option<i64>,
+    look_back_sec: Option<i64>,
+) -> Result<SyncWindow, String> {
+    let mut window = SyncWindow::from_env(env);
+    if let Some(days) = days {
+        if days <= 0 {
+            return Err("--days must be greater than 0".to_string());
+        }
+        window.look_ahead = chrono::Duration::days(days);
+    }
+    if let Some(look_back_sec) = look_back_sec {
+        if look_back_sec < 0 {
+            return Err("--look-back-sec must not be negative".to_string());
+        }
+        window.look_back = chrono::Duration::seconds(look_back_sec);
+    }
+    Ok(window)
+}
+
 // use chrono::Timelike;
 // use clap::Parser;
 // use std::io::{self, BufRead};
@@ -269,3 +603,80 @@
 //         Err(_) => println!("数字を入力してください"),
 //     }
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_window_with_override() {
+        let env = Env {
+            sync_look_back_sec: 600,
+            sync_look_ahead_days: 7,
+            ..Default::default()
+        };
+
+        let window = sync_window_with_override(&env, None, None).unwrap();
+        assert_eq!(window.look_ahead, chrono::Duration::days(7));
+        assert_eq!(window.look_back, chrono::Duration::seconds(600));
+
+        let window = sync_window_with_override(&env, Some(30), Some(0)).unwrap();
+        assert_eq!(window.look_ahead, chrono::Duration::days(30));
+        assert_eq!(window.look_back, chrono::Duration::seconds(0));
+
+        assert!(sync_window_with_override(&env, Some(0), None).is_err());
+        assert!(sync_window_with_override(&env, None, Some(-1)).is_err());
+    }
+
+    #[test]
+    fn test_parse_start() {
+        let tokyo = DisplayTimezone::Named(chrono_tz::Asia::Tokyo);
+        let new_york = DisplayTimezone::Named(chrono_tz::America::New_York);
+        assert_eq!(
+            parse_start("2026-10-20T12:30:00+09:00", new_york)
+                .unwrap()
+                .to_rfc3339(),
+            "2026-10-20T12:30:00+09:00"
+        );
+        // 日時だけの場合は DISPLAY_TIMEZONE の時刻として読む
+        assert_eq!(
+            parse_start("2026-10-20 12:30", tokyo).unwrap().to_rfc3339(),
+            "2026-10-20T12:30:00+09:00"
+        );
+        assert_eq!(
+            parse_start("2026-10-20 12:30", new_york)
+                .unwrap()
+                .to_rfc3339(),
+            "2026-10-20T12:30:00-04:00"
+        );
+        // 夏時間の開始で存在しない時刻は切り替え後の時刻になる
+        assert_eq!(
+            parse_start("2026-03-08 02:30", new_york)
+                .unwrap()
+                .to_rfc3339(),
+            "2026-03-08T03:30:00-04:00"
+        );
+        assert!(parse_start("tomorrow", tokyo).is_err());
+    }
+
+    #[test]
+    fn test_notification_settings_from_args() {
+        assert_eq!(
+            notification_settings_from_args(true, false, None),
+            Ok(NotificationSettings {
+                enabled: Some(false),
+                sec_from_start: None,
+            })
+        );
+        assert_eq!(
+            notification_settings_from_args(false, true, Some(5)),
+            Ok(NotificationSettings {
+                enabled: Some(true),
+                sec_from_start: Some(300),
+            })
+        );
+
+        assert!(notification_settings_from_args(false, false, None).is_err());
+        assert!(notification_settings_from_args(false, false, Some(-1)).is_err());
+    }
+}
@@ -1,12 +1,69 @@
 #![allow(unused_variables, dead_code)]
 use dotenvy::dotenv;
-use std::env;
+use std::{env, str::FromStr};
+
+use crate::{
+    launcher::LaunchPreferences, location::TravelTimeRules, meeting_link::MeetingLinkPatterns,
+};
+
+const DEFAULT_SYNC_INTERVAL_SEC: u64 = 60 * 10;
+const DEFAULT_SYNC_LOOK_BACK_SEC: i64 = 60 * 10;
+const DEFAULT_SYNC_LOOK_AHEAD_DAYS: i64 = 7;
+const DEFAULT_NOTIFICATION_PERIOD_DAYS: i64 = 7;
+const DEFAULT_GOOGLE_API_BASE_URL: &str = "https://www.googleapis.com";
+const DEFAULT_GOOGLE_OAUTH_BASE_URL: &str = "https://accounts.google.com";
+const DEFAULT_OAUTH_SECRET_PATH: &str = "oauth_secret.json";
+const DEFAULT_MICROSOFT_GRAPH_BASE_URL: &str = "https://graph.microsoft.com";
+const DEFAULT_MICROSOFT_LOGIN_BASE_URL: &str = "https://login.microsoftonline.com";
+const DEFAULT_MICROSOFT_TENANT: &str = "common";
 
 #[derive(Default)]
 pub struct Env {
     pub database_url: String,
     pub port: u16,
     pub base_url: String,
+    /// カレンダー同期の間隔(秒)
+    pub sync_interval_sec: u64,
+    /// 同期対象とする過去の期間(秒)
+    pub sync_look_back_sec: i64,
+    /// 同期対象とする未来の期間(日)
+    pub sync_look_ahead_days: i64,
+    /// 通知対象とする未来の期間(日)
+    pub notification_period_days: i64,
+    /// Google Calendar API のベース URL。テスト時はモックサーバーを指定する
+    pub google_api_base_url: String,
+    /// Google OAuth 認可画面のベース URL
+    pub google_oauth_base_url: String,
+    pub oauth_secret_path: String,
+    /// Google からの変更通知を受け取る公開 URL(https)。未設定の場合はポーリングのみで同期する
+    pub webhook_address: Option<String>,
+    /// 予定を表示するタイムゾーン(例: Asia/Tokyo)。未設定の場合はシステムのタイムゾーン
+    pub display_timezone: Option<chrono_tz::Tz>,
+    /// 対面の予定の場所ごとに、通知を早める移動時間
+    pub travel_time_rules: TravelTimeRules,
+    /// CalDAV サーバーの URL。設定するとそのユーザーのカレンダーも同期する
+    pub caldav_url: Option<String>,
+    pub caldav_username: Option<String>,
+    /// パスワード、またはアプリパスワード
+    pub caldav_password: Option<String>,
+    /// 読み取り専用で取り込む .ics ファイルのパス、または iCal フィードの URL
+    pub ics_sources: Vec<String>,
+    /// Microsoft Entra ID に登録したアプリのクライアント ID。設定すると Outlook の予定も同期する
+    pub microsoft_client_id: Option<String>,
+    /// common / organizations / テナント ID
+    pub microsoft_tenant: String,
+    /// Microsoft Graph API のベース URL。テスト時はモックサーバーを指定する
+    pub microsoft_graph_base_url: String,
+    /// Microsoft ID プラットフォームのベース URL
+    pub microsoft_login_base_url: String,
+    /// 組み込みのものより優先する、会議のリンクの正規表現
+    pub meeting_link_patterns: MeetingLinkPatterns,
+    /// 会議のリンクの名前ごとの開き方(アプリ・ブラウザ・コマンド)
+    pub meeting_launch: LaunchPreferences,
+    /// ブラウザで開くコマンド。{url} と {profile} を置き換える。未設定の場合は OS の既定のブラウザ
+    pub browser_command: Option<String>,
+    /// 既定のブラウザのプロファイル
+    pub browser_profile: Option<String>,
 }
 
 impl Env {
@@ -20,6 +77,170 @@ impl Env {
                 .parse()
                 .expect("PORT must be a number"),
             base_url: env::var("BASE_URL").expect("BASE_URL must be set"),
+            sync_interval_sec: var_or("SYNC_INTERVAL_SEC", DEFAULT_SYNC_INTERVAL_SEC),
+            sync_look_back_sec: var_or("SYNC_LOOK_BACK_SEC", DEFAULT_SYNC_LOOK_BACK_SEC),
+            sync_look_ahead_days: var_or("SYNC_LOOK_AHEAD_DAYS", DEFAULT_SYNC_LOOK_AHEAD_DAYS),
+            notification_period_days: var_or(
+                "NOTIFICATION_PERIOD_DAYS",
+                DEFAULT_NOTIFICATION_PERIOD_DAYS,
+            ),
+            google_api_base_url: var_or(
+                "GOOGLE_API_BASE_URL",
+                DEFAULT_GOOGLE_API_BASE_URL.to_string(),
+            ),
+            google_oauth_base_url: var_or(
+                "GOOGLE_OAUTH_BASE_URL",
+                DEFAULT_GOOGLE_OAUTH_BASE_URL.to_string(),
+            ),
+            oauth_secret_path: var_or("OAUTH_SECRET_PATH", DEFAULT_OAUTH_SECRET_PATH.to_string()),
+            webhook_address: env::var("WEBHOOK_ADDRESS").ok(),
+            display_timezone: env::var("DISPLAY_TIMEZONE").ok().map(|value| {
+                value
+                    .parse()
+                    .unwrap_or_else(|_| panic!("DISPLAY_TIMEZONE is invalid: {}", value))
+            }),
+            travel_time_rules: var_or("TRAVEL_TIME_RULES", TravelTimeRules::default()),
+            caldav_url: env::var("CALDAV_URL").ok(),
+            caldav_username: env::var("CALDAV_USERNAME").ok(),
+            caldav_password: env::var("CALDAV_PASSWORD").ok(),
+            ics_sources: env::var("ICS_SOURCES")
+                .map(|value| {
+                    value
+                        .split(';')
+                        .map(str::trim)
+                        .filter(|source| !source.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            microsoft_client_id: env::var("MICROSOFT_CLIENT_ID").ok(),
+            microsoft_tenant: var_or("MICROSOFT_TENANT", DEFAULT_MICROSOFT_TENANT.to_string()),
+            microsoft_graph_base_url: var_or(
+                "MICROSOFT_GRAPH_BASE_URL",
+                DEFAULT_MICROSOFT_GRAPH_BASE_URL.to_string(),
+            ),
+            microsoft_login_base_url: var_or(
+                "MICROSOFT_LOGIN_BASE_URL",
+                DEFAULT_MICROSOFT_LOGIN_BASE_URL.to_string(),
+            ),
+            meeting_link_patterns: var_or("MEETING_LINK_PATTERNS", MeetingLinkPatterns::default()),
+            meeting_launch: var_or("MEETING_LAUNCH", LaunchPreferences::default()),
+            browser_command: env::var("BROWSER_COMMAND").ok(),
+            browser_profile: env::var("BROWSER_PROFILE").ok(),
+        }
+    }
+
+    /**
+     * 設定値同士の整合性を検証する。
+     * 同期していない期間のイベントを通知対象にしないよう、通知期間は同期期間以下である必要がある
+     */
+    pub fn validate(&self) -> Result<(), String> {
+        if self.sync_interval_sec == 0 {
+            return Err("SYNC_INTERVAL_SEC must be greater than 0".to_string());
+        }
+        if self.sync_look_back_sec < 0 {
+            return Err("SYNC_LOOK_BACK_SEC must not be negative".to_string());
+        }
+        if self.sync_look_ahead_days <= 0 {
+            return Err("SYNC_LOOK_AHEAD_DAYS must be greater than 0".to_string());
+        }
+        if self.notification_period_days <= 0 {
+            return Err("NOTIFICATION_PERIOD_DAYS must be greater than 0".to_string());
+        }
+        if self.notification_period_days > self.sync_look_ahead_days {
+            return Err(format!(
+                "NOTIFICATION_PERIOD_DAYS ({}) must not exceed SYNC_LOOK_AHEAD_DAYS ({})",
+                self.notification_period_days, self.sync_look_ahead_days
+            ));
+        }
+        if self.sync_interval_sec as i64 >= self.sync_look_ahead_days * 60 * 60 * 24 {
+            return Err(format!(
+                "SYNC_INTERVAL_SEC ({}) must be shorter than SYNC_LOOK_AHEAD_DAYS ({} days)",
+                self.sync_interval_sec, self.sync_look_ahead_days
+            ));
+        }
+        if self.caldav_url.is_some()
+            && (self.caldav_username.is_none() || self.caldav_password.is_none())
+        {
+            return Err(
+                "CALDAV_USERNAME and CALDAV_PASSWORD must be set with CALDAV_URL".to_string(),
+            );
+        }
+        Ok(())
+    }
+}
+
+/**
+ * 環境変数が未設定の場合はデフォルト値を使う。設定されているが不正な値の場合は panic
+ */
+fn var_or<T: FromStr>(key: &str, default: T) -> T {
+    match env::var(key) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("{} is invalid: {}", key, value)),
+        Err(_) => default,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_env() -> Env {
+        Env {
+            sync_interval_sec: DEFAULT_SYNC_INTERVAL_SEC,
+            sync_look_back_sec: DEFAULT_SYNC_LOOK_BACK_SEC,
+            sync_look_ahead_days: DEFAULT_SYNC_LOOK_AHEAD_DAYS,
+            notification_period_days: DEFAULT_NOTIFICATION_PERIOD_DAYS,
+            ..Default::default()
         }
     }
+
+    #[test]
+    fn validate_default_values() {
+        assert!(valid_env().validate().is_ok());
+    }
+
+    #[test]
+    fn validate_notification_period_longer_than_sync_window() {
+        let env = Env {
+            sync_look_ahead_days: 3,
+            notification_period_days: 7,
+            ..valid_env()
+        };
+        assert!(env.validate().is_err());
+    }
+
+    #[test]
+    fn validate_interval() {
+        let env = Env {
+            sync_interval_sec: 0,
+            ..valid_env()
+        };
+        assert!(env.validate().is_err());
+
+        let env = Env {
+            sync_interval_sec: 60 * 60 * 24,
+            sync_look_ahead_days: 1,
+            notification_period_days: 1,
+            ..valid_env()
+        };
+        assert!(env.validate().is_err());
+    }
+
+    #[test]
+    fn validate_caldav_credentials() {
+        let env = Env {
+            caldav_url: Some("https://caldav.example.com/dav/".to_string()),
+            ..valid_env()
+        };
+        assert!(env.validate().is_err());
+
+        let env = Env {
+            caldav_username: Some("me@example.com".to_string()),
+            caldav_password: Some("app-password".to_string()),
+            ..env
+        };
+        assert!(env.validate().is_ok());
+    }
 }
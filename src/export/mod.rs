@@ -0,0 +1,432 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use warp::{http::StatusCode, Filter};
+
+use crate::{
+    ical::{Component, Property},
+    repository::{
+        self,
+        models::{ConferenceEntryPoint, Event, EventFindMany, EventMeetingLink},
+    },
+    timezone::{self, DisplayTimezone},
+};
+
+/// 保存済みの予定を配信するパス
+pub const EXPORT_PATH: &str = "export.ics";
+const PRODUCT_ID: &str = "-//calendar-notice//calendar-notice//JA";
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// iCalendar(RFC 5545)
+    Ics,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Repository(String),
+}
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Repository(e) => write!(f, "Failed to get events: {}", e),
+        }
+    }
+}
+
+/// 書き出す予定と、その参加方法
+pub struct ExportEvent {
+    pub event: Event,
+    pub entry_points: Vec<ConferenceEntryPoint>,
+    pub meeting_links: Vec<EventMeetingLink>,
+}
+
+/**
+ * 期間の端を RFC3339、または表示タイムゾーンの日付("%Y-%m-%d" のその日の 0 時)として読む
+ */
+pub fn parse_bound(
+    value: &str,
+    display_timezone: DisplayTimezone,
+) -> Result<DateTime<Utc>, String> {
+    if let Some(utc) = timezone::parse_rfc3339(value) {
+        return Ok(utc);
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Date must be \"YYYY-MM-DD\" or RFC3339: {}", value))?;
+    let naive = date.and_time(NaiveTime::MIN);
+    Ok(match display_timezone {
+        DisplayTimezone::System => timezone::local_to_utc(naive, &chrono::Local),
+        DisplayTimezone::Named(tz) => timezone::local_to_utc(naive, &tz),
+    })
+}
+
+/**
+ * 期間内に始まり、期間内に終わる保存済みの予定。from / to が None の場合はその側を制限しない
+ */
+pub fn find_export_events(
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> Result<Vec<ExportEvent>, Error> {
+    let events = repository::event::find_many(EventFindMany {
+        from,
+        to,
+        ..Default::default()
+    })
+    .map_err(|e| Error::Repository(e.to_string()))?;
+    events
+        .into_iter()
+        .map(|event| {
+            let entry_points = repository::conference_entry_point::find_by_event_id(&event.id)
+                .map_err(|e| Error::Repository(e.to_string()))?;
+            let meeting_links = repository::meeting_link::find_by_event_id(&event.id)
+                .map_err(|e| Error::Repository(e.to_string()))?;
+            Ok(ExportEvent {
+                event,
+                entry_points,
+                meeting_links,
+            })
+        })
+        .collect()
+}
+
+/**
+ * 予定を VCALENDAR にする。通知設定は VALARM、参加用のリンクは URL と説明文の末尾に書き出す
+ */
+pub fn to_ics(
+    events: &[ExportEvent],
+    now: DateTime<Utc>,
+    display_timezone: DisplayTimezone,
+) -> String {
+    Component {
+        name: "VCALENDAR".to_string(),
+        properties: vec![
+            Property::new("VERSION", "2.0"),
+            Property::new("PRODID", PRODUCT_ID),
+            Property::new("CALSCALE", "GREGORIAN"),
+            Property::text_value("X-WR-CALNAME", "calendar-notice"),
+        ],
+        components: events
+            .iter()
+            .map(|export_event| to_vevent(export_event, now, display_timezone))
+            .collect(),
+    }
+    .to_ical_string()
+}
+
+fn ical_date_time(date_time: DateTime<Utc>) -> String {
+    date_time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// 終日の予定はタイムゾーンを変換せず、カレンダー上の日付で書き出す
+fn date_property(
+    name: &str,
+    date_time: DateTime<Utc>,
+    all_day_date: Option<NaiveDate>,
+) -> Property {
+    match all_day_date {
+        Some(date) => {
+            Property::new(name, &date.format("%Y%m%d").to_string()).with_param("VALUE", "DATE")
+        }
+        None => Property::new(name, &ical_date_time(date_time)),
+    }
+}
+
+/**
+ * 参加用のリンク。会議情報のビデオ会議 URL を優先し、なければ Teams / Zoom / Meet、その他の会議のリンクの順
+ */
+fn join_link(export_event: &ExportEvent) -> Option<&str> {
+    let event = &export_event.event;
+    export_event
+        .entry_points
+        .iter()
+        .find(|entry_point| entry_point.entry_point_type == "video")
+        .map(|entry_point| entry_point.uri.as_str())
+        .or(event.teams_link.as_deref())
+        .or(event.zoom_link.as_deref())
+        .or(event.hangout_link.as_deref())
+        .or(export_event
+            .meeting_links
+            .first()
+            .map(|meeting_link| meeting_link.url.as_str()))
+}
+
+fn to_vevent(
+    export_event: &ExportEvent,
+    now: DateTime<Utc>,
+    display_timezone: DisplayTimezone,
+) -> Component {
+    let event = &export_event.event;
+    let summary = event
+        .summary
+        .clone()
+        .unwrap_or("[タイトル未設定]".to_string());
+    let link = join_link(export_event);
+    let all_day_dates = event.all_day_dates(&display_timezone);
+
+    let mut properties = vec![
+        Property::text_value("UID", &event.id),
+        Property::new("DTSTAMP", &ical_date_time(now)),
+        date_property(
+            "DTSTART",
+            event.start_datetime,
+            all_day_dates.map(|(start_date, _)| start_date),
+        ),
+        date_property(
+            "DTEND",
+            event.end_datetime,
+            all_day_dates.map(|(_, end_date)| end_date),
+        ),
+        Property::text_value("SUMMARY", &summary),
+    ];
+    if let Some(status) = event.status.as_deref() {
+        properties.push(Property::new("STATUS", &status.to_uppercase()));
+    }
+    if let Some(location) = event.location.as_deref().filter(|l| !l.is_empty()) {
+        properties.push(Property::text_value("LOCATION", location));
+    }
+    let description = match (event.description.as_deref(), link) {
+        (Some(description), Some(link)) if !description.contains(link) => {
+            Some(format!("{}\n\n参加: {}", description, link))
+        }
+        (Some(description), _) => Some(description.to_string()),
+        (None, Some(link)) => Some(format!("参加: {}", link)),
+        (None, None) => None,
+    };
+    if let Some(description) = description {
+        properties.push(Property::text_value("DESCRIPTION", &description));
+    }
+    if let Some(link) = link {
+        properties.push(Property::new("URL", link));
+    }
+    if let Some(recurring_event_id) = event.recurring_event_id.as_deref() {
+        properties.push(
+            Property::text_value("RELATED-TO", recurring_event_id).with_param("RELTYPE", "PARENT"),
+        );
+    }
+
+    let components = if event.notification_enabled {
+        vec![Component {
+            name: "VALARM".to_string(),
+            properties: vec![
+                Property::new("ACTION", "DISPLAY"),
+                Property::new(
+                    "TRIGGER",
+                    &format!("-PT{}S", event.notification_sec_from_start.max(0)),
+                ),
+                Property::text_value("DESCRIPTION", &summary),
+            ],
+            components: vec![],
+        }]
+    } else {
+        vec![]
+    };
+
+    Component {
+        name: "VEVENT".to_string(),
+        properties,
+        components,
+    }
+}
+
+pub fn routes(
+    display_timezone: DisplayTimezone,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::get()
+        .and(warp::path(EXPORT_PATH))
+        .and(warp::path::end())
+        .and(warp::query::<HashMap<String, String>>())
+        .and(warp::any().map(move || display_timezone))
+        .and_then(handle_export)
+}
+
+/**
+ * export サブコマンドと同じ内容を配信する。期間は from / to のクエリで指定する
+ */
+async fn handle_export(
+    params: HashMap<String, String>,
+    display_timezone: DisplayTimezone,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let bound = |name: &str| {
+        params
+            .get(name)
+            .map(|value| parse_bound(value, display_timezone))
+            .transpose()
+    };
+    let bad_request = |message: String| {
+        Ok(warp::reply::with_status(
+            warp::reply::with_header(message, "Content-Type", "text/plain; charset=utf-8"),
+            StatusCode::BAD_REQUEST,
+        ))
+    };
+    let (from, to) = match (bound("from"), bound("to")) {
+        (Ok(from), Ok(to)) => (from, to),
+        (Err(e), _) | (_, Err(e)) => return bad_request(e),
+    };
+    if let (Some(from), Some(to)) = (from, to) {
+        if from >= to {
+            return bad_request("to must be after from".to_string());
+        }
+    }
+
+    match find_export_events(from, to) {
+        Ok(events) => Ok(warp::reply::with_status(
+            warp::reply::with_header(
+                to_ics(&events, Utc::now(), display_timezone),
+                "Content-Type",
+                "text/calendar; charset=utf-8",
+            ),
+            StatusCode::OK,
+        )),
+        Err(e) => {
+            println!("Failed to export events: {}", e);
+            Ok(warp::reply::with_status(
+                warp::reply::with_header(
+                    e.to_string(),
+                    "Content-Type",
+                    "text/plain; charset=utf-8",
+                ),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ical;
+
+    fn export_event() -> ExportEvent {
+        ExportEvent {
+            event: Event {
+                id: "standup_20261020T010000Z".to_string(),
+                summary: Some("Standup, daily".to_string()),
+                description: Some("Agenda: https://example.com/agenda".to_string()),
+                status: Some("confirmed".to_string()),
+                zoom_link: Some("https://zoom.us/j/95428352872".to_string()),
+                start_datetime: "2026-10-20T01:00:00Z".parse().unwrap(),
+                end_datetime: "2026-10-20T01:15:00Z".parse().unwrap(),
+                notification_enabled: true,
+                notification_sec_from_start: 600,
+                recurring_event_id: Some("standup".to_string()),
+                location: Some("Room A".to_string()),
+                provider: "google".to_string(),
+                ..Default::default()
+            },
+            entry_points: vec![],
+            meeting_links: vec![],
+        }
+    }
+
+    #[test]
+    fn test_to_ics() {
+        let now = "2026-10-19T00:00:00Z".parse().unwrap();
+        let mut holiday = export_event();
+        holiday.event = Event {
+            id: "holiday".to_string(),
+            summary: None,
+            description: None,
+            zoom_link: None,
+            start_datetime: "2026-10-22T15:00:00Z".parse().unwrap(),
+            end_datetime: "2026-10-23T15:00:00Z".parse().unwrap(),
+            all_day: true,
+            start_date: NaiveDate::from_ymd_opt(2026, 10, 23),
+            end_date: NaiveDate::from_ymd_opt(2026, 10, 24),
+            notification_enabled: false,
+            recurring_event_id: None,
+            location: None,
+            ..holiday.event
+        };
+        let text = to_ics(
+            &[export_event(), holiday],
+            now,
+            DisplayTimezone::Named(chrono_tz::America::New_York),
+        );
+
+        let calendars = ical::parse(&text).unwrap();
+        let vevents: Vec<&Component> = calendars[0].components_named("VEVENT").collect();
+        assert_eq!(vevents.len(), 2);
+
+        let standup = vevents[0];
+        assert_eq!(standup.text("SUMMARY").as_deref(), Some("Standup, daily"));
+        assert_eq!(standup.text("DTSTART").as_deref(), Some("20261020T010000Z"));
+        assert_eq!(standup.text("STATUS").as_deref(), Some("CONFIRMED"));
+        assert_eq!(
+            standup.text("URL").as_deref(),
+            Some("https://zoom.us/j/95428352872")
+        );
+        assert_eq!(
+            standup.text("DESCRIPTION").as_deref(),
+            Some("Agenda: https://example.com/agenda\n\n参加: https://zoom.us/j/95428352872")
+        );
+        assert_eq!(
+            standup.property("RELATED-TO").unwrap().param("RELTYPE"),
+            Some("PARENT")
+        );
+        let alarm = standup.components_named("VALARM").next().unwrap();
+        assert_eq!(alarm.text("TRIGGER").as_deref(), Some("-PT600S"));
+        // 取り込み直すと同じ通知設定になる
+        let imported =
+            ical::to_calendar_event(standup, "standup".to_string(), None, None, None).unwrap();
+        assert_eq!(imported.reminder_sec_from_start, Some(600));
+        assert_eq!(imported.start, export_event().event.start_datetime);
+
+        // 終日の予定は表示タイムゾーンによらずカレンダー上の日付。通知しない予定には VALARM を付けない
+        let holiday = vevents[1];
+        let dtstart = holiday.property("DTSTART").unwrap();
+        assert_eq!(dtstart.param("VALUE"), Some("DATE"));
+        assert_eq!(dtstart.value, "20261023");
+        assert_eq!(holiday.property("DTEND").unwrap().value, "20261024");
+        assert_eq!(holiday.text("SUMMARY").as_deref(), Some("[タイトル未設定]"));
+        assert!(holiday.text("URL").is_none());
+        assert_eq!(holiday.components_named("VALARM").count(), 0);
+    }
+
+    #[test]
+    fn test_join_link() {
+        let mut export_event = export_event();
+        export_event.event.zoom_link = None;
+        assert_eq!(join_link(&export_event), None);
+        export_event.meeting_links = vec![EventMeetingLink {
+            provider: "webex".to_string(),
+            url: "https://example.webex.com/meet/alice".to_string(),
+            ..Default::default()
+        }];
+        assert_eq!(
+            join_link(&export_event),
+            Some("https://example.webex.com/meet/alice")
+        );
+
+        let mut export_event = self::export_event();
+        assert_eq!(
+            join_link(&export_event),
+            Some("https://zoom.us/j/95428352872")
+        );
+
+        export_event.entry_points = vec![ConferenceEntryPoint {
+            entry_point_type: "video".to_string(),
+            uri: "https://meet.google.com/abc-defg-hij".to_string(),
+            ..Default::default()
+        }];
+        assert_eq!(
+            join_link(&export_event),
+            Some("https://meet.google.com/abc-defg-hij")
+        );
+    }
+
+    #[test]
+    fn test_parse_bound() {
+        let tokyo = DisplayTimezone::Named(chrono_tz::Asia::Tokyo);
+        assert_eq!(
+            parse_bound("2026-10-20", tokyo).unwrap().to_rfc3339(),
+            "2026-10-19T15:00:00+00:00"
+        );
+        assert_eq!(
+            parse_bound("2026-10-20T12:00:00+09:00", tokyo)
+                .unwrap()
+                .to_rfc3339(),
+            "2026-10-20T03:00:00+00:00"
+        );
+        assert!(parse_bound("tomorrow", tokyo).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use async_trait::async_trait;
+
+use crate::{
+    env::Env,
+    oauth,
+    provider::{self, CalendarEvent, CalendarProvider, EventBatch, SyncWindow},
+    repository::models::{self, OAuthToken},
+};
+
+use super::{
+    attendees_of, calendar_time_zone,
+    create::{insert_event, quick_add, NewEvent},
+    handle_google_calendar_event_result, latest_valid_token, list_changed_events, list_events,
+    rsvp::{has_events_scope, respond_to_event, ResponseStatus},
+    watch, Error, GoogleCalendarEvent, PRIMARY_CALENDAR_ID,
+};
+
+/// events.provider に保存する名前
+pub const GOOGLE_PROVIDER_NAME: &str = "google";
+
+/**
+ * Google Calendar の primary カレンダー
+ */
+#[derive(Default)]
+pub struct GoogleProvider {
+    /// 認可直後など、使うトークンが決まっている場合に指定する。None の場合は最新のトークン
+    oauth_token: Option<OAuthToken>,
+}
+impl GoogleProvider {
+    pub fn with_token(oauth_token: OAuthToken) -> Self {
+        Self {
+            oauth_token: Some(oauth_token),
+        }
+    }
+
+    async fn token(&self) -> Result<OAuthToken, Error> {
+        match &self.oauth_token {
+            Some(oauth_token) => Ok(oauth_token.clone()),
+            None => latest_valid_token().await,
+        }
+    }
+
+    /// 予定を変更できるトークン
+    async fn writable_token(&self) -> Result<OAuthToken, Error> {
+        let oauth_token = self.token().await?;
+        if !has_events_scope(&oauth_token) {
+            return Err(Error::InsufficientScope);
+        }
+        Ok(oauth_token)
+    }
+
+    /// 作成したイベントを、一覧と同じくカレンダーのタイムゾーンで UTC にする
+    async fn created_event(
+        access_token: &str,
+        event: GoogleCalendarEvent,
+    ) -> Result<CalendarEvent, provider::Error> {
+        // イベントは作成済みのため、タイムゾーンが取得できなくても失敗にしない
+        let time_zone = calendar_time_zone(access_token).await.unwrap_or_else(|e| {
+            println!("Failed to get the calendar time zone: {:?}", e);
+            None
+        });
+        event
+            .to_calendar_event(time_zone.as_deref())
+            .ok_or(Error::Parse(format!("Created event {} has no valid time", event.id)).into())
+    }
+}
+
+#[async_trait]
+impl CalendarProvider for GoogleProvider {
+    fn name(&self) -> &'static str {
+        GOOGLE_PROVIDER_NAME
+    }
+
+    fn calendar_id(&self) -> String {
+        PRIMARY_CALENDAR_ID.to_string()
+    }
+
+    async fn list_events(&self, window: SyncWindow) -> Result<EventBatch, provider::Error> {
+        let oauth_token = self.token().await?;
+        let result = list_events(oauth_token.access_token.clone(), window).await;
+        let google_calendar_parent =
+            handle_google_calendar_event_result(result, oauth_token).await?;
+        Ok(google_calendar_parent.into_batch())
+    }
+
+    async fn list_changes(
+        &self,
+        sync_token: &str,
+        _window: SyncWindow,
+    ) -> Result<EventBatch, provider::Error> {
+        let oauth_token = self.token().await?;
+        let result =
+            list_changed_events(oauth_token.access_token.clone(), sync_token.to_string()).await;
+        match handle_google_calendar_event_result(result, oauth_token).await {
+            Ok(google_calendar_parent) => Ok(google_calendar_parent.into_batch()),
+            Err(Error::Http {
+                status: reqwest::StatusCode::GONE,
+                ..
+            }) => Err(provider::Error::SyncTokenExpired),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn authorization_url(&self) -> Option<String> {
+        Some(oauth::oauth_url(&Env::new()))
+    }
+
+    async fn watch(&self) -> Result<(), provider::Error> {
+        let oauth_token = self.token().await?;
+        watch::ensure_watch_channel(&oauth_token).await?;
+        Ok(())
+    }
+
+    async fn respond(
+        &self,
+        event_id: &str,
+        status: ResponseStatus,
+    ) -> Result<Vec<models::Attendee>, provider::Error> {
+        let oauth_token = self.writable_token().await?;
+        let event = respond_to_event(&oauth_token.access_token, event_id, status).await?;
+        Ok(attendees_of(&event))
+    }
+
+    async fn quick_add(&self, text: &str) -> Result<CalendarEvent, provider::Error> {
+        let oauth_token = self.writable_token().await?;
+        let event = quick_add(&oauth_token.access_token, text).await?;
+        Self::created_event(&oauth_token.access_token, event).await
+    }
+
+    async fn create(&self, new_event: &NewEvent) -> Result<CalendarEvent, provider::Error> {
+        let oauth_token = self.writable_token().await?;
+        let event = insert_event(&oauth_token.access_token, new_event).await?;
+        Self::created_event(&oauth_token.access_token, event).await
+    }
+}
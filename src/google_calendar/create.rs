@@ -0,0 +1,114 @@
+use serde_json::json;
+
+use crate::env::Env;
+
+use super::{
+    authorization_headers, retry::classify_response, Error, GoogleCalendarEvent,
+    PRIMARY_CALENDAR_ID,
+};
+
+/// 構造化して作成するイベント
+#[derive(Debug, Clone)]
+pub struct NewEvent {
+    pub summary: String,
+    pub start: chrono::DateTime<chrono::FixedOffset>,
+    pub duration: chrono::Duration,
+    /// Google Meet のリンクを発行する
+    pub with_meet: bool,
+}
+impl NewEvent {
+    fn request_body(&self) -> serde_json::Value {
+        let end = self.start + self.duration;
+        let mut body = json!({
+            "summary": self.summary,
+            "start": { "dateTime": self.start.to_rfc3339() },
+            "end": { "dateTime": end.to_rfc3339() },
+            "reminders": { "useDefault": true },
+        });
+        if self.with_meet {
+            body["conferenceData"] = json!({
+                "createRequest": {
+                    "requestId": uuid::Uuid::new_v4().to_string(),
+                    "conferenceSolutionKey": { "type": "hangoutsMeet" },
+                }
+            });
+        }
+        body
+    }
+}
+
+fn events_url() -> String {
+    format!(
+        "{}/calendar/v3/calendars/{}/events",
+        Env::new().google_api_base_url,
+        PRIMARY_CALENDAR_ID
+    )
+}
+
+/// 作成は冪等でなく、再送すると同じイベントが重複するため 1 回だけ送る
+async fn send_once(request: reqwest::RequestBuilder) -> Result<GoogleCalendarEvent, Error> {
+    let response = classify_response(request.send().await?).await?;
+    let text = response.text().await?;
+    serde_json::from_str(&text).map_err(|e| Error::Parse(e.to_string()))
+}
+
+/**
+ * events.quickAdd で「明日 12:30 にランチ」のような文章からイベントを作成する
+ */
+pub async fn quick_add(access_token: &str, text: &str) -> Result<GoogleCalendarEvent, Error> {
+    let request = reqwest::Client::new()
+        .post(format!("{}/quickAdd", events_url()))
+        .headers(authorization_headers(access_token)?)
+        .query(&[("text", text)]);
+    send_once(request).await
+}
+
+/**
+ * events.insert でイベントを作成する。Meet のリンクを発行する場合は conferenceDataVersion=1 が必要
+ */
+pub async fn insert_event(
+    access_token: &str,
+    new_event: &NewEvent,
+) -> Result<GoogleCalendarEvent, Error> {
+    let request = reqwest::Client::new()
+        .post(events_url())
+        .headers(authorization_headers(access_token)?)
+        .query(&[("conferenceDataVersion", "1")])
+        .json(&new_event.request_body());
+    send_once(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_body() {
+        let new_event = NewEvent {
+            summary: "Lunch".to_string(),
+            start: chrono::DateTime::parse_from_rfc3339("2026-10-20T12:30:00+09:00").unwrap(),
+            duration: chrono::Duration::minutes(45),
+            with_meet: false,
+        };
+        assert_eq!(
+            new_event.request_body(),
+            json!({
+                "summary": "Lunch",
+                "start": { "dateTime": "2026-10-20T12:30:00+09:00" },
+                "end": { "dateTime": "2026-10-20T13:15:00+09:00" },
+                "reminders": { "useDefault": true },
+            })
+        );
+
+        let body = NewEvent {
+            with_meet: true,
+            ..new_event
+        }
+        .request_body();
+        assert_eq!(
+            body["conferenceData"]["createRequest"]["conferenceSolutionKey"]["type"],
+            "hangoutsMeet"
+        );
+        assert!(body["conferenceData"]["createRequest"]["requestId"].is_string());
+    }
+}
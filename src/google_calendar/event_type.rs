@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+
+use crate::repository::models::Event;
+
+/// 会議以外の予定は通知の扱いを変える
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventType {
+    Default,
+    /// 不在。期間中は他の予定を通知しない
+    OutOfOffice,
+    /// サイレント モード。開始時にお知らせだけ出す
+    FocusTime,
+    /// 勤務場所。通知はせず TUI に表示する
+    WorkingLocation,
+    /// fromGmail / birthday など、会議と同じく扱うもの
+    Other,
+}
+impl EventType {
+    /**
+     * API の eventType から変換する
+     *
+     * ```
+     * use calendar_notice::google_calendar::event_type::EventType;
+     *
+     * assert_eq!(EventType::from_api(Some("outOfOffice")), EventType::OutOfOffice);
+     * assert_eq!(EventType::from_api(Some("birthday")), EventType::Other);
+     * assert_eq!(EventType::from_api(None), EventType::Default);
+     * ```
+     */
+    pub fn from_api(event_type: Option<&str>) -> Self {
+        match event_type {
+            None | Some("default") => EventType::Default,
+            Some("outOfOffice") => EventType::OutOfOffice,
+            Some("focusTime") => EventType::FocusTime,
+            Some("workingLocation") => EventType::WorkingLocation,
+            Some(_) => EventType::Other,
+        }
+    }
+
+    pub fn of(event: &Event) -> Self {
+        Self::from_api(event.event_type.as_deref())
+    }
+
+    /**
+     * 新しく保存する予定の、既定の通知の有無と何秒前に通知するか
+     */
+    pub fn default_notification(&self, all_day: bool) -> (bool, i32) {
+        match self {
+            EventType::OutOfOffice | EventType::WorkingLocation => {
+                (false, DEFAULT_NOTIFICATION_SEC_FROM_START)
+            }
+            // 終日の予定は 0 時に通知しても意味がないため、既定では通知しない
+            EventType::FocusTime => (!all_day, FOCUS_TIME_NOTIFICATION_SEC_FROM_START),
+            EventType::Default | EventType::Other => {
+                (!all_day, DEFAULT_NOTIFICATION_SEC_FROM_START)
+            }
+        }
+    }
+}
+
+/// 新しいイベントを何秒前に通知するか
+pub const DEFAULT_NOTIFICATION_SEC_FROM_START: i32 = 60 * 10;
+/// 集中時間は直前(通知の確認間隔以内)にお知らせする
+pub const FOCUS_TIME_NOTIFICATION_SEC_FROM_START: i32 = 60;
+
+/**
+ * 勤務場所の予定の workingLocationProperties
+ */
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkingLocationProperties {
+    /// homeOffice / officeLocation / customLocation
+    #[serde(rename = "type")]
+    pub location_type: Option<String>,
+    pub home_office: Option<serde_json::Value>,
+    pub office_location: Option<WorkingLocationLabel>,
+    pub custom_location: Option<WorkingLocationLabel>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkingLocationLabel {
+    pub label: Option<String>,
+}
+
+impl WorkingLocationProperties {
+    /**
+     * TUI に表示する勤務場所の名前
+     *
+     * ```
+     * use calendar_notice::google_calendar::event_type::{
+     *     WorkingLocationLabel, WorkingLocationProperties,
+     * };
+     *
+     * let office = WorkingLocationProperties {
+     *     location_type: Some("officeLocation".to_string()),
+     *     office_location: Some(WorkingLocationLabel { label: Some("Office B".to_string()) }),
+     *     ..Default::default()
+     * };
+     * assert_eq!(office.label(), Some("Office B".to_string()));
+     *
+     * let home = WorkingLocationProperties {
+     *     location_type: Some("homeOffice".to_string()),
+     *     ..Default::default()
+     * };
+     * assert_eq!(home.label(), Some("自宅".to_string()));
+     * ```
+     */
+    pub fn label(&self) -> Option<String> {
+        let label_of = |location: &Option<WorkingLocationLabel>| {
+            location
+                .as_ref()
+                .and_then(|location| location.label.clone())
+        };
+        match self.location_type.as_deref() {
+            Some("homeOffice") => Some("自宅".to_string()),
+            Some("officeLocation") => {
+                label_of(&self.office_location).or(Some("オフィス".to_string()))
+            }
+            Some("customLocation") => label_of(&self.custom_location),
+            _ => None,
+        }
+    }
+}
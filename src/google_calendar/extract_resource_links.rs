@@ -0,0 +1,60 @@
+use regex::Regex;
+
+/// 会議の参加に使うため、資料として扱わないホスト
+const MEETING_HOSTS: [&str; 5] = [
+    "zoom.us",
+    "meet.google.com",
+    "tel.meet",
+    "teams.microsoft.com",
+    "teams.live.com",
+];
+
+/// 説明文から会議のリンク以外の URL を抽出する関数
+///
+/// # 引数
+///
+/// * `description` - 資料などのリンクを含む可能性のある文字列
+///
+/// # 戻り値
+///
+/// 見つかった順に重複を除いた URL の一覧
+///
+/// # Examples
+///
+/// ```
+/// use calendar_notice::google_calendar::extract_resource_links;
+///
+/// let description = "アジェンダ: https://docs.google.com/document/d/abc/edit.
+/// 参加: https://zoom.us/j/95428352872
+/// <a href=\"https://example.com/slides\">スライド</a> https://docs.google.com/document/d/abc/edit";
+/// assert_eq!(
+///     extract_resource_links(description),
+///     vec![
+///         "https://docs.google.com/document/d/abc/edit".to_string(),
+///         "https://example.com/slides".to_string(),
+///     ]
+/// );
+///
+/// assert!(extract_resource_links("リンクなし").is_empty());
+/// ```
+pub fn extract_resource_links(description: &str) -> Vec<String> {
+    let re = Regex::new(r#"https?://[^\s"'<>]+"#).unwrap();
+    let mut links: Vec<String> = vec![];
+    for link in re.find_iter(description) {
+        let link = link
+            .as_str()
+            .trim_end_matches(['.', ',', ';', ':', ')', ']', '」', '。', '、']);
+        let host = link
+            .split("://")
+            .nth(1)
+            .and_then(|rest| rest.split(['/', '?', '#']).next())
+            .unwrap_or("");
+        let is_meeting = MEETING_HOSTS.iter().any(|meeting_host| {
+            host == *meeting_host || host.ends_with(&format!(".{}", meeting_host))
+        });
+        if !is_meeting && !links.iter().any(|l| l == link) {
+            links.push(link.to_string());
+        }
+    }
+    links
+}
@@ -1,4 +1,4 @@
-use regex::Regex;
+use crate::meeting_link::{builtin::teams, MeetingLinkExtractor};
 
 /// Teams のミーティングリンクを抽出する関数
 ///
@@ -25,6 +25,5 @@ use regex::Regex;
 /// assert_eq!(extract_teams_link(no_link), None);
 /// ```
 pub fn extract_teams_link(description: &str) -> Option<String> {
-    let re = Regex::new(r"https://teams\.microsoft\.com/l/meetup-join/[^\s]+").unwrap();
-    re.find(description).map(|m| m.as_str().to_string())
+    teams().extract(description).map(|link| link.url)
 }
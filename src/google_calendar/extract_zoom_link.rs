@@ -1,4 +1,4 @@
-use regex::Regex;
+use crate::meeting_link::{builtin::ZoomExtractor, MeetingLinkExtractor};
 
 /// Zoom のミーティングリンクを抽出する関数
 ///
@@ -31,16 +31,7 @@ use regex::Regex;
 /// );
 /// ```
 pub fn extract_zoom_link(description: &str) -> Option<String> {
-    let re =
-        Regex::new(r"https://(?:[\w-]+\.)?zoom\.us/(?:j|w|wc)/(\d+)(?:\?pwd=([a-zA-Z0-9.]+))?")
-            .unwrap();
-    re.captures(description).map(|cap| {
-        let meeting_id = &cap[1];
-        let password = cap.get(2).map(|m| m.as_str());
-
-        match password {
-            Some(pwd) => format!("https://zoom.us/j/{}?pwd={}", meeting_id, pwd),
-            None => format!("https://zoom.us/j/{}", meeting_id),
-        }
-    })
+    ZoomExtractor::new()
+        .extract(description)
+        .map(|link| link.url)
 }
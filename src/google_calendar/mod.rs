@@ -1,16 +1,18 @@
 use std::fmt;
-use std::thread;
 use std::time::Duration;
 
 use reqwest::header::{HeaderMap, HeaderValue, InvalidHeaderValue};
 
 use crate::{
+    env::Env,
     google_calendar::{self},
-    oauth::{self, is_token_expired::is_token_expired, refresh_and_save_token},
+    oauth::{is_token_expired::is_token_expired, refresh_and_save_token},
+    provider::{self, CalendarEvent, EventBatch},
     repository::{
         self,
-        models::{Event, EventFindMany, EventUpdate, OAuthToken},
+        models::{self, OAuthToken},
     },
+    timezone,
 };
 use serde::{Deserialize, Serialize};
 
@@ -19,8 +21,21 @@ mod extract_zoom_link;
 pub use self::extract_zoom_link::extract_zoom_link;
 mod extract_teams_link;
 pub use self::extract_teams_link::extract_teams_link;
+mod extract_resource_links;
+pub use self::extract_resource_links::extract_resource_links;
+mod calendar_provider;
+pub use self::calendar_provider::{GoogleProvider, GOOGLE_PROVIDER_NAME};
+pub mod create;
+pub mod event_type;
+pub mod retry;
+pub mod rsvp;
+pub mod watch;
+use self::event_type::WorkingLocationProperties;
+use self::retry::{send_with_retry, RetryPolicy};
+pub use crate::provider::SyncWindow;
 
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GoogleCalendarParent {
     pub kind: String,
     pub etag: String,
@@ -31,6 +46,7 @@ pub struct GoogleCalendarParent {
     pub access_role: Option<String>,
     pub default_reminders: Option<Vec<Reminder>>,
     pub next_page_token: Option<String>,
+    pub next_sync_token: Option<String>,
     pub items: Vec<GoogleCalendarEvent>,
 }
 
@@ -69,7 +85,8 @@ impl fmt::Display for EventStatus {
  * TODO 不要な値を削る
  */
 #[derive(Debug, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+// 差分同期で返る削除済みイベントは id と status しか持たないため、欠けている値はデフォルトで埋める
+#[serde(rename_all = "camelCase", default)]
 pub struct GoogleCalendarEvent {
     pub kind: String,
     pub etag: String,
@@ -80,6 +97,7 @@ pub struct GoogleCalendarEvent {
     pub updated: String,
     pub summary: String,
     pub description: Option<String>,
+    pub location: Option<String>,
     pub creator: EventPerson,
     pub organizer: EventPerson,
     pub start: EventDateTime,
@@ -93,10 +111,11 @@ pub struct GoogleCalendarEvent {
     pub attendees: Option<Vec<Attendee>>,
     pub reminders: Option<Reminders>,
     pub event_type: Option<String>,
+    pub working_location_properties: Option<WorkingLocationProperties>,
     pub hangout_link: Option<String>,
     pub conference_data: Option<ConferenceData>,
+    pub attachments: Option<Vec<EventAttachment>>,
 }
-impl GoogleCalendarEvent {}
 impl Default for GoogleCalendarEvent {
     fn default() -> Self {
         Self {
@@ -109,6 +128,7 @@ impl Default for GoogleCalendarEvent {
             updated: String::new(),
             summary: String::new(),
             description: None,
+            location: None,
             creator: EventPerson::default(),
             organizer: EventPerson::default(),
             start: EventDateTime::default(),
@@ -122,8 +142,10 @@ impl Default for GoogleCalendarEvent {
             attendees: None,
             reminders: None,
             event_type: None,
+            working_location_properties: None,
             hangout_link: None,
             conference_data: None,
+            attachments: None,
         }
     }
 }
@@ -133,6 +155,7 @@ impl Default for GoogleCalendarEvent {
 pub struct EventPerson {
     email: String,
     display_name: Option<String>,
+    #[serde(rename = "self")]
     self_: Option<bool>,
 }
 
@@ -146,15 +169,49 @@ pub struct EventDateTime {
     #[serde(rename = "timeZone")]
     pub time_zone: Option<String>,
 }
+impl EventDateTime {
+    pub fn is_all_day(&self) -> bool {
+        self.date_time.is_none() && self.date.is_some()
+    }
+
+    /// 終日の予定の日付
+    pub fn all_day_date(&self) -> Option<chrono::NaiveDate> {
+        self.date_time
+            .is_none()
+            .then(|| chrono::NaiveDate::parse_from_str(self.date.as_deref()?, "%Y-%m-%d").ok())
+            .flatten()
+    }
+
+    /**
+     * UTC の日時にする。
+     * オフセットのない日時や終日の予定の日付は、イベント(なければカレンダー)のタイムゾーンの時刻として扱う
+     */
+    pub fn to_utc(
+        &self,
+        calendar_time_zone: Option<&str>,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        let time_zone = self
+            .time_zone
+            .as_deref()
+            .or(calendar_time_zone)
+            .and_then(|time_zone| time_zone.parse::<chrono_tz::Tz>().ok());
+        match (&self.date_time, &self.date) {
+            (Some(date_time), _) => timezone::parse_date_time(date_time, time_zone),
+            (None, Some(date)) => timezone::parse_date(date, time_zone),
+            (None, None) => None,
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Attendee {
-    email: String,
-    display_name: Option<String>,
-    organizer: Option<bool>,
-    self_: Option<bool>,
-    response_status: Option<String>,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub organizer: Option<bool>,
+    #[serde(rename = "self")]
+    pub self_: Option<bool>,
+    pub response_status: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -163,22 +220,37 @@ pub struct Reminders {
     use_default: Option<bool>,
 }
 
+/**
+ * Google ドライブなどの添付ファイル
+ */
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventAttachment {
+    pub file_url: String,
+    pub title: Option<String>,
+    pub mime_type: Option<String>,
+    pub file_id: Option<String>,
+    pub icon_link: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ConferenceData {
-    entry_points: Vec<EntryPoint>,
-    conference_solution: ConferenceSolution,
-    conference_id: String,
+    // 会議の作成中(createRequest のみ)は entryPoints などが含まれない
+    #[serde(default)]
+    pub entry_points: Vec<EntryPoint>,
+    pub conference_solution: Option<ConferenceSolution>,
+    pub conference_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EntryPoint {
-    entry_point_type: String,
-    uri: String,
-    label: Option<String>,
-    pin: Option<String>,
-    region_code: Option<String>,
+    pub entry_point_type: String,
+    pub uri: String,
+    pub label: Option<String>,
+    pub pin: Option<String>,
+    pub region_code: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -200,7 +272,25 @@ pub struct ConferenceSolutionKey {
 pub enum Error {
     Reqwest(reqwest::Error),
     Unauthorized,
+    TokenNotFound,
+    /// 429 または 403 rateLimitExceeded / userRateLimitExceeded
+    RateLimited {
+        retry_after: Option<Duration>,
+    },
+    /// レート制限以外の 403
+    Forbidden(String),
+    /// 5xx
+    Server(reqwest::StatusCode),
+    /// その他の成功以外のステータス
+    Http {
+        status: reqwest::StatusCode,
+        body: String,
+    },
     Parse(String),
+    /// 自分が参加者に含まれていない
+    NotAttendee,
+    /// トークンに必要なスコープが含まれていない。再認可が必要
+    InsufficientScope,
 }
 impl From<reqwest::Error> for Error {
     fn from(e: reqwest::Error) -> Self {
@@ -213,66 +303,59 @@ impl From<InvalidHeaderValue> for Error {
     }
 }
 
-const SYNC_CALENDAR_INTERVAL_SEC: u16 = 60 * 10;
-// TODO 扱う期間を const or env 化
-const FROM_SUB_SEC: u16 = 60 * 10;
-const TO_ADD_DAYS: u8 = 3;
-
-pub fn spawn_sync_calendar_cron() {
-    tokio::spawn(async {
-        loop {
-            let latest_token = repository::oauth_token::find_latest().unwrap_or_else(|e| {
-                panic!(
-                    "Failed to get latest token in run_sync_calendar_cron_thread: {:?}",
-                    e
-                )
-            });
-
-            match latest_token {
-                Some(oauth_token) if is_token_expired(&oauth_token, chrono::Local::now()) => {
-                    refresh_and_save_token(
-                        oauth_token.id.clone(),
-                        oauth_token.refresh_token.clone().unwrap(),
-                    )
-                    .await;
-
-                    let _ =
-                        repository::oauth_token::find_latest().expect("new token must be found");
-                    sync_events(oauth_token).await.unwrap_or_else(|_| {
-                        // println!(
-                        //     "Failed to sync events in run_sync_calendar_cron_thread with new token: {:?}",
-                        //     e
-                        // )
-                    });
-                }
-                Some(oauth_token) => {
-                    sync_events(oauth_token).await.unwrap_or_else(|e| {
-                        println!(
-                            "Failed to sync events in run_sync_calendar_cron_thread: {:?}",
-                            e
-                        )
-                    });
-                }
-                None => {
-                    // println!("OAuth token is not found. Please authenticate ");
-                    // TODO 認証完了まで、次のループで再度認証催促が発生するのを防ぐ？
-                    oauth::to_oauth_on_browser();
-                }
-            }
-
-            thread::sleep(Duration::from_secs(SYNC_CALENDAR_INTERVAL_SEC.into()));
-        }
-    });
+const PRIMARY_CALENDAR_ID: &str = "primary";
+
+fn authorization_headers(access_token: &str) -> Result<HeaderMap, Error> {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        "AUTHORIZATION",
+        HeaderValue::from_str(&format!("Bearer {}", access_token))?,
+    );
+    Ok(headers)
 }
 
-pub async fn sync_events(oauth_token: OAuthToken) -> Result<(), Error> {
-    let google_calendar_result =
-        google_calendar::list_events(oauth_token.access_token.clone()).await;
-    let google_calendar_parent =
-        handle_google_calendar_event_result(google_calendar_result, oauth_token.clone()).await?;
-    let _ = update_events(google_calendar_parent);
+/**
+ * 最新のトークンを返す。期限切れの場合はリフレッシュしてから返す
+ */
+pub async fn latest_valid_token() -> Result<OAuthToken, Error> {
+    let latest_token = repository::oauth_token::find_latest()
+        .map_err(|e| Error::Parse(e.to_string()))?
+        .ok_or(Error::TokenNotFound)?;
+
+    if !is_token_expired(&latest_token, chrono::Local::now()) {
+        return Ok(latest_token);
+    }
 
-    Ok(())
+    let refresh_token = latest_token
+        .refresh_token
+        .clone()
+        .ok_or(Error::TokenNotFound)?;
+    refresh_and_save_token(latest_token.id.clone(), refresh_token).await;
+
+    repository::oauth_token::find_latest()
+        .map_err(|e| Error::Parse(e.to_string()))?
+        .ok_or(Error::TokenNotFound)
+}
+
+/**
+ * 最新のトークンで同期期間内のイベントをすべて同期する
+ */
+pub async fn sync_with_latest_token(window: SyncWindow) -> Result<(), provider::Error> {
+    provider::sync_events(&GoogleProvider::default(), window).await
+}
+
+/**
+ * 最新のトークンで前回の同期以降の変更だけを同期する
+ */
+pub async fn sync_changes_with_latest_token(window: SyncWindow) -> Result<(), provider::Error> {
+    provider::sync_changed_events(&GoogleProvider::default(), window).await
+}
+
+pub async fn sync_events(
+    oauth_token: OAuthToken,
+    window: SyncWindow,
+) -> Result<(), provider::Error> {
+    provider::sync_events(&GoogleProvider::with_token(oauth_token), window).await
 }
 
 pub async fn handle_google_calendar_event_result(
@@ -299,159 +382,235 @@ pub async fn handle_google_calendar_event_result(
     }
 }
 
-pub fn update_events(google_calendar_parent: GoogleCalendarParent) -> Result<(), String> {
-    // println!(
-    //     "fetched google calendar events: {:?}",
-    //     google_calendar_parent
-    //         .items
-    //         .iter()
-    //         .map(|item| &item.summary)
-    //         .collect::<Vec<&String>>()
-    // );
-
-    let duplicated_events = repository::event::find_many(EventFindMany {
-        ids_in: Some(
-            google_calendar_parent
-                .items
+impl GoogleCalendarParent {
+    /**
+     * キャンセルされたイベントは削除対象にし、それ以外はプロバイダーに依存しないイベントにする
+     */
+    pub fn into_batch(self) -> EventBatch {
+        let time_zone = self.time_zone;
+        let (cancelled_events, changed_events): (Vec<_>, Vec<_>) = self
+            .items
+            .into_iter()
+            .partition(|event| event.status == Some(EventStatus::Cancelled));
+        EventBatch {
+            events: changed_events
                 .iter()
-                .map(|event| event.id.clone())
+                .filter_map(|event| event.to_calendar_event(time_zone.as_deref()))
                 .collect(),
-        ),
-        ..Default::default()
-    })
-    .unwrap_or_else(|e| {
-        println!(
-            "Failed to get upcoming events in handle_sync_events: {:?}",
-            e
-        );
-        vec![]
-    });
-
-    // すでに存在するイベントは、events を更新する
-    for event in &duplicated_events {
-        let event_update: EventUpdate = google_calendar_parent
-            .items
-            .iter()
-            .find(|e| e.id == event.id)
-            .map(|e| EventUpdate {
-                summary: Some(e.summary.clone()),
-                description: e.description.clone(),
-                status: Some(
-                    e.status
-                        .as_ref()
-                        .unwrap_or(&EventStatus::Unknown)
-                        .to_string(),
-                ),
-                hangout_link: e.hangout_link.clone(),
-                zoom_link: match e.description {
-                    Some(ref description) => extract_zoom_link(description),
-                    None => None,
-                },
-                teams_link: match e.description {
-                    Some(ref description) => extract_teams_link(description),
-                    None => None,
-                },
-                start_datetime: Some(e.start.date_time.clone().unwrap()),
-                end_datetime: Some(e.end.date_time.clone().unwrap()),
-                ..Default::default()
-            })
-            .expect("EventUpdate must be created");
-        let _ = repository::event::update(event.id.clone(), event_update);
+            deleted_ids: cancelled_events.into_iter().map(|event| event.id).collect(),
+            next_sync_token: self.next_sync_token,
+        }
     }
+}
 
-    // 新規イベントは、events を作成する
-    let new_google_calendar_events = google_calendar_parent.items.iter().filter(|event| {
-        !duplicated_events
-            .iter()
-            .any(|duplicated_event| duplicated_event.id == event.id)
-    });
-
-    let event_creates: Vec<Event> = new_google_calendar_events
-        .clone()
-        .map(|event| Event {
-            id: event.id.clone(),
-            summary: Some(event.summary.clone()),
-            description: event.description.clone(),
+impl GoogleCalendarEvent {
+    /**
+     * プロバイダーに依存しないイベントにする。開始・終了日時が読めない場合は None
+     */
+    pub fn to_calendar_event(&self, calendar_time_zone: Option<&str>) -> Option<CalendarEvent> {
+        let (Some(start), Some(end)) = (
+            self.start.to_utc(calendar_time_zone),
+            self.end.to_utc(calendar_time_zone),
+        ) else {
+            println!("Skip event {} without valid start or end", self.id);
+            return None;
+        };
+        Some(CalendarEvent {
+            id: self.id.clone(),
+            summary: Some(self.summary.clone()),
+            description: self.description.clone(),
             status: Some(
-                event
-                    .status
+                self.status
                     .as_ref()
                     .unwrap_or(&EventStatus::Unknown)
                     .to_string(),
             ),
-            hangout_link: event.hangout_link.clone(),
-            zoom_link: match event.description {
-                Some(ref description) => extract_zoom_link(description),
-                None => None,
-            },
-            teams_link: match event.description {
-                Some(ref description) => extract_teams_link(description),
-                None => None,
-            },
-            start_datetime: event
-                .start
-                .date_time
-                .clone()
-                .expect("start_datetime must exist"),
-            end_datetime: event
-                .end
-                .date_time
-                .clone()
-                .expect("end_datetime must exist"),
-            notification_enabled: true,
-            notification_sec_from_start: 60 * 10,
+            start,
+            end,
+            all_day: self.start.is_all_day(),
+            start_date: self.start.all_day_date(),
+            end_date: self.end.all_day_date(),
+            recurring_event_id: self.recurring_event_id.clone(),
+            original_start_time: self
+                .original_start_time
+                .as_ref()
+                .and_then(|time| time.to_utc(calendar_time_zone)),
+            location: location_of(self),
+            event_type: self.event_type.clone(),
+            // Google のリマインダーは Google 側で通知されるため、通知設定には使わない
+            reminder_sec_from_start: None,
+            hangout_link: self.hangout_link.clone(),
+            attendees: attendees_of(self),
+            entry_points: entry_points_of(self),
+            attachments: attachments_of(self),
         })
-        .collect();
-    let event_result = repository::event::create_many(event_creates);
-    if let Err(e) = event_result {
-        return Err(format!("Failed to create events: {:?}", e).to_string());
     }
+}
 
-    Ok(())
+/**
+ * 予定の場所。勤務場所の予定は場所の代わりに勤務場所の名前を使う
+ */
+fn location_of(event: &GoogleCalendarEvent) -> Option<String> {
+    event.location.clone().or_else(|| {
+        event
+            .working_location_properties
+            .as_ref()
+            .and_then(WorkingLocationProperties::label)
+    })
 }
 
-// TODO 期間をクエリパラメータで指定できるようにする
+fn attendees_of(event: &GoogleCalendarEvent) -> Vec<models::Attendee> {
+    event
+        .attendees
+        .iter()
+        .flatten()
+        .map(|attendee| models::Attendee {
+            event_id: event.id.clone(),
+            email: attendee.email.clone(),
+            display_name: attendee.display_name.clone(),
+            organizer: attendee.organizer.unwrap_or(false),
+            is_self: attendee.self_.unwrap_or(false),
+            response_status: attendee.response_status.clone(),
+        })
+        .collect()
+}
+
+fn entry_points_of(event: &GoogleCalendarEvent) -> Vec<models::ConferenceEntryPoint> {
+    event
+        .conference_data
+        .iter()
+        .flat_map(|conference_data| &conference_data.entry_points)
+        .enumerate()
+        .map(|(position, entry_point)| models::ConferenceEntryPoint {
+            event_id: event.id.clone(),
+            position: position as i32,
+            entry_point_type: entry_point.entry_point_type.clone(),
+            uri: entry_point.uri.clone(),
+            label: entry_point.label.clone(),
+            pin: entry_point.pin.clone(),
+            region_code: entry_point.region_code.clone(),
+        })
+        .collect()
+}
+
+fn attachments_of(event: &GoogleCalendarEvent) -> Vec<models::EventResource> {
+    event
+        .attachments
+        .iter()
+        .flatten()
+        .map(|attachment| models::EventResource {
+            source: "attachment".to_string(),
+            url: attachment.file_url.clone(),
+            title: attachment.title.clone(),
+            mime_type: attachment.mime_type.clone(),
+            ..Default::default()
+        })
+        .collect()
+}
+
+const LIST_EVENTS_MAX_RESULTS: &str = "250";
+
 // TODO item だけ返却でも良いのでは？
-pub async fn list_events(access_token: String) -> Result<GoogleCalendarParent, Error> {
+/**
+ * 同期期間内のイベントを取得する
+ */
+pub async fn list_events(
+    access_token: String,
+    window: SyncWindow,
+) -> Result<GoogleCalendarParent, Error> {
+    let (time_min, time_max) = window.time_range(chrono::Utc::now());
+
+    list_event_pages(
+        access_token,
+        vec![
+            ("orderBy", "startTime".to_string()),
+            ("timeMin", time_min.to_rfc3339()),
+            ("timeMax", time_max.to_rfc3339()),
+        ],
+    )
+    .await
+}
+
+/**
+ * syncToken 発行以降に変更されたイベントを取得する。削除されたイベントは status が cancelled で返る
+ */
+pub async fn list_changed_events(
+    access_token: String,
+    sync_token: String,
+) -> Result<GoogleCalendarParent, Error> {
+    list_event_pages(access_token, vec![("syncToken", sync_token)]).await
+}
+
+/**
+ * calendars.get でカレンダーのタイムゾーンを取得する。作成したイベントを一覧と同じように UTC にするために使う
+ */
+pub async fn calendar_time_zone(access_token: &str) -> Result<Option<String>, Error> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Calendar {
+        time_zone: Option<String>,
+    }
+
     let url = format!(
-        "https://www.googleapis.com/calendar/v3/calendars/{}/events",
-        "primary"
+        "{}/calendar/v3/calendars/{}",
+        Env::new().google_api_base_url,
+        PRIMARY_CALENDAR_ID
     );
+    let request = reqwest::Client::new()
+        .get(url)
+        .headers(authorization_headers(access_token)?);
+    let text = send_with_retry(request, RetryPolicy::default())
+        .await?
+        .text()
+        .await?;
+    let calendar: Calendar =
+        serde_json::from_str(&text).map_err(|e| Error::Parse(e.to_string()))?;
+    Ok(calendar.time_zone)
+}
 
-    let mut headers = HeaderMap::new();
-    headers.insert(
-        "AUTHORIZATION",
-        HeaderValue::from_str(&format!("Bearer {}", access_token))?,
+/**
+ * nextPageToken がある限り続けて取得し、items をまとめて返す
+ */
+async fn list_event_pages(
+    access_token: String,
+    params: Vec<(&str, String)>,
+) -> Result<GoogleCalendarParent, Error> {
+    let url = format!(
+        "{}/calendar/v3/calendars/{}/events",
+        Env::new().google_api_base_url,
+        PRIMARY_CALENDAR_ID
     );
 
-    let now = chrono::Utc::now();
-    let response = reqwest::Client::new()
-        .get(&url)
-        .headers(headers)
-        .query(&[
-            ("maxResults", "10"),
-            ("orderBy", "startTime"),
-            ("singleEvents", "true"),
-            (
-                "timeMin",
-                &(now - chrono::Duration::minutes(FROM_SUB_SEC.into())).to_rfc3339(),
-            ),
-            (
-                "timeMax",
-                &(now + chrono::Duration::days(TO_ADD_DAYS.into())).to_rfc3339(),
-            ),
-        ])
-        .send()
-        .await?;
-    if response.status() == reqwest::StatusCode::UNAUTHORIZED {
-        // println!("Unauthorized when requesting list events");
-        return Err(Error::Unauthorized);
-    }
+    let headers = authorization_headers(&access_token)?;
+
+    let client = reqwest::Client::new();
+    let mut page_token: Option<String> = None;
+    let mut items: Vec<GoogleCalendarEvent> = vec![];
+    loop {
+        let mut query = vec![
+            ("maxResults", LIST_EVENTS_MAX_RESULTS.to_string()),
+            ("singleEvents", "true".to_string()),
+        ];
+        query.extend(params.iter().cloned());
+        if let Some(ref page_token) = page_token {
+            query.push(("pageToken", page_token.clone()));
+        }
 
-    let text = response.text().await?;
-    // println!("🔶 text: {:?}", text);
-    let google_calendar_parent: GoogleCalendarParent =
-        serde_json::from_str(&text).map_err(|e| Error::Parse(e.to_string()))?;
-    // println!("🔵 google_calendar_parent: {:?}", google_calendar_parent);
-    Ok(google_calendar_parent)
+        let request = client.get(&url).headers(headers.clone()).query(&query);
+        // 途中のページで失敗した場合も、一部だけを同期済みとしないよう Err にする
+        let response = send_with_retry(request, RetryPolicy::default()).await?;
+
+        let text = response.text().await?;
+        // println!("🔶 text: {:?}", text);
+        let mut google_calendar_parent: GoogleCalendarParent =
+            serde_json::from_str(&text).map_err(|e| Error::Parse(e.to_string()))?;
+        // println!("🔵 google_calendar_parent: {:?}", google_calendar_parent);
+
+        items.append(&mut google_calendar_parent.items);
+        page_token = google_calendar_parent.next_page_token.take();
+        if page_token.is_none() {
+            google_calendar_parent.items = items;
+            return Ok(google_calendar_parent);
+        }
+    }
 }
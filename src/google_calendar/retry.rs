@@ -0,0 +1,323 @@
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::{header::RETRY_AFTER, RequestBuilder, Response, StatusCode};
+use serde::Deserialize;
+
+use super::Error;
+
+/// リトライの回数と待機時間の設定
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// 初回を含めた最大試行回数
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(32),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleErrorResponse {
+    error: GoogleErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleErrorBody {
+    #[serde(default)]
+    errors: Vec<GoogleErrorDetail>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleErrorDetail {
+    reason: Option<String>,
+}
+
+/**
+ * リクエストを送信し、レート制限・サーバーエラー・通信エラーの場合は指数バックオフで再送する。
+ * Retry-After ヘッダーがあればその時間だけ待つ。待機時間の上限より長い場合は再送せずに RateLimited を返す
+ */
+pub async fn send_with_retry(
+    request: RequestBuilder,
+    policy: RetryPolicy,
+) -> Result<Response, Error> {
+    let mut attempt = 0;
+    loop {
+        let cloned_request = request
+            .try_clone()
+            .ok_or_else(|| Error::Parse("Request body must be cloneable".to_string()))?;
+
+        let result = match cloned_request.send().await {
+            Ok(response) => classify_response(response).await,
+            Err(e) => Err(Error::Reqwest(e)),
+        };
+
+        attempt += 1;
+        match result {
+            Err(e) if is_retryable(&e) && attempt < policy.max_attempts => {
+                let retry_after = match e {
+                    Error::RateLimited { retry_after } => retry_after,
+                    _ => None,
+                };
+                if retry_after.is_some_and(|retry_after| retry_after > policy.max_delay) {
+                    return Err(e);
+                }
+                tokio::time::sleep(backoff_delay(&policy, attempt, retry_after)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/**
+ * ステータスコードからエラーを分類する。成功時はレスポンスをそのまま返す
+ */
+pub async fn classify_response(response: Response) -> Result<Response, Error> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| parse_retry_after(value, chrono::Utc::now()));
+    let body = response.text().await.unwrap_or_default();
+
+    Err(match status {
+        StatusCode::UNAUTHORIZED => Error::Unauthorized,
+        StatusCode::TOO_MANY_REQUESTS => Error::RateLimited { retry_after },
+        StatusCode::FORBIDDEN if is_rate_limit_reason(&body) => Error::RateLimited { retry_after },
+        StatusCode::FORBIDDEN => Error::Forbidden(body),
+        status if status.is_server_error() => Error::Server(status),
+        status => Error::Http { status, body },
+    })
+}
+
+fn is_rate_limit_reason(body: &str) -> bool {
+    serde_json::from_str::<GoogleErrorResponse>(body)
+        .map(|response| {
+            response.error.errors.iter().any(|detail| {
+                matches!(
+                    detail.reason.as_deref(),
+                    Some("rateLimitExceeded") | Some("userRateLimitExceeded")
+                )
+            })
+        })
+        .unwrap_or(false)
+}
+
+fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::RateLimited { .. } | Error::Server(_) => true,
+        Error::Reqwest(e) => e.is_timeout() || e.is_connect(),
+        _ => false,
+    }
+}
+
+/**
+ * 待機時間を求める。Retry-After があればそれを優先し、なければ指数バックオフに full jitter をかける。
+ * どちらも max_delay を上限にする
+ */
+pub fn backoff_delay(
+    policy: &RetryPolicy,
+    attempt: u32,
+    retry_after: Option<Duration>,
+) -> Duration {
+    if let Some(retry_after) = retry_after {
+        return retry_after.min(policy.max_delay);
+    }
+
+    let exponential = policy
+        .base_delay
+        .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+        .min(policy.max_delay);
+    let jitter_millis = rand::thread_rng().gen_range(0..=exponential.as_millis() as u64);
+    Duration::from_millis(jitter_millis)
+}
+
+/**
+ * Retry-After ヘッダーの値(秒数 または HTTP-date)を待機時間に変換する
+ */
+pub fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    if let Ok(seconds) = value.trim().parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value.trim()).ok()?;
+    Some(
+        date.signed_duration_since(now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::SocketAddr,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    use warp::{http::Response as HttpResponse, Filter};
+
+    use super::*;
+
+    const RATE_LIMIT_BODY: &str = r#"{"error":{"code":403,"message":"Rate Limit Exceeded","errors":[{"domain":"usageLimits","reason":"rateLimitExceeded"}]}}"#;
+    const FORBIDDEN_BODY: &str = r#"{"error":{"code":403,"message":"Forbidden","errors":[{"domain":"global","reason":"forbidden"}]}}"#;
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+        }
+    }
+
+    /// 最初の failures 回は status を返し、以降は 200 を返すモックサーバーを起動する
+    fn spawn_mock_server(
+        failures: usize,
+        status: u16,
+        retry_after: Option<&'static str>,
+        body: &'static str,
+    ) -> (SocketAddr, Arc<AtomicUsize>) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let route_count = count.clone();
+        let route = warp::any().map(move || {
+            let n = route_count.fetch_add(1, Ordering::SeqCst);
+            if n < failures {
+                let mut builder = HttpResponse::builder().status(status);
+                if let Some(retry_after) = retry_after {
+                    builder = builder.header("Retry-After", retry_after);
+                }
+                builder.body(body.to_string()).unwrap()
+            } else {
+                HttpResponse::builder()
+                    .status(200)
+                    .body("ok".to_string())
+                    .unwrap()
+            }
+        });
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        (addr, count)
+    }
+
+    async fn send(addr: SocketAddr) -> Result<Response, Error> {
+        let request = reqwest::Client::new().get(format!("http://{}/events", addr));
+        send_with_retry(request, fast_policy()).await
+    }
+
+    #[tokio::test]
+    async fn retries_too_many_requests_with_retry_after() {
+        let (addr, count) = spawn_mock_server(2, 429, Some("0"), "");
+
+        let result = send(addr).await;
+
+        assert!(result.is_ok());
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_when_retry_after_exceeds_max_delay() {
+        let (addr, count) = spawn_mock_server(2, 429, Some("120"), "");
+
+        let result = send(addr).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::RateLimited {
+                retry_after: Some(retry_after)
+            }) if retry_after == Duration::from_secs(120)
+        ));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn retries_rate_limit_exceeded() {
+        let (addr, count) = spawn_mock_server(1, 403, None, RATE_LIMIT_BODY);
+
+        let result = send(addr).await;
+
+        assert!(result.is_ok());
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn gives_up_server_error_after_max_attempts() {
+        let (addr, count) = spawn_mock_server(10, 503, None, "");
+
+        let result = send(addr).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::Server(StatusCode::SERVICE_UNAVAILABLE))
+        ));
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_forbidden_or_unauthorized() {
+        let (addr, count) = spawn_mock_server(10, 403, None, FORBIDDEN_BODY);
+        assert!(matches!(send(addr).await, Err(Error::Forbidden(_))));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        let (addr, count) = spawn_mock_server(10, 401, None, "");
+        assert!(matches!(send(addr).await, Err(Error::Unauthorized)));
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = chrono::DateTime::parse_from_rfc3339("2024-08-01T00:00:00Z")
+            .unwrap()
+            .to_utc();
+
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Thu, 01 Aug 2024 00:00:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 31 Jul 2024 23:59:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("invalid", now), None);
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+        };
+
+        assert_eq!(
+            backoff_delay(&policy, 1, Some(Duration::from_millis(200))),
+            Duration::from_millis(200)
+        );
+        assert_eq!(
+            backoff_delay(&policy, 1, Some(Duration::from_secs(3))),
+            policy.max_delay
+        );
+        for attempt in 1..10 {
+            assert!(backoff_delay(&policy, attempt, None) <= policy.max_delay);
+        }
+        assert!(backoff_delay(&policy, 1, None) <= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,118 @@
+use std::{fmt, str::FromStr};
+
+use crate::{env::Env, repository::models::OAuthToken};
+
+use super::{
+    authorization_headers,
+    retry::{send_with_retry, RetryPolicy},
+    Error, GoogleCalendarEvent, PRIMARY_CALENDAR_ID,
+};
+
+/// 予定の変更に必要なスコープ
+pub const EVENTS_SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+const CALENDAR_SCOPE: &str = "https://www.googleapis.com/auth/calendar";
+
+/// 自分の出欠
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseStatus {
+    Accepted,
+    Declined,
+    Tentative,
+}
+impl ResponseStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResponseStatus::Accepted => "accepted",
+            ResponseStatus::Declined => "declined",
+            ResponseStatus::Tentative => "tentative",
+        }
+    }
+}
+impl fmt::Display for ResponseStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+impl FromStr for ResponseStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "accepted" | "accept" => Ok(ResponseStatus::Accepted),
+            "declined" | "decline" => Ok(ResponseStatus::Declined),
+            "tentative" => Ok(ResponseStatus::Tentative),
+            _ => Err(format!(
+                "{} is invalid. Use accept, decline or tentative",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * トークンに予定を変更できるスコープが含まれるか。スコープが保存されていない場合は試してみる
+ *
+ * ```
+ * use calendar_notice::{google_calendar::rsvp::has_events_scope, repository::models::OAuthToken};
+ *
+ * let token = |scope: &str| OAuthToken {
+ *     scope: Some(scope.to_string()),
+ *     ..Default::default()
+ * };
+ * assert!(has_events_scope(&token("https://www.googleapis.com/auth/calendar.events")));
+ * assert!(!has_events_scope(&token("https://www.googleapis.com/auth/calendar.readonly")));
+ * ```
+ */
+pub fn has_events_scope(oauth_token: &OAuthToken) -> bool {
+    match &oauth_token.scope {
+        Some(scope) => scope
+            .split_whitespace()
+            .any(|scope| scope == EVENTS_SCOPE || scope == CALENDAR_SCOPE),
+        None => true,
+    }
+}
+
+/**
+ * 自分の出欠を変更する。
+ * attendees は配列ごと置き換わるため、最新のイベントを取得して自分の responseStatus だけを書き換えて送る
+ */
+pub async fn respond_to_event(
+    access_token: &str,
+    event_id: &str,
+    status: ResponseStatus,
+) -> Result<GoogleCalendarEvent, Error> {
+    let url = format!(
+        "{}/calendar/v3/calendars/{}/events/{}",
+        Env::new().google_api_base_url,
+        PRIMARY_CALENDAR_ID,
+        event_id
+    );
+    let client = reqwest::Client::new();
+    let headers = authorization_headers(access_token)?;
+
+    let request = client.get(&url).headers(headers.clone());
+    let text = send_with_retry(request, RetryPolicy::default())
+        .await?
+        .text()
+        .await?;
+    let event: serde_json::Value =
+        serde_json::from_str(&text).map_err(|e| Error::Parse(e.to_string()))?;
+
+    let mut attendees = event["attendees"].as_array().cloned().unwrap_or_default();
+    let self_attendee = attendees
+        .iter_mut()
+        .find(|attendee| attendee["self"].as_bool() == Some(true))
+        .ok_or(Error::NotAttendee)?;
+    self_attendee["responseStatus"] = status.as_str().into();
+
+    let request = client
+        .patch(&url)
+        .headers(headers)
+        .query(&[("sendUpdates", "all")])
+        .json(&serde_json::json!({ "attendees": attendees }));
+    let text = send_with_retry(request, RetryPolicy::default())
+        .await?
+        .text()
+        .await?;
+    serde_json::from_str(&text).map_err(|e| Error::Parse(e.to_string()))
+}
@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use warp::{http::StatusCode, Filter};
+
+use crate::{
+    env::Env,
+    repository::{self, models::OAuthToken, models::WatchChannel},
+    sync::{SyncCommand, SyncHandle},
+};
+
+use super::{
+    authorization_headers,
+    retry::{classify_response, send_with_retry, RetryPolicy},
+    Error, PRIMARY_CALENDAR_ID,
+};
+
+/// Google からの変更通知を受け取るパス
+pub const WATCH_PATH: &str = "notifications";
+/// チャンネルの有効期間。Google 側の上限は 7 日
+const CHANNEL_TTL_SEC: i64 = 60 * 60 * 24 * 7;
+/// 期限切れの少し前に登録し直す
+const RENEW_MARGIN_SEC: i64 = 60 * 60;
+
+#[derive(Debug, Serialize)]
+struct WatchRequest {
+    id: String,
+    #[serde(rename = "type")]
+    type_: String,
+    address: String,
+    token: String,
+    params: WatchParams,
+}
+
+#[derive(Debug, Serialize)]
+struct WatchParams {
+    ttl: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WatchResponse {
+    id: String,
+    resource_id: String,
+    resource_uri: Option<String>,
+    /// ミリ秒単位の UNIX 時刻
+    expiration: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StopRequest {
+    id: String,
+    resource_id: String,
+}
+
+/**
+ * events.watch でチャンネルを登録する。変更があると address に通知が届く
+ */
+pub async fn watch_events(access_token: &str, address: &str) -> Result<WatchChannel, Error> {
+    let url = format!(
+        "{}/calendar/v3/calendars/{}/events/watch",
+        Env::new().google_api_base_url,
+        PRIMARY_CALENDAR_ID
+    );
+    let token = uuid::Uuid::new_v4().to_string();
+    let body = WatchRequest {
+        id: uuid::Uuid::new_v4().to_string(),
+        type_: "web_hook".to_string(),
+        address: address.to_string(),
+        token: token.clone(),
+        params: WatchParams {
+            ttl: CHANNEL_TTL_SEC.to_string(),
+        },
+    };
+
+    let request = reqwest::Client::new()
+        .post(url)
+        .headers(authorization_headers(access_token)?)
+        .json(&body);
+    // 再送するとチャンネルが重複して登録され、停止できなくなるため 1 回だけ送る
+    let response = classify_response(request.send().await?).await?;
+    let text = response.text().await?;
+    let watch_response: WatchResponse =
+        serde_json::from_str(&text).map_err(|e| Error::Parse(e.to_string()))?;
+
+    let now = chrono::Utc::now();
+    let expiration = watch_response
+        .expiration
+        .and_then(|ms| ms.parse::<i64>().ok())
+        .and_then(chrono::DateTime::from_timestamp_millis)
+        .unwrap_or(now + chrono::Duration::seconds(CHANNEL_TTL_SEC));
+
+    Ok(WatchChannel {
+        id: watch_response.id,
+        calendar_id: PRIMARY_CALENDAR_ID.to_string(),
+        resource_id: watch_response.resource_id,
+        resource_uri: watch_response.resource_uri,
+        token,
+        expiration,
+        created_at: now,
+    })
+}
+
+/**
+ * channels.stop でチャンネルを停止する
+ */
+pub async fn stop_channel(access_token: &str, channel: &WatchChannel) -> Result<(), Error> {
+    let url = format!(
+        "{}/calendar/v3/channels/stop",
+        Env::new().google_api_base_url
+    );
+    let request = reqwest::Client::new()
+        .post(url)
+        .headers(authorization_headers(access_token)?)
+        .json(&StopRequest {
+            id: channel.id.clone(),
+            resource_id: channel.resource_id.clone(),
+        });
+    send_with_retry(request, RetryPolicy::default()).await?;
+    Ok(())
+}
+
+/**
+ * WEBHOOK_ADDRESS が設定されている場合、有効なチャンネルがなければ登録し、古いチャンネルは停止する
+ */
+pub async fn ensure_watch_channel(oauth_token: &OAuthToken) -> Result<(), Error> {
+    let Some(address) = Env::new().webhook_address else {
+        return Ok(());
+    };
+
+    let renew_at = chrono::Utc::now() + chrono::Duration::seconds(RENEW_MARGIN_SEC);
+    let active_channel = repository::watch_channel::find_active(PRIMARY_CALENDAR_ID, renew_at)
+        .map_err(|e| Error::Parse(e.to_string()))?;
+    if active_channel.is_some() {
+        return Ok(());
+    }
+
+    let old_channels = repository::watch_channel::find_many(PRIMARY_CALENDAR_ID)
+        .map_err(|e| Error::Parse(e.to_string()))?;
+
+    let channel = watch_events(&oauth_token.access_token, &address).await?;
+    repository::watch_channel::create(channel).map_err(|e| Error::Parse(e.to_string()))?;
+
+    for old_channel in old_channels {
+        // 期限切れで既に停止している場合もあるため、失敗しても削除する
+        stop_channel(&oauth_token.access_token, &old_channel)
+            .await
+            .unwrap_or_else(|e| println!("Failed to stop channel {}: {:?}", old_channel.id, e));
+        repository::watch_channel::delete(&old_channel.id)
+            .unwrap_or_else(|e| println!("Failed to delete channel {}: {:?}", old_channel.id, e));
+    }
+
+    Ok(())
+}
+
+pub fn routes(
+    sync_handle: SyncHandle,
+) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::post()
+        .and(warp::path(WATCH_PATH))
+        .and(warp::path::end())
+        .and(warp::header::<String>("x-goog-channel-id"))
+        .and(warp::header::optional::<String>("x-goog-channel-token"))
+        .and(warp::header::<String>("x-goog-resource-state"))
+        .and(warp::any().map(move || sync_handle.clone()))
+        .and_then(handle_notification)
+}
+
+/**
+ * 変更通知を受け取り、登録済みのチャンネルからであれば同期ループに差分同期を要求する。
+ * Google は素早い応答を期待するため、同期の完了は待たない
+ */
+async fn handle_notification(
+    channel_id: String,
+    channel_token: Option<String>,
+    resource_state: String,
+    sync_handle: SyncHandle,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let channel = match repository::watch_channel::find(&channel_id) {
+        Ok(Some(channel)) => channel,
+        Ok(None) => {
+            return Ok(warp::reply::with_status(
+                "Unknown channel",
+                StatusCode::NOT_FOUND,
+            ))
+        }
+        Err(e) => {
+            println!("Failed to find watch channel: {:?}", e);
+            return Ok(warp::reply::with_status(
+                "Failed to find channel",
+                StatusCode::INTERNAL_SERVER_ERROR,
+            ));
+        }
+    };
+    if channel_token.as_deref() != Some(channel.token.as_str()) {
+        return Ok(warp::reply::with_status(
+            "Invalid token",
+            StatusCode::FORBIDDEN,
+        ));
+    }
+
+    // 登録直後の疎通確認
+    if resource_state == "sync" {
+        return Ok(warp::reply::with_status("Ok", StatusCode::OK));
+    }
+
+    if let Err(e) = sync_handle.request(SyncCommand::SyncChanges) {
+        println!("Failed to request sync in handle_notification: {}", e);
+    }
+
+    Ok(warp::reply::with_status("Ok", StatusCode::OK))
+}
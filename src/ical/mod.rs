@@ -0,0 +1,977 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::{
+    provider::CalendarEvent,
+    repository::models::{Attendee, EventResource},
+    timezone::local_to_utc,
+};
+
+pub mod rrule;
+use self::rrule::RecurrenceRule;
+
+/**
+ * iCalendar(RFC 5545)のコンポーネント。VCALENDAR / VEVENT / VALARM など
+ */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Component {
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+impl Component {
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|property| property.name == name)
+    }
+
+    pub fn properties_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Property> {
+        self.properties
+            .iter()
+            .filter(move |property| property.name == name)
+    }
+
+    pub fn components_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Component> {
+        self.components
+            .iter()
+            .filter(move |component| component.name == name)
+    }
+
+    /// 値をエスケープ解除した文字列
+    pub fn text(&self, name: &str) -> Option<String> {
+        self.property(name).map(Property::text)
+    }
+
+    /**
+     * iCalendar の文字列にする。行は CRLF で区切り、75 オクテットを超える行は折り返す
+     *
+     * ```
+     * use calendar_notice::ical::{parse, Component, Property};
+     *
+     * let calendar = Component {
+     *     name: "VCALENDAR".to_string(),
+     *     properties: vec![Property::new("VERSION", "2.0")],
+     *     components: vec![Component {
+     *         name: "VEVENT".to_string(),
+     *         properties: vec![Property::text_value("SUMMARY", &"定例".repeat(30))],
+     *         components: vec![],
+     *     }],
+     * };
+     * let text = calendar.to_ical_string();
+     * assert!(text.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\n"));
+     * assert!(text.lines().all(|line| line.len() <= 75));
+     * assert_eq!(parse(&text).unwrap(), vec![calendar]);
+     * ```
+     */
+    pub fn to_ical_string(&self) -> String {
+        let mut text = String::new();
+        self.write_to(&mut text);
+        text
+    }
+
+    fn write_to(&self, text: &mut String) {
+        text.push_str(&fold(&format!("BEGIN:{}", self.name)));
+        for property in &self.properties {
+            text.push_str(&fold(&property.to_line()));
+        }
+        for component in &self.components {
+            component.write_to(text);
+        }
+        text.push_str(&fold(&format!("END:{}", self.name)));
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Property {
+    pub name: String,
+    /// パラメーター名は大文字。値の引用符は外す
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+impl Property {
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn text(&self) -> String {
+        unescape_text(&self.value)
+    }
+
+    pub fn new(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            params: vec![],
+            value: value.to_string(),
+        }
+    }
+
+    /// TEXT の値を持つプロパティ。値はエスケープする
+    pub fn text_value(name: &str, text: &str) -> Self {
+        Self::new(name, &escape_text(text))
+    }
+
+    pub fn with_param(mut self, name: &str, value: &str) -> Self {
+        self.params.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    fn to_line(&self) -> String {
+        let mut line = self.name.clone();
+        for (name, value) in &self.params {
+            if value.contains([':', ';', ',']) {
+                line.push_str(&format!(";{}=\"{}\"", name, value));
+            } else {
+                line.push_str(&format!(";{}={}", name, value));
+            }
+        }
+        line.push(':');
+        line.push_str(&self.value);
+        line
+    }
+}
+
+/**
+ * iCalendar の文字列をコンポーネントに分解する。BEGIN / END の対応が取れない場合は Err
+ *
+ * ```
+ * use calendar_notice::ical::parse;
+ *
+ * let text = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:1\r\nSUMMARY:Design \r\n review\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+ * let calendars = parse(text).unwrap();
+ * let event = calendars[0].components_named("VEVENT").next().unwrap();
+ * assert_eq!(event.text("SUMMARY"), Some("Design review".to_string()));
+ *
+ * assert!(parse("BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nEND:VCALENDAR").is_err());
+ * ```
+ */
+pub fn parse(text: &str) -> Result<Vec<Component>, String> {
+    let mut roots: Vec<Component> = vec![];
+    let mut stack: Vec<Component> = vec![];
+    for line in unfold(text) {
+        let Some(property) = parse_line(&line) else {
+            continue;
+        };
+        match property.name.as_str() {
+            "BEGIN" => stack.push(Component {
+                name: property.value.to_uppercase(),
+                ..Default::default()
+            }),
+            "END" => {
+                let component = stack
+                    .pop()
+                    .ok_or(format!("Unexpected END:{}", property.value))?;
+                if component.name != property.value.to_uppercase() {
+                    return Err(format!(
+                        "END:{} does not match BEGIN:{}",
+                        property.value, component.name
+                    ));
+                }
+                match stack.last_mut() {
+                    Some(parent) => parent.components.push(component),
+                    None => roots.push(component),
+                }
+            }
+            _ => {
+                if let Some(component) = stack.last_mut() {
+                    component.properties.push(property);
+                }
+            }
+        }
+    }
+    match stack.pop() {
+        Some(component) => Err(format!("BEGIN:{} is not closed", component.name)),
+        None => Ok(roots),
+    }
+}
+
+/// 空白で始まる行は前の行の続き
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// NAME;PARAM=VALUE;PARAM="VALUE":VALUE を分解する
+fn parse_line(line: &str) -> Option<Property> {
+    let mut in_quotes = false;
+    let mut segments: Vec<&str> = vec![];
+    let mut segment_start = 0;
+    let mut value_start = None;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => {
+                segments.push(&line[segment_start..i]);
+                segment_start = i + 1;
+            }
+            ':' if !in_quotes => {
+                segments.push(&line[segment_start..i]);
+                value_start = Some(i + 1);
+                break;
+            }
+            _ => {}
+        }
+    }
+    let value = &line[value_start?..];
+    let (name, params) = segments.split_first()?;
+    if name.is_empty() {
+        return None;
+    }
+    Some(Property {
+        name: name.to_uppercase(),
+        params: params
+            .iter()
+            .filter_map(|param| param.split_once('='))
+            .map(|(key, value)| (key.to_uppercase(), value.trim_matches('"').to_string()))
+            .collect(),
+        value: value.to_string(),
+    })
+}
+
+/**
+ * TEXT の値のエスケープを外す
+ *
+ * ```
+ * use calendar_notice::ical::unescape_text;
+ *
+ * assert_eq!(unescape_text(r"Room A\, 3F\nhttps://zoom.us/j/1\\2"), "Room A, 3F\nhttps://zoom.us/j/1\\2");
+ * ```
+ */
+pub fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => text.push('\n'),
+            Some(escaped) => text.push(escaped),
+            None => text.push('\\'),
+        }
+    }
+    text
+}
+
+/**
+ * TEXT の値をエスケープする
+ *
+ * ```
+ * use calendar_notice::ical::escape_text;
+ *
+ * assert_eq!(escape_text("Room A, 3F\nhttps://zoom.us/j/1\\2"), r"Room A\, 3F\nhttps://zoom.us/j/1\\2");
+ * ```
+ */
+pub fn escape_text(text: &str) -> String {
+    let mut value = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                value.push('\\');
+                value.push(c);
+            }
+            '\n' => value.push_str("\\n"),
+            '\r' => {}
+            _ => value.push(c),
+        }
+    }
+    value
+}
+
+/// 75 オクテットを超える行を、文字の途中で切らないように折り返す
+fn fold(line: &str) -> String {
+    const MAX_OCTETS: usize = 75;
+    let mut folded = String::with_capacity(line.len() + 2);
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > MAX_OCTETS {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// 日時の値の時刻がどのタイムゾーンのものか
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ValueTimeZone {
+    Utc,
+    Tz(Tz),
+    /// システムのタイムゾーン
+    Local,
+}
+
+/**
+ * DTSTART や EXDATE などの日時の値。繰り返しの展開は UTC ではなく、この時刻のまま行う
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateTimeValue {
+    /// 日付だけの場合は 0 時
+    pub naive: NaiveDateTime,
+    /// 日付だけの値
+    pub all_day: bool,
+    time_zone: ValueTimeZone,
+}
+impl DateTimeValue {
+    /**
+     * Z のない日時と日付は TZID(IANA 名でなければ default_time_zone、それもなければシステムのタイムゾーン)の時刻として扱う
+     */
+    pub fn parse(
+        value: &str,
+        property: &Property,
+        default_time_zone: Option<Tz>,
+    ) -> Option<DateTimeValue> {
+        let time_zone = match property
+            .param("TZID")
+            .and_then(|tzid| tzid.parse::<Tz>().ok())
+            .or(default_time_zone)
+        {
+            Some(tz) => ValueTimeZone::Tz(tz),
+            None => ValueTimeZone::Local,
+        };
+
+        let value = value.trim();
+        if property.param("VALUE") == Some("DATE") || value.len() == 8 {
+            let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+            return Some(DateTimeValue {
+                naive: date.and_time(NaiveTime::MIN),
+                all_day: true,
+                time_zone,
+            });
+        }
+        if let Some(utc) = value.strip_suffix('Z') {
+            return Some(DateTimeValue {
+                naive: NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?,
+                all_day: false,
+                time_zone: ValueTimeZone::Utc,
+            });
+        }
+        Some(DateTimeValue {
+            naive: NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?,
+            all_day: false,
+            time_zone,
+        })
+    }
+
+    /// 同じタイムゾーンの別の時刻
+    pub fn with_naive(&self, naive: NaiveDateTime) -> DateTimeValue {
+        DateTimeValue { naive, ..*self }
+    }
+
+    pub fn to_utc(&self) -> DateTime<Utc> {
+        match self.time_zone {
+            ValueTimeZone::Utc => Utc.from_utc_datetime(&self.naive),
+            ValueTimeZone::Tz(tz) => local_to_utc(self.naive, &tz),
+            ValueTimeZone::Local => local_to_utc(self.naive, &chrono::Local),
+        }
+    }
+}
+
+/**
+ * DTSTART などの日時を UTC にする。日付だけの場合は終日として true を返す。
+ * Z のない日時と日付は TZID(IANA 名でなければ default_time_zone、それもなければシステムのタイムゾーン)の時刻として扱う
+ */
+pub fn parse_date_time(
+    property: &Property,
+    default_time_zone: Option<Tz>,
+) -> Option<(DateTime<Utc>, bool)> {
+    DateTimeValue::parse(&property.value, property, default_time_zone)
+        .map(|value| (value.to_utc(), value.all_day))
+}
+
+/// EXDATE や RDATE のようにカンマ区切りで複数の日時を持つ値。読めない値は除く
+fn parse_date_time_list(property: &Property, default_time_zone: Option<Tz>) -> Vec<DateTimeValue> {
+    property
+        .value
+        .split(',')
+        .filter_map(|value| DateTimeValue::parse(value, property, default_time_zone))
+        .collect()
+}
+
+/**
+ * DURATION や TRIGGER の期間を読む
+ *
+ * ```
+ * use calendar_notice::ical::parse_duration;
+ *
+ * assert_eq!(parse_duration("PT1H30M"), Some(chrono::Duration::minutes(90)));
+ * assert_eq!(parse_duration("-PT15M"), Some(chrono::Duration::minutes(-15)));
+ * assert_eq!(parse_duration("P1W"), Some(chrono::Duration::weeks(1)));
+ * assert_eq!(parse_duration("P1DT12H"), Some(chrono::Duration::hours(36)));
+ * assert_eq!(parse_duration("1H"), None);
+ * ```
+ */
+pub fn parse_duration(value: &str) -> Option<chrono::Duration> {
+    let value = value.trim();
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let value = value.strip_prefix('P')?;
+
+    let mut seconds: i64 = 0;
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in value.chars() {
+        match c {
+            '0'..='9' => number.push(c),
+            'T' => in_time = true,
+            unit => {
+                let n: i64 = number.parse().ok()?;
+                number.clear();
+                seconds += n * match (unit, in_time) {
+                    ('W', false) => 60 * 60 * 24 * 7,
+                    ('D', false) => 60 * 60 * 24,
+                    ('H', true) => 60 * 60,
+                    ('M', true) => 60,
+                    ('S', true) => 1,
+                    _ => return None,
+                };
+            }
+        }
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    Some(chrono::Duration::seconds(if negative {
+        -seconds
+    } else {
+        seconds
+    }))
+}
+
+/// mailto: を外したメールアドレス
+fn email_of(value: &str) -> String {
+    let value = value.trim();
+    value
+        .strip_prefix("mailto:")
+        .or_else(|| value.strip_prefix("MAILTO:"))
+        .unwrap_or(value)
+        .to_string()
+}
+
+/// PARTSTAT を Google Calendar の responseStatus に合わせる
+fn response_status_of(partstat: &str) -> Option<String> {
+    match partstat.to_uppercase().as_str() {
+        "ACCEPTED" => Some("accepted".to_string()),
+        "DECLINED" => Some("declined".to_string()),
+        "TENTATIVE" => Some("tentative".to_string()),
+        "NEEDS-ACTION" => Some("needsAction".to_string()),
+        _ => None,
+    }
+}
+
+/**
+ * VALARM のうち最も早く鳴るものが、開始の何秒前か。メールで知らせるものと、開始後に鳴るものは除く
+ */
+fn reminder_sec_from_start(
+    event: &Component,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    default_time_zone: Option<Tz>,
+) -> Option<i32> {
+    event
+        .components_named("VALARM")
+        .filter(|alarm| {
+            alarm
+                .text("ACTION")
+                .is_none_or(|action| !action.eq_ignore_ascii_case("EMAIL"))
+        })
+        .filter_map(|alarm| {
+            let trigger = alarm.property("TRIGGER")?;
+            let alarm_at = if trigger.param("VALUE") == Some("DATE-TIME") {
+                parse_date_time(trigger, default_time_zone)?.0
+            } else {
+                let related = if trigger.param("RELATED") == Some("END") {
+                    end
+                } else {
+                    start
+                };
+                related + parse_duration(&trigger.value)?
+            };
+            i32::try_from((start - alarm_at).num_seconds()).ok()
+        })
+        .filter(|sec_from_start| *sec_from_start >= 0)
+        .max()
+}
+
+/**
+ * VEVENT をプロバイダーに依存しないイベントにする。開始日時が読めない場合は None。
+ * self_email は参加者のうち自分を判定するためのメールアドレス
+ */
+pub fn to_calendar_event(
+    event: &Component,
+    id: String,
+    recurring_event_id: Option<String>,
+    default_time_zone: Option<Tz>,
+    self_email: Option<&str>,
+) -> Option<CalendarEvent> {
+    let dtstart = event
+        .property("DTSTART")
+        .and_then(|dtstart| DateTimeValue::parse(&dtstart.value, dtstart, default_time_zone))?;
+    let (start, all_day) = (dtstart.to_utc(), dtstart.all_day);
+    let end = match (event.property("DTEND"), event.property("DURATION")) {
+        (Some(dtend), _) => parse_date_time(dtend, default_time_zone).map(|(end, _)| end),
+        (None, Some(duration)) => parse_duration(&duration.value).map(|duration| start + duration),
+        // 終了がない場合、終日の予定は 1 日、それ以外は開始と同時に終わる
+        (None, None) if all_day => Some(start + chrono::Duration::days(1)),
+        (None, None) => Some(start),
+    }?;
+    let start_date = all_day.then(|| dtstart.naive.date());
+
+    let organizer = event
+        .property("ORGANIZER")
+        .map(|organizer| email_of(&organizer.value));
+    let attendees = event
+        .properties_named("ATTENDEE")
+        .map(|attendee| {
+            let email = email_of(&attendee.value);
+            Attendee {
+                event_id: id.clone(),
+                display_name: attendee.param("CN").map(str::to_string),
+                organizer: organizer.as_ref() == Some(&email),
+                is_self: self_email
+                    .is_some_and(|self_email| self_email.eq_ignore_ascii_case(&email)),
+                response_status: attendee.param("PARTSTAT").and_then(response_status_of),
+                email,
+            }
+        })
+        .collect();
+    // 添付ファイルはリンクのものだけを扱う
+    let attachments = event
+        .properties_named("ATTACH")
+        .filter(|attach| attach.param("VALUE") != Some("BINARY"))
+        .map(|attach| EventResource {
+            source: "attachment".to_string(),
+            url: attach.value.clone(),
+            title: attach
+                .param("FILENAME")
+                .or(attach.param("X-FILENAME"))
+                .map(str::to_string),
+            mime_type: attach.param("FMTTYPE").map(str::to_string),
+            ..Default::default()
+        })
+        .collect();
+
+    Some(CalendarEvent {
+        summary: event.text("SUMMARY"),
+        description: event.text("DESCRIPTION"),
+        status: Some(
+            event
+                .text("STATUS")
+                .map(|status| status.to_lowercase())
+                .unwrap_or("confirmed".to_string()),
+        ),
+        start,
+        end,
+        all_day,
+        start_date,
+        end_date: start_date.map(|date| date + chrono::Duration::days(whole_days(end - start))),
+        original_start_time: event
+            .property("RECURRENCE-ID")
+            .and_then(|recurrence_id| parse_date_time(recurrence_id, default_time_zone))
+            .map(|(original_start_time, _)| original_start_time),
+        recurring_event_id,
+        location: event.text("LOCATION"),
+        event_type: Some("default".to_string()),
+        reminder_sec_from_start: reminder_sec_from_start(event, start, end, default_time_zone),
+        attendees,
+        attachments,
+        id,
+        ..Default::default()
+    })
+}
+
+/**
+ * カレンダーの予定を、range に重なるものだけイベントにする。
+ * 繰り返しの予定は RRULE / RDATE / EXDATE で展開し、RECURRENCE-ID のある VEVENT でその回を置き換える。
+ * イベントの ID は id_prefix と UID、繰り返しのインスタンスはさらに #開始日時(UTC)を付けたもの。キャンセルされたものは除く
+ */
+pub fn to_calendar_events(
+    calendars: &[Component],
+    id_prefix: &str,
+    range: (DateTime<Utc>, DateTime<Utc>),
+    default_time_zone: Option<Tz>,
+    self_email: Option<&str>,
+) -> Vec<CalendarEvent> {
+    // UID ごとに、繰り返しの元の VEVENT と、置き換える回の VEVENT に分ける
+    let mut series: BTreeMap<String, (Option<&Component>, Vec<&Component>)> = BTreeMap::new();
+    for vevent in calendars
+        .iter()
+        .flat_map(|calendar| calendar.components_named("VEVENT"))
+    {
+        let Some(uid) = vevent.text("UID") else {
+            continue;
+        };
+        let entry = series.entry(uid).or_default();
+        match vevent.property("RECURRENCE-ID") {
+            Some(_) => entry.1.push(vevent),
+            None => entry.0 = Some(vevent),
+        }
+    }
+
+    let mut events = vec![];
+    for (uid, (master, overrides)) in series {
+        let base_id = format!("{}{}", id_prefix, uid);
+        let is_recurring = !overrides.is_empty()
+            || master.is_some_and(|master| {
+                master.property("RRULE").is_some() || master.property("RDATE").is_some()
+            });
+        if !is_recurring {
+            events.extend(master.and_then(|master| {
+                to_calendar_event(master, base_id, None, default_time_zone, self_email)
+            }));
+            continue;
+        }
+
+        let mut instances: BTreeMap<String, CalendarEvent> = BTreeMap::new();
+        if let Some(master) = master {
+            for (id, event) in
+                expand_recurrence(master, &base_id, range.1, default_time_zone, self_email)
+            {
+                instances.insert(id, event);
+            }
+        }
+        for vevent in overrides {
+            let Some((original_start, _)) = vevent
+                .property("RECURRENCE-ID")
+                .and_then(|recurrence_id| parse_date_time(recurrence_id, default_time_zone))
+            else {
+                continue;
+            };
+            let id = instance_id(&base_id, original_start);
+            if let Some(event) = to_calendar_event(
+                vevent,
+                id.clone(),
+                Some(base_id.clone()),
+                default_time_zone,
+                self_email,
+            ) {
+                instances.insert(id, event);
+            }
+        }
+        events.extend(instances.into_values());
+    }
+
+    events
+        .into_iter()
+        .filter(|event| event.status.as_deref() != Some("cancelled"))
+        .filter(|event| event.end > range.0 && event.start < range.1)
+        .collect()
+}
+
+/// 繰り返しのインスタンスの ID
+fn instance_id(base_id: &str, original_start: DateTime<Utc>) -> String {
+    format!("{}#{}", base_id, original_start.format("%Y%m%dT%H%M%SZ"))
+}
+
+/// 繰り返しの予定の、end より前に始まる回。ID とイベントの組
+fn expand_recurrence(
+    master: &Component,
+    base_id: &str,
+    end: DateTime<Utc>,
+    default_time_zone: Option<Tz>,
+    self_email: Option<&str>,
+) -> Vec<(String, CalendarEvent)> {
+    let Some(start) = master
+        .property("DTSTART")
+        .and_then(|dtstart| DateTimeValue::parse(&dtstart.value, dtstart, default_time_zone))
+    else {
+        return vec![];
+    };
+    let Some(template) = to_calendar_event(
+        master,
+        base_id.to_string(),
+        None,
+        default_time_zone,
+        self_email,
+    ) else {
+        return vec![];
+    };
+
+    let mut occurrences = match master.property("RRULE") {
+        Some(rrule) => match rrule.value.parse::<RecurrenceRule>() {
+            Ok(rule) => rule.occurrences(&start, end),
+            Err(e) => {
+                println!("Skip invalid RRULE of {}: {}", base_id, e);
+                vec![start]
+            }
+        },
+        None => vec![start],
+    };
+    occurrences.extend(
+        master
+            .properties_named("RDATE")
+            .flat_map(|rdate| parse_date_time_list(rdate, default_time_zone))
+            .filter(|rdate| rdate.to_utc() < end),
+    );
+    let excluded: Vec<DateTime<Utc>> = master
+        .properties_named("EXDATE")
+        .flat_map(|exdate| parse_date_time_list(exdate, default_time_zone))
+        .map(|exdate| exdate.to_utc())
+        .collect();
+
+    let duration = template.end - template.start;
+    let days = whole_days(duration);
+    occurrences
+        .into_iter()
+        .filter(|occurrence| !excluded.contains(&occurrence.to_utc()))
+        .map(|occurrence| {
+            let start = occurrence.to_utc();
+            // 終日の予定は夏時間の切り替えをまたいでも日付単位で終わる
+            let end = if template.all_day {
+                occurrence
+                    .with_naive(occurrence.naive + chrono::Duration::days(days))
+                    .to_utc()
+            } else {
+                start + duration
+            };
+            let id = instance_id(base_id, start);
+            let event = CalendarEvent {
+                id: id.clone(),
+                recurring_event_id: Some(base_id.to_string()),
+                original_start_time: Some(start),
+                start,
+                end,
+                start_date: template.all_day.then(|| occurrence.naive.date()),
+                end_date: template
+                    .all_day
+                    .then(|| occurrence.naive.date() + chrono::Duration::days(days)),
+                attendees: template
+                    .attendees
+                    .iter()
+                    .map(|attendee| Attendee {
+                        event_id: id.clone(),
+                        ..attendee.clone()
+                    })
+                    .collect(),
+                ..template.clone()
+            };
+            (id, event)
+        })
+        .collect()
+}
+
+/// 終日の予定の日数。夏時間の切り替えをまたぐと 23・25 時間になるため丸める
+fn whole_days(duration: chrono::Duration) -> i64 {
+    (duration.num_hours() as f64 / 24.0).round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEETING: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+UID:meeting@example.com\r
+DTSTART;TZID=Asia/Tokyo:20261020T100000\r
+DURATION:PT45M\r
+SUMMARY:Design review\r
+DESCRIPTION:Join: https://zoom.us/j/95428352872\\nAgenda: https://example.c\r
+ om/agenda\r
+LOCATION:Room A\\, 3F\r
+ORGANIZER;CN=Boss:mailto:boss@example.com\r
+ATTENDEE;CN=Boss;PARTSTAT=ACCEPTED:mailto:boss@example.com\r
+ATTENDEE;CN=\"Me: myself\";PARTSTAT=NEEDS-ACTION:mailto:me@example.com\r
+ATTACH;FMTTYPE=application/pdf;FILENAME=slides.pdf:https://example.com/slides.pdf\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn test_to_calendar_event() {
+        let calendars = parse(MEETING).unwrap();
+        let vevent = calendars[0].components_named("VEVENT").next().unwrap();
+        let event = to_calendar_event(vevent, "id".to_string(), None, None, Some("ME@example.com"))
+            .unwrap();
+
+        assert_eq!(event.summary.as_deref(), Some("Design review"));
+        assert_eq!(
+            event.description.as_deref(),
+            Some("Join: https://zoom.us/j/95428352872\nAgenda: https://example.com/agenda")
+        );
+        assert_eq!(event.location.as_deref(), Some("Room A, 3F"));
+        assert_eq!(event.start.to_rfc3339(), "2026-10-20T01:00:00+00:00");
+        assert_eq!(event.end.to_rfc3339(), "2026-10-20T01:45:00+00:00");
+        assert!(!event.all_day);
+        assert_eq!(event.status.as_deref(), Some("confirmed"));
+
+        assert_eq!(
+            event
+                .attendees
+                .iter()
+                .map(|a| (
+                    a.email.as_str(),
+                    a.display_name.as_deref(),
+                    a.organizer,
+                    a.is_self,
+                    a.response_status.as_deref()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "boss@example.com",
+                    Some("Boss"),
+                    true,
+                    false,
+                    Some("accepted")
+                ),
+                (
+                    "me@example.com",
+                    Some("Me: myself"),
+                    false,
+                    true,
+                    Some("needsAction")
+                ),
+            ]
+        );
+        assert_eq!(event.attachments.len(), 1);
+        assert_eq!(event.attachments[0].title.as_deref(), Some("slides.pdf"));
+        assert_eq!(
+            event.attachments[0].mime_type.as_deref(),
+            Some("application/pdf")
+        );
+    }
+
+    #[test]
+    fn test_parse_date_time() {
+        let property = |params: Vec<(&str, &str)>, value: &str| Property {
+            name: "DTSTART".to_string(),
+            params: params
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            value: value.to_string(),
+        };
+        let tokyo: Tz = "Asia/Tokyo".parse().unwrap();
+
+        let (utc, all_day) = parse_date_time(&property(vec![], "20261020T010000Z"), None).unwrap();
+        assert_eq!(utc.to_rfc3339(), "2026-10-20T01:00:00+00:00");
+        assert!(!all_day);
+
+        let (utc, all_day) =
+            parse_date_time(&property(vec![("VALUE", "DATE")], "20261020"), Some(tokyo)).unwrap();
+        assert_eq!(utc.to_rfc3339(), "2026-10-19T15:00:00+00:00");
+        assert!(all_day);
+
+        // IANA 名でない TZID は既定のタイムゾーンで読む
+        let (utc, _) = parse_date_time(
+            &property(vec![("TZID", "Tokyo Standard Time")], "20261020T100000"),
+            Some(tokyo),
+        )
+        .unwrap();
+        assert_eq!(utc.to_rfc3339(), "2026-10-20T01:00:00+00:00");
+
+        assert_eq!(parse_date_time(&property(vec![], "tomorrow"), None), None);
+    }
+
+    const WEEKLY: &str = "BEGIN:VCALENDAR\r
+BEGIN:VEVENT\r
+UID:weekly\r
+DTSTART;TZID=Asia/Tokyo:20261019T100000\r
+DTEND;TZID=Asia/Tokyo:20261019T103000\r
+RRULE:FREQ=WEEKLY;COUNT=5\r
+EXDATE;TZID=Asia/Tokyo:20261026T100000\r
+SUMMARY:Weekly sync\r
+BEGIN:VALARM\r
+ACTION:DISPLAY\r
+TRIGGER:-PT5M\r
+END:VALARM\r
+BEGIN:VALARM\r
+ACTION:EMAIL\r
+TRIGGER:-P1D\r
+END:VALARM\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:weekly\r
+RECURRENCE-ID;TZID=Asia/Tokyo:20261102T100000\r
+DTSTART;TZID=Asia/Tokyo:20261102T150000\r
+DTEND;TZID=Asia/Tokyo:20261102T153000\r
+SUMMARY:Weekly sync (moved)\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:weekly\r
+RECURRENCE-ID;TZID=Asia/Tokyo:20261109T100000\r
+DTSTART;TZID=Asia/Tokyo:20261109T100000\r
+STATUS:CANCELLED\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:holiday\r
+DTSTART;VALUE=DATE:20261103\r
+SUMMARY:Culture Day\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn test_to_calendar_events() {
+        let calendars = parse(WEEKLY).unwrap();
+        let range = (
+            "2026-10-20T00:00:00Z".parse().unwrap(),
+            "2026-12-01T00:00:00Z".parse().unwrap(),
+        );
+        let tokyo: Tz = "Asia/Tokyo".parse().unwrap();
+        let events = to_calendar_events(&calendars, "ics:", range, Some(tokyo), None);
+
+        // 範囲より前の回、EXDATE の回、キャンセルされた回は除く
+        assert_eq!(
+            events
+                .iter()
+                .map(|event| (
+                    event.id.as_str(),
+                    event.summary.as_deref().unwrap_or_default(),
+                    event.start.to_rfc3339()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "ics:holiday",
+                    "Culture Day",
+                    "2026-11-02T15:00:00+00:00".to_string()
+                ),
+                (
+                    "ics:weekly#20261102T010000Z",
+                    "Weekly sync (moved)",
+                    "2026-11-02T06:00:00+00:00".to_string()
+                ),
+                (
+                    "ics:weekly#20261116T010000Z",
+                    "Weekly sync",
+                    "2026-11-16T01:00:00+00:00".to_string()
+                ),
+            ]
+        );
+        let holiday = &events[0];
+        assert!(holiday.all_day);
+        assert_eq!(holiday.start_date, NaiveDate::from_ymd_opt(2026, 11, 3));
+        assert_eq!(holiday.end_date, NaiveDate::from_ymd_opt(2026, 11, 4));
+        assert_eq!(holiday.recurring_event_id, None);
+        assert_eq!(holiday.reminder_sec_from_start, None);
+
+        let instance = &events[2];
+        assert_eq!(instance.recurring_event_id.as_deref(), Some("ics:weekly"));
+        assert_eq!(
+            instance.original_start_time.map(|time| time.to_rfc3339()),
+            Some("2026-11-16T01:00:00+00:00".to_string())
+        );
+        assert_eq!(instance.end - instance.start, chrono::Duration::minutes(30));
+        // メールのリマインダーは除く
+        assert_eq!(instance.reminder_sec_from_start, Some(5 * 60));
+    }
+}
@@ -0,0 +1,469 @@
+use std::str::FromStr;
+
+use chrono::{
+    DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday,
+};
+
+use super::DateTimeValue;
+
+/// 該当する日がない規則(2 月 30 日など)で展開が終わらないよう、調べる期間の数の上限
+const MAX_PERIODS: i64 = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+/// UNTIL の値。展開する日時と同じタイムゾーンで比べる
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Until {
+    Utc(DateTime<Utc>),
+    Local(NaiveDateTime),
+}
+
+/**
+ * RRULE の値。BYHOUR など時刻単位の指定には対応しておらず、DTSTART の時刻で繰り返す
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub count: Option<u32>,
+    until: Option<Until>,
+    /// 序数(2MO の 2、-1FR の -1)と曜日
+    pub by_day: Vec<(Option<i32>, Weekday)>,
+    pub by_month_day: Vec<i32>,
+    pub by_month: Vec<u32>,
+    pub by_set_pos: Vec<i32>,
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut frequency = None;
+        let mut rule = RecurrenceRule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            count: None,
+            until: None,
+            by_day: vec![],
+            by_month_day: vec![],
+            by_month: vec![],
+            by_set_pos: vec![],
+        };
+        for part in value.trim().split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or(format!("Invalid RRULE part: {}", part))?;
+            let invalid = || format!("Invalid {}: {}", key, value);
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(format!("Unsupported FREQ: {}", value)),
+                    })
+                }
+                "INTERVAL" => {
+                    rule.interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| *interval > 0)
+                        .ok_or_else(invalid)?
+                }
+                "COUNT" => rule.count = Some(value.parse().map_err(|_| invalid())?),
+                "UNTIL" => rule.until = Some(parse_until(value).ok_or_else(invalid)?),
+                "BYDAY" => {
+                    rule.by_day = value
+                        .split(',')
+                        .map(parse_by_day)
+                        .collect::<Option<_>>()
+                        .ok_or_else(invalid)?
+                }
+                "BYMONTHDAY" => rule.by_month_day = parse_numbers(value).ok_or_else(invalid)?,
+                "BYMONTH" => rule.by_month = parse_numbers(value).ok_or_else(invalid)?,
+                "BYSETPOS" => rule.by_set_pos = parse_numbers(value).ok_or_else(invalid)?,
+                // WKST などは月曜始まりとして扱う
+                _ => {}
+            }
+        }
+        rule.frequency = frequency.ok_or("RRULE has no FREQ".to_string())?;
+        Ok(rule)
+    }
+}
+
+fn parse_until(value: &str) -> Option<Until> {
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Until::Utc(naive.and_utc()));
+    }
+    if value.len() == 8 {
+        // 日付だけの場合は、その日の最後まで
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some(Until::Local(date.and_hms_opt(23, 59, 59)?));
+    }
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .map(Until::Local)
+}
+
+fn parse_by_day(value: &str) -> Option<(Option<i32>, Weekday)> {
+    let value = value.trim();
+    let split = value.len().checked_sub(2)?;
+    let weekday = match &value.get(split..)?.to_uppercase()[..] {
+        "MO" => Weekday::Mon,
+        "TU" => Weekday::Tue,
+        "WE" => Weekday::Wed,
+        "TH" => Weekday::Thu,
+        "FR" => Weekday::Fri,
+        "SA" => Weekday::Sat,
+        "SU" => Weekday::Sun,
+        _ => return None,
+    };
+    let ordinal = match &value[..split] {
+        "" => None,
+        ordinal => Some(ordinal.trim_start_matches('+').parse().ok()?),
+    };
+    Some((ordinal, weekday))
+}
+
+fn parse_numbers<T: FromStr>(value: &str) -> Option<Vec<T>> {
+    value
+        .split(',')
+        .map(|number| number.trim().trim_start_matches('+').parse().ok())
+        .collect()
+}
+
+fn last_day_of_month(year: i32, month: u32) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(year, month, 1)?
+        .checked_add_months(Months::new(1))?
+        .pred_opt()
+}
+
+/// first 〜 last のうち by_day に当てはまる日。序数があれば期間内の何番目か(負の場合は後ろから)
+fn weekdays_between(
+    first: NaiveDate,
+    last: NaiveDate,
+    by_day: &[(Option<i32>, Weekday)],
+) -> Vec<NaiveDate> {
+    let mut dates = vec![];
+    for (ordinal, weekday) in by_day {
+        let matched: Vec<NaiveDate> = first
+            .iter_days()
+            .take_while(|date| *date <= last)
+            .filter(|date| date.weekday() == *weekday)
+            .collect();
+        match ordinal {
+            None => dates.extend(matched),
+            Some(n) if *n > 0 => dates.extend(matched.get(*n as usize - 1)),
+            Some(n) => dates.extend(
+                matched
+                    .len()
+                    .checked_sub(n.unsigned_abs() as usize)
+                    .and_then(|i| matched.get(i)),
+            ),
+        }
+    }
+    dates
+}
+
+impl RecurrenceRule {
+    fn matches_by_day(&self, date: NaiveDate) -> bool {
+        self.by_day.is_empty()
+            || self
+                .by_day
+                .iter()
+                .any(|(_, weekday)| date.weekday() == *weekday)
+    }
+
+    fn matches_by_month_day(&self, date: NaiveDate) -> bool {
+        self.by_month_day.is_empty()
+            || self
+                .by_month_day
+                .iter()
+                .any(|day| self.month_day(date.year(), date.month(), *day) == Some(date))
+    }
+
+    fn matches_by_month(&self, date: NaiveDate) -> bool {
+        self.by_month.is_empty() || self.by_month.contains(&date.month())
+    }
+
+    /// 負の日は月末から数える
+    fn month_day(&self, year: i32, month: u32, day: i32) -> Option<NaiveDate> {
+        let last = last_day_of_month(year, month)?;
+        match day {
+            day if day > 0 => NaiveDate::from_ymd_opt(year, month, day as u32),
+            day if day < 0 => last
+                .checked_sub_days(chrono::Days::new(day.unsigned_abs() as u64 - 1))
+                .filter(|date| date.month() == month),
+            _ => None,
+        }
+    }
+
+    /// その月のうち規則に当てはまる日。指定がなければ DTSTART と同じ日
+    fn dates_in_month(&self, year: i32, month: u32, default_day: u32) -> Vec<NaiveDate> {
+        if !self.by_month_day.is_empty() {
+            return self
+                .by_month_day
+                .iter()
+                .filter_map(|day| self.month_day(year, month, *day))
+                .filter(|date| self.matches_by_day(*date))
+                .collect();
+        }
+        if !self.by_day.is_empty() {
+            let (Some(first), Some(last)) = (
+                NaiveDate::from_ymd_opt(year, month, 1),
+                last_day_of_month(year, month),
+            ) else {
+                return vec![];
+            };
+            return weekdays_between(first, last, &self.by_day);
+        }
+        NaiveDate::from_ymd_opt(year, month, default_day)
+            .into_iter()
+            .collect()
+    }
+
+    /// DTSTART から period 番目の期間(日・週・月・年)の最初の日と、その期間に当てはまる日
+    fn period(&self, start: NaiveDate, period: i64) -> Option<(NaiveDate, Vec<NaiveDate>)> {
+        let step = period * self.interval as i64;
+        let (period_start, mut dates) = match self.frequency {
+            Frequency::Daily => {
+                let date = start.checked_add_signed(Duration::days(step))?;
+                let dates = Some(date)
+                    .filter(|date| self.matches_by_day(*date) && self.matches_by_month_day(*date))
+                    .into_iter()
+                    .collect();
+                (date, dates)
+            }
+            Frequency::Weekly => {
+                let monday = start
+                    .checked_sub_signed(Duration::days(
+                        start.weekday().num_days_from_monday() as i64
+                    ))?
+                    .checked_add_signed(Duration::weeks(step))?;
+                let weekdays: Vec<Weekday> = if self.by_day.is_empty() {
+                    vec![start.weekday()]
+                } else {
+                    self.by_day.iter().map(|(_, weekday)| *weekday).collect()
+                };
+                let dates = monday
+                    .iter_days()
+                    .take(7)
+                    .filter(|date| weekdays.contains(&date.weekday()))
+                    .collect();
+                (monday, dates)
+            }
+            Frequency::Monthly => {
+                let first = start
+                    .with_day(1)?
+                    .checked_add_months(Months::new(u32::try_from(step).ok()?))?;
+                let dates = self.dates_in_month(first.year(), first.month(), start.day());
+                (first, dates)
+            }
+            Frequency::Yearly => {
+                let year = start.year() + i32::try_from(step).ok()?;
+                let first = NaiveDate::from_ymd_opt(year, 1, 1)?;
+                let dates = if !self.by_month.is_empty() {
+                    self.by_month
+                        .iter()
+                        .flat_map(|month| self.dates_in_month(year, *month, start.day()))
+                        .collect()
+                } else if !self.by_day.is_empty() && self.by_month_day.is_empty() {
+                    weekdays_between(first, NaiveDate::from_ymd_opt(year, 12, 31)?, &self.by_day)
+                } else {
+                    self.dates_in_month(year, start.month(), start.day())
+                };
+                (first, dates)
+            }
+        };
+        dates.retain(|date| self.matches_by_month(*date));
+        dates.sort();
+        dates.dedup();
+
+        if !self.by_set_pos.is_empty() {
+            let len = dates.len() as i32;
+            dates = self
+                .by_set_pos
+                .iter()
+                .filter_map(|pos| match *pos {
+                    pos if pos > 0 && pos <= len => Some(dates[pos as usize - 1]),
+                    pos if pos < 0 && -pos <= len => Some(dates[(len + pos) as usize]),
+                    _ => None,
+                })
+                .collect();
+            dates.sort();
+        }
+        Some((period_start, dates))
+    }
+
+    /**
+     * DTSTART から end より前に始まる回の開始日時。DTSTART 自体は規則に当てはまらなくても最初の回になる
+     */
+    pub fn occurrences(&self, start: &DateTimeValue, end: DateTime<Utc>) -> Vec<DateTimeValue> {
+        let time = if start.all_day {
+            NaiveTime::MIN
+        } else {
+            start.naive.time()
+        };
+        let is_after_until = |occurrence: &DateTimeValue| match self.until {
+            Some(Until::Utc(until)) => occurrence.to_utc() > until,
+            Some(Until::Local(until)) => occurrence.naive > until,
+            None => false,
+        };
+
+        let mut occurrences = vec![];
+        if start.to_utc() >= end || is_after_until(start) {
+            return occurrences;
+        }
+        occurrences.push(*start);
+
+        let start_date = start.naive.date();
+        // タイムゾーンの差で end の日付を越えても取りこぼさないよう、1 日余裕を持たせる
+        let last_date = end.date_naive() + Duration::days(1);
+        for period in 0..MAX_PERIODS {
+            let Some((period_start, dates)) = self.period(start_date, period) else {
+                break;
+            };
+            if period_start > last_date {
+                break;
+            }
+            for date in dates {
+                let occurrence = start.with_naive(date.and_time(time));
+                if occurrence.naive <= start.naive {
+                    continue;
+                }
+                if self
+                    .count
+                    .is_some_and(|count| occurrences.len() >= count as usize)
+                    || is_after_until(&occurrence)
+                    || occurrence.to_utc() >= end
+                {
+                    return occurrences;
+                }
+                occurrences.push(occurrence);
+            }
+        }
+        occurrences
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ical::Property;
+
+    fn dtstart(value: &str) -> DateTimeValue {
+        let property = Property {
+            name: "DTSTART".to_string(),
+            params: vec![("TZID".to_string(), "Asia/Tokyo".to_string())],
+            value: value.to_string(),
+        };
+        DateTimeValue::parse(value, &property, None).unwrap()
+    }
+
+    fn expand(rule: &str, start: &str, end: &str) -> Vec<String> {
+        let end = NaiveDateTime::parse_from_str(end, "%Y%m%dT%H%M%S")
+            .unwrap()
+            .and_utc();
+        rule.parse::<RecurrenceRule>()
+            .unwrap()
+            .occurrences(&dtstart(start), end)
+            .iter()
+            .map(|occurrence| occurrence.naive.format("%Y%m%dT%H%M").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_weekly() {
+        assert_eq!(
+            expand(
+                "FREQ=WEEKLY;BYDAY=MO,WE",
+                "20261019T100000",
+                "20261030T000000"
+            ),
+            vec![
+                "20261019T1000",
+                "20261021T1000",
+                "20261026T1000",
+                "20261028T1000"
+            ]
+        );
+        assert_eq!(
+            expand(
+                "FREQ=WEEKLY;INTERVAL=2;COUNT=3",
+                "20261019T100000",
+                "20270101T000000"
+            ),
+            vec!["20261019T1000", "20261102T1000", "20261116T1000"]
+        );
+    }
+
+    #[test]
+    fn test_monthly() {
+        // 第 2 火曜日
+        assert_eq!(
+            expand(
+                "FREQ=MONTHLY;BYDAY=2TU;UNTIL=20261231T000000Z",
+                "20261013T150000",
+                "20270601T000000"
+            ),
+            vec!["20261013T1500", "20261110T1500", "20261208T1500"]
+        );
+        // 31 日がない月は飛ばす
+        assert_eq!(
+            expand("FREQ=MONTHLY", "20261031T090000", "20270201T000000"),
+            vec!["20261031T0900", "20261231T0900", "20270131T0900"]
+        );
+        // 月末の平日
+        assert_eq!(
+            expand(
+                "FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+                "20261030T180000",
+                "20270101T000000"
+            ),
+            vec!["20261030T1800", "20261130T1800", "20261231T1800"]
+        );
+        assert_eq!(
+            expand(
+                "FREQ=MONTHLY;BYMONTHDAY=-1",
+                "20260131T090000",
+                "20260401T000000"
+            ),
+            vec!["20260131T0900", "20260228T0900", "20260331T0900"]
+        );
+    }
+
+    #[test]
+    fn test_daily_and_yearly() {
+        assert_eq!(
+            expand(
+                "FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR",
+                "20261023T090000",
+                "20261028T000000"
+            ),
+            vec!["20261023T0900", "20261026T0900", "20261027T0900"]
+        );
+        assert_eq!(
+            expand(
+                "FREQ=YEARLY;BYMONTH=11;BYDAY=4TH",
+                "20261126T000000",
+                "20290101T000000"
+            ),
+            vec!["20261126T0000", "20271125T0000", "20281123T0000"]
+        );
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!("BYDAY=MO".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=HOURLY".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=WEEKLY;BYDAY=XX".parse::<RecurrenceRule>().is_err());
+    }
+}
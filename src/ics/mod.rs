@@ -0,0 +1,253 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+use reqwest::{
+    header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    env::Env,
+    ical,
+    provider::{self, CalendarProvider, EventBatch, SyncWindow},
+    repository::{self, models::EventFindMany},
+};
+
+/// events.provider に保存する名前
+pub const ICS_PROVIDER_NAME: &str = "ics";
+
+#[derive(Debug)]
+pub enum Error {
+    Reqwest(reqwest::Error),
+    Io(std::io::Error),
+    Http { status: StatusCode, body: String },
+    Parse(String),
+}
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Reqwest(e)
+    }
+}
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/**
+ * 差分同期のトークンとして保存する、前回取得したフィードの検証子と、繰り返しの予定を展開した期間
+ */
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FeedState {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    expanded_until: DateTime<Utc>,
+}
+impl FeedState {
+    /// 保存したトークンを読む。展開済みの期間を過ぎていれば、繰り返しの予定を展開し直すため None
+    fn for_window(sync_token: &str, window: SyncWindow) -> Option<Self> {
+        serde_json::from_str::<Self>(sync_token)
+            .ok()
+            .filter(|state| window.time_range(Utc::now()).1 <= state.expanded_until)
+    }
+}
+
+/// 取得したフィード
+struct Feed {
+    body: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/**
+ * 祝日やカンファレンスの予定表など、読み取り専用の .ics ファイル・iCal フィード。
+ * 変更がなければ取得し直さないよう、ETag / Last-Modified(ファイルは更新日時)を保存する
+ */
+pub struct IcsProvider {
+    /// ファイルのパス、または http(s):// / webcal:// の URL
+    source: String,
+    /// 終日の予定や、タイムゾーンのない日時を読むタイムゾーン
+    default_time_zone: Option<Tz>,
+}
+impl IcsProvider {
+    /// ICS_SOURCES に設定されたフィードごとのプロバイダー
+    pub fn from_env(env: &Env) -> Vec<Self> {
+        env.ics_sources
+            .iter()
+            .map(|source| Self {
+                source: source.clone(),
+                default_time_zone: env.display_timezone,
+            })
+            .collect()
+    }
+
+    /// イベント ID の接頭辞。この後ろに UID が付く
+    fn event_id_prefix(&self) -> String {
+        format!("{}:{}#", ICS_PROVIDER_NAME, self.source)
+    }
+
+    fn url(&self) -> Option<String> {
+        if let Some(rest) = self.source.strip_prefix("webcal://") {
+            return Some(format!("https://{}", rest));
+        }
+        (self.source.starts_with("http://") || self.source.starts_with("https://"))
+            .then(|| self.source.clone())
+    }
+
+    /// フィードを取得する。前回から変更がなければ None
+    async fn fetch(&self, previous: Option<&FeedState>) -> Result<Option<Feed>, Error> {
+        let Some(url) = self.url() else {
+            return self.read_file(previous);
+        };
+
+        let mut request = reqwest::Client::new().get(url);
+        if let Some(previous) = previous {
+            if let Some(etag) = &previous.etag {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &previous.last_modified {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        let response = request.send().await?;
+
+        let status = response.status();
+        if status == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header(ETAG);
+        let last_modified = header(LAST_MODIFIED);
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(Error::Http { status, body });
+        }
+        Ok(Some(Feed {
+            body,
+            etag,
+            last_modified,
+        }))
+    }
+
+    fn read_file(&self, previous: Option<&FeedState>) -> Result<Option<Feed>, Error> {
+        let path = self.source.strip_prefix("file://").unwrap_or(&self.source);
+        let modified: DateTime<Utc> = std::fs::metadata(path)?.modified()?.into();
+        let last_modified = Some(modified.to_rfc3339());
+        if previous.is_some_and(|previous| previous.last_modified == last_modified) {
+            return Ok(None);
+        }
+        Ok(Some(Feed {
+            body: std::fs::read_to_string(path)?,
+            etag: None,
+            last_modified,
+        }))
+    }
+
+    /**
+     * 前回の状態があれば条件付きで取得する。変更されていなければ空の差分と前回のトークンを返す
+     */
+    async fn sync_feed(
+        &self,
+        previous: Option<(&str, &FeedState)>,
+        window: SyncWindow,
+    ) -> Result<EventBatch, provider::Error> {
+        match self.fetch(previous.map(|(_, state)| state)).await? {
+            Some(feed) => self.batch_of(feed, window),
+            None => Ok(EventBatch {
+                next_sync_token: previous.map(|(sync_token, _)| sync_token.to_string()),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /**
+     * フィードのすべての予定を同期期間で展開する。
+     * 期間が進むたびに取得し直さないよう、1 日先まで展開しておく。
+     * フィードから無くなった予定は削除する(過去の予定は残す)
+     */
+    fn batch_of(&self, feed: Feed, window: SyncWindow) -> Result<EventBatch, provider::Error> {
+        let calendars = ical::parse(&feed.body).map_err(Error::Parse)?;
+        let (start, end) = window.time_range(Utc::now());
+        let expanded_until = end + chrono::Duration::days(1);
+        let events = ical::to_calendar_events(
+            &calendars,
+            &self.event_id_prefix(),
+            (start, expanded_until),
+            self.default_time_zone,
+            None,
+        );
+
+        let stale_ids: Vec<String> =
+            repository::event::find_ids_starting_with(&self.event_id_prefix())
+                .map_err(|e| provider::Error::Repository(e.to_string()))?
+                .into_iter()
+                .filter(|id| !events.iter().any(|event| &event.id == id))
+                .collect();
+        let deleted_ids = repository::event::find_many(EventFindMany {
+            from: Some(start),
+            ids_in: Some(stale_ids),
+            ..Default::default()
+        })
+        .map_err(|e| provider::Error::Repository(e.to_string()))?
+        .into_iter()
+        .map(|event| event.id)
+        .collect();
+
+        let state = FeedState {
+            etag: feed.etag,
+            last_modified: feed.last_modified,
+            expanded_until,
+        };
+        Ok(EventBatch {
+            events,
+            deleted_ids,
+            next_sync_token: serde_json::to_string(&state).ok(),
+        })
+    }
+}
+
+#[async_trait]
+impl CalendarProvider for IcsProvider {
+    fn name(&self) -> &'static str {
+        ICS_PROVIDER_NAME
+    }
+
+    fn calendar_id(&self) -> String {
+        format!("{}:{}", ICS_PROVIDER_NAME, self.source)
+    }
+
+    /// 前回の同期の状態が保存されていれば、フィードが変更されたときだけ取得し直す
+    async fn list_events(&self, window: SyncWindow) -> Result<EventBatch, provider::Error> {
+        let previous = repository::sync_state::find(&self.calendar_id())
+            .map_err(|e| provider::Error::Repository(e.to_string()))?
+            .and_then(|sync_state| sync_state.sync_token)
+            .and_then(|sync_token| {
+                FeedState::for_window(&sync_token, window).map(|state| (sync_token, state))
+            });
+        self.sync_feed(
+            previous
+                .as_ref()
+                .map(|(sync_token, state)| (sync_token.as_str(), state)),
+            window,
+        )
+        .await
+    }
+
+    /// フィードは差分を返さないため、変更されていれば全件を取得し直す
+    async fn list_changes(
+        &self,
+        sync_token: &str,
+        window: SyncWindow,
+    ) -> Result<EventBatch, provider::Error> {
+        let state =
+            FeedState::for_window(sync_token, window).ok_or(provider::Error::SyncTokenExpired)?;
+        self.sync_feed(Some((sync_token, &state)), window).await
+    }
+}
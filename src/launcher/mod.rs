@@ -0,0 +1,420 @@
+use std::{io, process::Command, str::FromStr};
+
+use crate::{
+    env::Env,
+    meeting_link::{app_link::app_link, MeetingLink},
+};
+
+/**
+ * 会議のリンクを開く方法
+ *
+ * ```
+ * use calendar_notice::launcher::LaunchMethod;
+ *
+ * assert_eq!("app".parse(), Ok(LaunchMethod::App));
+ * assert_eq!("browser".parse(), Ok(LaunchMethod::Browser(None)));
+ * assert_eq!("browser:Profile 1".parse(), Ok(LaunchMethod::Browser(Some("Profile 1".to_string()))));
+ * assert_eq!(
+ *     "command:webex-launcher --join {url}".parse(),
+ *     Ok(LaunchMethod::Command("webex-launcher --join {url}".to_string()))
+ * );
+ * assert!("command:".parse::<LaunchMethod>().is_err());
+ * assert!("native".parse::<LaunchMethod>().is_err());
+ * ```
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchMethod {
+    /// 会議アプリのリンク(zoommtg: / msteams:)を開く。アプリのリンクがないサービスはブラウザで開く
+    App,
+    /// ブラウザで開く。プロファイルを指定すると BROWSER_PROFILE より優先する
+    Browser(Option<String>),
+    /// コマンド。{url} はリンク、{app_url} はアプリのリンク(なければリンク)に置き換える。
+    /// どちらもなければリンクを最後の引数にする
+    Command(String),
+}
+impl FromStr for LaunchMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (method, value) = match s.trim().split_once(':') {
+            Some((method, value)) => (method, Some(value.trim())),
+            None => (s.trim(), None),
+        };
+        match (method, value) {
+            ("app", None) => Ok(LaunchMethod::App),
+            ("browser", None) => Ok(LaunchMethod::Browser(None)),
+            ("browser", Some(profile)) if !profile.is_empty() => {
+                Ok(LaunchMethod::Browser(Some(profile.to_string())))
+            }
+            ("command", Some(command)) if !command.is_empty() => {
+                Ok(LaunchMethod::Command(command.to_string()))
+            }
+            _ => Err(format!(
+                "{} must be app, browser, browser:<profile> or command:<command>",
+                s
+            )),
+        }
+    }
+}
+
+/**
+ * MEETING_LAUNCH の値。"<会議のリンクの名前>=<開く方法>" を ; 区切りで指定する。
+ * 指定のないサービスは、Zoom はアプリ、それ以外はブラウザで開く
+ *
+ * ```
+ * use calendar_notice::launcher::{LaunchMethod, LaunchPreferences};
+ *
+ * let preferences: LaunchPreferences = "teams=app; zoom=browser".parse().unwrap();
+ * assert_eq!(preferences.method("teams"), LaunchMethod::App);
+ * assert_eq!(preferences.method("zoom"), LaunchMethod::Browser(None));
+ * assert_eq!(preferences.method("meet"), LaunchMethod::Browser(None));
+ * assert_eq!(LaunchPreferences::default().method("zoom"), LaunchMethod::App);
+ *
+ * assert!("teams".parse::<LaunchPreferences>().is_err());
+ * ```
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchPreferences(pub Vec<(String, LaunchMethod)>);
+impl LaunchPreferences {
+    pub fn method(&self, provider: &str) -> LaunchMethod {
+        self.0
+            .iter()
+            .find(|(name, _)| name == provider)
+            .map(|(_, method)| method.clone())
+            .unwrap_or(match provider {
+                "zoom" => LaunchMethod::App,
+                _ => LaunchMethod::Browser(None),
+            })
+    }
+}
+impl FromStr for LaunchPreferences {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(';')
+            .map(str::trim)
+            .filter(|preference| !preference.is_empty())
+            .map(|preference| {
+                let (provider, method) = preference
+                    .split_once('=')
+                    .ok_or(format!("{} must be <name>=<method>", preference))?;
+                Ok((provider.trim().to_string(), method.parse()?))
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .map(LaunchPreferences)
+    }
+}
+
+/**
+ * 実行するコマンド。組み立てと実行を分け、テストでは実行せずに中身を確認する
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LaunchCommand {
+    Program {
+        program: String,
+        args: Vec<String>,
+    },
+    /// open クレートで、OS の既定の方法(macOS は open、Linux は xdg-open など)で開く
+    System(String),
+}
+impl LaunchCommand {
+    pub fn new(program: &str, args: &[&str]) -> Self {
+        LaunchCommand::Program {
+            program: program.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    /// 起動したプロセスの終了は待たない
+    pub fn run(&self) -> Result<(), io::Error> {
+        match self {
+            LaunchCommand::Program { program, args } => {
+                Command::new(program).args(args).spawn()?;
+            }
+            LaunchCommand::System(url) => open::that_detached(url)?,
+        }
+        Ok(())
+    }
+}
+
+/**
+ * 会議のリンクや資料を、サービスごとの設定に従って開く
+ */
+#[derive(Debug, Clone, Default)]
+pub struct Launcher {
+    pub preferences: LaunchPreferences,
+    /// ブラウザで開くコマンド。{url} をリンク、{profile} をプロファイルに置き換える。
+    /// 未設定の場合は OS の既定のブラウザで開き、プロファイルは選べない
+    pub browser_command: Option<String>,
+    /// MEETING_LAUNCH で指定しない場合のブラウザのプロファイル
+    pub browser_profile: Option<String>,
+}
+impl Launcher {
+    pub fn from_env(env: &Env) -> Self {
+        Self {
+            preferences: env.meeting_launch.clone(),
+            browser_command: env.browser_command.clone(),
+            browser_profile: env.browser_profile.clone(),
+        }
+    }
+
+    /**
+     * 会議のリンクを開くコマンド
+     *
+     * ```
+     * use calendar_notice::{
+     *     launcher::{LaunchCommand, Launcher},
+     *     meeting_link::MeetingLinkRegistry,
+     * };
+     *
+     * let registry = MeetingLinkRegistry::default();
+     * let launcher = Launcher {
+     *     preferences: "teams=browser:Work".parse().unwrap(),
+     *     browser_command: Some(r#"open -na "Google Chrome" --args --profile-directory={profile} {url}"#.to_string()),
+     *     ..Default::default()
+     * };
+     *
+     * let zoom = registry.extract_first("https://zoom.us/j/95428352872").unwrap();
+     * assert_eq!(
+     *     launcher.command_for(&zoom),
+     *     LaunchCommand::System("zoommtg://zoom.us/join?action=join&confno=95428352872".to_string())
+     * );
+     *
+     * let teams = registry.extract_first("https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc").unwrap();
+     * assert_eq!(
+     *     launcher.command_for(&teams),
+     *     LaunchCommand::new(
+     *         "open",
+     *         &[
+     *             "-na",
+     *             "Google Chrome",
+     *             "--args",
+     *             "--profile-directory=Work",
+     *             "https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc"
+     *         ]
+     *     )
+     * );
+     * ```
+     */
+    pub fn command_for(&self, link: &MeetingLink) -> LaunchCommand {
+        match self.preferences.method(&link.provider) {
+            LaunchMethod::App => match app_link(link) {
+                Some(app_url) => LaunchCommand::System(app_url),
+                None => self.browser_command(&link.url, None),
+            },
+            LaunchMethod::Browser(profile) => self.browser_command(&link.url, profile.as_deref()),
+            LaunchMethod::Command(template) => {
+                let app_url = app_link(link).unwrap_or(link.url.clone());
+                render_command(&template, &link.url, &[("app_url", Some(&app_url))])
+            }
+        }
+    }
+
+    pub fn open(&self, link: &MeetingLink) -> Result<(), io::Error> {
+        self.command_for(link).run()
+    }
+
+    /**
+     * ブラウザで開くコマンド。プロファイルを指定しなければ BROWSER_PROFILE を使う
+     */
+    pub fn browser_command(&self, url: &str, profile: Option<&str>) -> LaunchCommand {
+        match &self.browser_command {
+            Some(template) => {
+                let profile = profile.or(self.browser_profile.as_deref());
+                render_command(template, url, &[("profile", profile)])
+            }
+            None => LaunchCommand::System(url.to_string()),
+        }
+    }
+}
+
+/**
+ * コマンドの文字列を引数に分け、{url} などを埋め込む。
+ * 値のないプレースホルダーを含む引数は除き、{url} も {app_url} もなければ URL を最後の引数にする
+ */
+fn render_command(
+    template: &str,
+    url: &str,
+    placeholders: &[(&str, Option<&str>)],
+) -> LaunchCommand {
+    let mut words = split_command_line(template).into_iter();
+    let program = words.next().unwrap_or_default();
+    let mut args: Vec<String> = words
+        .filter_map(|word| {
+            placeholders
+                .iter()
+                .chain([("url", Some(url))].iter())
+                .try_fold(word, |word, (name, value)| {
+                    let placeholder = format!("{{{}}}", name);
+                    match value {
+                        Some(value) => Some(word.replace(&placeholder, value)),
+                        None if word.contains(&placeholder) => None,
+                        None => Some(word),
+                    }
+                })
+        })
+        .collect();
+    if !template.contains("{url}") && !template.contains("{app_url}") {
+        args.push(url.to_string());
+    }
+    LaunchCommand::Program { program, args }
+}
+
+/**
+ * 空白で引数に分ける。"..." と '...' の中の空白は区切らない
+ */
+fn split_command_line(command: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(provider: &str, url: &str) -> MeetingLink {
+        MeetingLink {
+            provider: provider.to_string(),
+            url: url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_command_for() {
+        let teams = link(
+            "teams",
+            "https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc",
+        );
+        let meet = link("meet", "https://meet.google.com/abc-defg-hij");
+
+        let launcher = Launcher {
+            preferences: "teams=app; meet=app".parse().unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(
+            launcher.command_for(&teams),
+            LaunchCommand::System("msteams:/l/meetup-join/19%3ameeting_abc".to_string())
+        );
+        // アプリのリンクがなければブラウザで開く
+        assert_eq!(
+            launcher.command_for(&meet),
+            LaunchCommand::System("https://meet.google.com/abc-defg-hij".to_string())
+        );
+
+        assert_eq!(
+            Launcher::default().command_for(&teams),
+            LaunchCommand::System(
+                "https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_browser_profile() {
+        let launcher = Launcher {
+            preferences: "teams=browser:Work".parse().unwrap(),
+            browser_command: Some("google-chrome --profile-directory={profile} {url}".to_string()),
+            browser_profile: Some("Default".to_string()),
+        };
+        assert_eq!(
+            launcher.command_for(&link("teams", "https://teams.microsoft.com/l/x")),
+            LaunchCommand::new(
+                "google-chrome",
+                &[
+                    "--profile-directory=Work",
+                    "https://teams.microsoft.com/l/x"
+                ]
+            )
+        );
+        assert_eq!(
+            launcher.browser_command("https://docs.example.com/agenda", None),
+            LaunchCommand::new(
+                "google-chrome",
+                &[
+                    "--profile-directory=Default",
+                    "https://docs.example.com/agenda"
+                ]
+            )
+        );
+
+        // プロファイルがなければその引数を除く
+        let launcher = Launcher {
+            browser_profile: None,
+            ..launcher
+        };
+        assert_eq!(
+            launcher.browser_command("https://docs.example.com/agenda", None),
+            LaunchCommand::new("google-chrome", &["https://docs.example.com/agenda"])
+        );
+
+        // {url} がなければ最後の引数にする
+        let launcher = Launcher {
+            browser_command: Some("firefox -P 'Work Profile' --new-tab".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            launcher.browser_command("https://example.com", None),
+            LaunchCommand::new(
+                "firefox",
+                &["-P", "Work Profile", "--new-tab", "https://example.com"]
+            )
+        );
+    }
+
+    #[test]
+    fn test_custom_command() {
+        let zoom = MeetingLink {
+            meeting_id: Some("95428352872".to_string()),
+            ..link("zoom", "https://zoom.us/j/95428352872")
+        };
+        let launcher = Launcher {
+            preferences:
+                "zoom=command:flatpak run us.zoom.Zoom --url={app_url}; meet=command:chromium --app"
+                    .parse()
+                    .unwrap(),
+            ..Default::default()
+        };
+        assert_eq!(
+            launcher.command_for(&zoom),
+            LaunchCommand::new(
+                "flatpak",
+                &[
+                    "run",
+                    "us.zoom.Zoom",
+                    "--url=zoommtg://zoom.us/join?action=join&confno=95428352872"
+                ]
+            )
+        );
+        assert_eq!(
+            launcher.command_for(&link("meet", "https://meet.google.com/abc-defg-hij")),
+            LaunchCommand::new(
+                "chromium",
+                &["--app", "https://meet.google.com/abc-defg-hij"]
+            )
+        );
+    }
+
+    #[test]
+    fn test_split_command_line() {
+        assert_eq!(
+            split_command_line(r#"open -na "Google Chrome" --args  '' x"#),
+            vec!["open", "-na", "Google Chrome", "--args", "", "x"]
+        );
+    }
+}
@@ -1,7 +1,19 @@
+pub mod caldav;
 pub mod command_line;
 pub mod env;
+pub mod export;
 pub mod google_calendar;
+pub mod ical;
+pub mod ics;
+pub mod launcher;
+pub mod location;
+pub mod meeting_link;
+pub mod microsoft_graph;
 pub mod notification;
 pub mod oauth;
+pub mod provider;
 pub mod repository;
 pub mod schema;
+pub mod sync;
+pub mod timezone;
+pub mod tui;
@@ -0,0 +1,124 @@
+use std::str::FromStr;
+
+use reqwest::Url;
+
+const MAPS_SEARCH_URL: &str = "https://www.google.com/maps/search/";
+
+/**
+ * 場所に含まれる文字列と、移動にかかる時間(分)の対応
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TravelTimeRule {
+    pub pattern: String,
+    pub extra_minutes: i64,
+}
+
+/**
+ * TRAVEL_TIME_RULES の値。"Office B=15;本社=30" のように ; 区切りで指定する
+ *
+ * ```
+ * use calendar_notice::location::{TravelTimeRule, TravelTimeRules};
+ *
+ * let rules: TravelTimeRules = "Office B=15; 本社 = 30".parse().unwrap();
+ * assert_eq!(
+ *     rules.0,
+ *     vec![
+ *         TravelTimeRule { pattern: "Office B".to_string(), extra_minutes: 15 },
+ *         TravelTimeRule { pattern: "本社".to_string(), extra_minutes: 30 },
+ *     ]
+ * );
+ * assert!("Office B".parse::<TravelTimeRules>().is_err());
+ * ```
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TravelTimeRules(pub Vec<TravelTimeRule>);
+impl FromStr for TravelTimeRules {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(';')
+            .map(str::trim)
+            .filter(|rule| !rule.is_empty())
+            .map(|rule| {
+                let (pattern, minutes) = rule
+                    .rsplit_once('=')
+                    .ok_or(format!("{} must be <pattern>=<minutes>", rule))?;
+                let extra_minutes = minutes
+                    .trim()
+                    .parse::<i64>()
+                    .ok()
+                    .filter(|minutes| *minutes >= 0)
+                    .ok_or(format!("{} is not a valid number of minutes", minutes))?;
+                Ok(TravelTimeRule {
+                    pattern: pattern.trim().to_string(),
+                    extra_minutes,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .map(TravelTimeRules)
+    }
+}
+impl TravelTimeRules {
+    /**
+     * 対面の予定の移動時間(分)。最初に一致したルールを使い、URL の場所や一致しない場所は 0
+     */
+    pub fn travel_minutes(&self, location: Option<&str>) -> i64 {
+        let Some(location) = location.filter(|location| !is_url(location)) else {
+            return 0;
+        };
+        let location = location.to_lowercase();
+        self.0
+            .iter()
+            .find(|rule| location.contains(&rule.pattern.to_lowercase()))
+            .map(|rule| rule.extra_minutes)
+            .unwrap_or(0)
+    }
+}
+
+fn is_url(location: &str) -> bool {
+    location.starts_with("https://") || location.starts_with("http://")
+}
+
+/**
+ * 場所を開くリンク。URL はそのまま、住所や会議室名は地図の検索結果にする
+ */
+pub fn location_link(location: &str) -> String {
+    let location = location.trim();
+    if is_url(location) {
+        return location.to_string();
+    }
+    Url::parse_with_params(MAPS_SEARCH_URL, &[("api", "1"), ("query", location)])
+        .map(|url| url.to_string())
+        .unwrap_or(MAPS_SEARCH_URL.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_travel_minutes() {
+        let rules: TravelTimeRules = "office b=15;本社=30".parse().unwrap();
+        assert_eq!(rules.travel_minutes(Some("Office B 3F 会議室")), 15);
+        assert_eq!(rules.travel_minutes(Some("東京本社")), 30);
+        assert_eq!(rules.travel_minutes(Some("Office A")), 0);
+        assert_eq!(rules.travel_minutes(None), 0);
+        // オンラインの予定には移動時間を足さない
+        assert_eq!(
+            rules.travel_minutes(Some("https://example.com/office-b")),
+            0
+        );
+    }
+
+    #[test]
+    fn test_location_link() {
+        assert_eq!(
+            location_link("https://zoom.us/j/123"),
+            "https://zoom.us/j/123"
+        );
+        assert_eq!(
+            location_link("東京都千代田区 1-1"),
+            "https://www.google.com/maps/search/?api=1&query=%E6%9D%B1%E4%BA%AC%E9%83%BD%E5%8D%83%E4%BB%A3%E7%94%B0%E5%8C%BA+1-1"
+        );
+    }
+}
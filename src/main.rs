@@ -1,22 +1,17 @@
-mod command_line;
-mod env;
-mod google_calendar;
-mod notification;
-mod oauth;
-mod repository;
-mod schema;
-mod tui;
-
-use google_calendar::spawn_sync_calendar_cron;
-use notification::spawn_notification_cron;
-use oauth::spawn_redirect_server;
-use tui::show_tui;
+use calendar_notice::{
+    command_line::{self, Args},
+    env::Env,
+    notification::spawn_notification_cron,
+    oauth::{reauthorize_if_scope_missing, spawn_redirect_server},
+    repository,
+    sync::{spawn_sync_loop, SyncCommand},
+    tui::show_tui,
+};
+use clap::Parser;
 
 /**
 functoin..
 - TUI での操作
-- 「参加する/辞退する」変更
-  - 辞退したら通知もなしにする
 - 初期起動時のUI調整
 
 db..
@@ -31,11 +26,32 @@ improvement..
 */
 #[tokio::main]
 async fn main() {
-    spawn_redirect_server();
+    if let Err(e) = Env::new().validate() {
+        eprintln!("Invalid configuration: {}", e);
+        std::process::exit(1);
+    }
 
-    spawn_notification_cron();
+    if let Err(e) = repository::run_migrations() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+
+    let args = Args::parse();
+    if let Some(command) = args.command {
+        command_line::run(command).await;
+        return;
+    }
+
+    let sync_handle = spawn_sync_loop();
+    if let Err(e) = sync_handle.request(SyncCommand::SyncNow) {
+        println!("Failed to request initial sync: {}", e);
+    }
 
-    spawn_sync_calendar_cron();
+    spawn_redirect_server(sync_handle.clone());
+    // 出欠の変更に必要なスコープがない古いトークンの場合は認可し直す
+    tokio::task::spawn_blocking(reauthorize_if_scope_missing);
+
+    spawn_notification_cron();
 
-    show_tui();
+    show_tui(sync_handle);
 }
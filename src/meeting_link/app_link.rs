@@ -0,0 +1,101 @@
+use reqwest::Url;
+
+use super::MeetingLink;
+
+/**
+ * 会議のリンクを、会議アプリで直接開くリンク(ディープリンク)にする。アプリのリンクがないサービスは None
+ *
+ * ```
+ * use calendar_notice::meeting_link::{app_link::app_link, MeetingLinkRegistry};
+ *
+ * let registry = MeetingLinkRegistry::default();
+ * let zoom = registry.extract_first("https://us02web.zoom.us/j/95428352872?pwd=abc.1").unwrap();
+ * assert_eq!(
+ *     app_link(&zoom).as_deref(),
+ *     Some("zoommtg://zoom.us/join?action=join&confno=95428352872&pwd=abc.1")
+ * );
+ *
+ * let teams = registry
+ *     .extract_first("https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc%40thread.v2/0")
+ *     .unwrap();
+ * assert_eq!(
+ *     app_link(&teams).as_deref(),
+ *     Some("msteams:/l/meetup-join/19%3ameeting_abc%40thread.v2/0")
+ * );
+ *
+ * let meet = registry.extract_first("https://meet.google.com/abc-defg-hij").unwrap();
+ * assert_eq!(app_link(&meet), None);
+ * ```
+ */
+pub fn app_link(link: &MeetingLink) -> Option<String> {
+    match link.provider.as_str() {
+        "zoom" => zoom_app_link(link),
+        "teams" => teams_app_link(link),
+        _ => None,
+    }
+}
+
+/**
+ * zoommtg://<ホスト>/join のリンク。URL の pwd がなければ説明文に書かれたパスコードを渡す。
+ * 個人用のリンクは vanity に名前を渡す
+ *
+ * ```
+ * use calendar_notice::meeting_link::{app_link::zoom_app_link, MeetingLink};
+ *
+ * let link = MeetingLink {
+ *     provider: "zoom".to_string(),
+ *     url: "https://acme.zoom.us/j/95428352872".to_string(),
+ *     meeting_id: Some("95428352872".to_string()),
+ *     passcode: Some("314 159".to_string()),
+ * };
+ * assert_eq!(
+ *     zoom_app_link(&link).as_deref(),
+ *     Some("zoommtg://acme.zoom.us/join?action=join&confno=95428352872&pwd=314+159")
+ * );
+ *
+ * let personal = MeetingLink {
+ *     url: "https://acme.zoom.us/my/alice.smith".to_string(),
+ *     passcode: None,
+ *     ..link
+ * };
+ * assert_eq!(
+ *     zoom_app_link(&personal).as_deref(),
+ *     Some("zoommtg://acme.zoom.us/join?action=join&vanity=alice.smith")
+ * );
+ * ```
+ */
+pub fn zoom_app_link(link: &MeetingLink) -> Option<String> {
+    let url = Url::parse(&link.url).ok()?;
+    let host = url.host_str()?;
+    let segments: Vec<&str> = url.path_segments()?.collect();
+
+    let mut params = vec![("action", "join".to_string())];
+    match segments.as_slice() {
+        ["my", vanity] => params.push(("vanity", vanity.to_string())),
+        [_, meeting_id] => {
+            params.push(("confno", meeting_id.to_string()));
+            let pwd = url
+                .query_pairs()
+                .find(|(key, _)| key == "pwd")
+                .map(|(_, value)| value.into_owned())
+                .or(link.passcode.clone());
+            if let Some(pwd) = pwd {
+                params.push(("pwd", pwd));
+            }
+        }
+        _ => return None,
+    }
+
+    Url::parse_with_params(&format!("zoommtg://{}/join", host), &params)
+        .ok()
+        .map(String::from)
+}
+
+/**
+ * Teams の会議(teams.microsoft.com/l/...)を msteams: で開くリンク。個人用の teams.live.com は None
+ */
+pub fn teams_app_link(link: &MeetingLink) -> Option<String> {
+    link.url
+        .strip_prefix("https://teams.microsoft.com/l/")
+        .map(|rest| format!("msteams:/l/{}", rest))
+}
@@ -0,0 +1,315 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::{MeetingLink, MeetingLinkExtractor, RegexExtractor};
+
+/// 組み込みの extractor。MeetingLinkRegistry ではこの順に優先する
+pub fn builtin_extractors() -> Vec<Box<dyn MeetingLinkExtractor>> {
+    vec![
+        Box::new(ZoomExtractor::new()),
+        Box::new(teams()),
+        Box::new(meet()),
+        Box::new(webex()),
+        Box::new(slack_huddle()),
+        Box::new(whereby()),
+        Box::new(jitsi()),
+        Box::new(goto_meeting()),
+        Box::new(chime()),
+        Box::new(around()),
+    ]
+}
+
+/**
+ * Zoom のミーティング・ウェビナーと個人用のリンク(/my/<名前>)。
+ * us02web などの地域のサブドメインは除き、会社ごとのサブドメインは残す。
+ * URL に pwd がなく、リンクより後ろに「パスコード: 123456」のように書かれていればパスコードにする
+ *
+ * ```
+ * use calendar_notice::meeting_link::{builtin::ZoomExtractor, MeetingLinkExtractor};
+ *
+ * let link = ZoomExtractor::new()
+ *     .extract("参加: https://us02web.zoom.us/j/95428352872?pwd=SYUHanJf8xXQR51lhTKI8u3RSDWLVF.1")
+ *     .unwrap();
+ * assert_eq!(link.provider, "zoom");
+ * assert_eq!(link.url, "https://zoom.us/j/95428352872?pwd=SYUHanJf8xXQR51lhTKI8u3RSDWLVF.1");
+ * assert_eq!(link.meeting_id.as_deref(), Some("95428352872"));
+ * assert_eq!(link.passcode.as_deref(), Some("SYUHanJf8xXQR51lhTKI8u3RSDWLVF.1"));
+ *
+ * let webinar = ZoomExtractor::new().extract("https://us02.zoom.us/w/98765432100").unwrap();
+ * assert_eq!(webinar.url, "https://zoom.us/j/98765432100");
+ * assert_eq!(ZoomExtractor::new().extract("https://zoom.us/pricing"), None);
+ *
+ * let personal = ZoomExtractor::new()
+ *     .extract("https://acme.zoom.us/my/alice.smith\nミーティング ID: 123 456 7890\nパスコード: 314159")
+ *     .unwrap();
+ * assert_eq!(personal.url, "https://acme.zoom.us/my/alice.smith");
+ * assert_eq!(personal.meeting_id.as_deref(), Some("alice.smith"));
+ * assert_eq!(personal.passcode.as_deref(), Some("314159"));
+ * ```
+ */
+pub struct ZoomExtractor {
+    regex: Regex,
+    passcode: Regex,
+}
+impl ZoomExtractor {
+    pub fn new() -> Self {
+        Self {
+            regex: Regex::new(
+                r"https://(?:(?P<subdomain>[\w-]+)\.)?zoom\.us/(?:(?:j|w|wc)/(?P<id>\d+)(?:\?pwd=(?P<pwd>[a-zA-Z0-9.]+))?|my/(?P<vanity>[\w.-]*\w))",
+            )
+            .unwrap(),
+            passcode: Regex::new(
+                r"(?i)(?:passcode|password|パスコード|パスワード)\s*[:：]\s*(?P<passcode>[\w.@*!#$%-]+)",
+            )
+            .unwrap(),
+        }
+    }
+}
+impl Default for ZoomExtractor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl MeetingLinkExtractor for ZoomExtractor {
+    fn provider(&self) -> &str {
+        "zoom"
+    }
+
+    fn find_all(&self, text: &str) -> Vec<(usize, MeetingLink)> {
+        let captures: Vec<_> = self.regex.captures_iter(text).collect();
+        captures
+            .iter()
+            .enumerate()
+            .map(|(i, cap)| {
+                let start = cap.get(0).unwrap().start();
+                // パスコードは次のリンクまでの間に書かれたものを使う
+                let end = captures
+                    .get(i + 1)
+                    .map(|next| next.get(0).unwrap().start())
+                    .unwrap_or(text.len());
+                let written_passcode = self
+                    .passcode
+                    .captures(&text[start..end])
+                    .map(|passcode| passcode["passcode"].to_string());
+                let host = match cap.name("subdomain").map(|m| m.as_str()) {
+                    Some(subdomain) if !is_regional_subdomain(subdomain) => {
+                        format!("{}.zoom.us", subdomain)
+                    }
+                    _ => "zoom.us".to_string(),
+                };
+
+                let link = match cap.name("vanity") {
+                    Some(vanity) => MeetingLink {
+                        provider: self.provider().to_string(),
+                        url: format!("https://{}/my/{}", host, vanity.as_str()),
+                        meeting_id: Some(vanity.as_str().to_string()),
+                        passcode: written_passcode,
+                    },
+                    None => {
+                        let meeting_id = cap["id"].to_string();
+                        let pwd = cap.name("pwd").map(|m| m.as_str().to_string());
+                        let url = match &pwd {
+                            Some(pwd) => format!("https://{}/j/{}?pwd={}", host, meeting_id, pwd),
+                            None => format!("https://{}/j/{}", host, meeting_id),
+                        };
+                        MeetingLink {
+                            provider: self.provider().to_string(),
+                            url,
+                            meeting_id: Some(meeting_id),
+                            passcode: written_passcode.or(pwd),
+                        }
+                    }
+                };
+                (start, link)
+            })
+            .collect()
+    }
+}
+
+static REGIONAL_SUBDOMAIN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:www|[a-z]{2}\d{2}(?:web)?)$").unwrap());
+
+/// us02web・us06・eu01web のような、参加者ごとに割り当てられる地域のサブドメインか
+fn is_regional_subdomain(subdomain: &str) -> bool {
+    REGIONAL_SUBDOMAIN.is_match(subdomain)
+}
+
+/**
+ * Microsoft Teams の会議(職場・学校の meetup-join と、個人用の teams.live.com)
+ *
+ * ```
+ * use calendar_notice::meeting_link::{builtin::teams, MeetingLinkExtractor};
+ *
+ * let link = teams()
+ *     .extract("<https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc%40thread.v2/0?context=%7b%7d>")
+ *     .unwrap();
+ * assert_eq!(link.url, "https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc%40thread.v2/0?context=%7b%7d");
+ *
+ * let live = teams().extract("https://teams.live.com/meet/9334567890123?p=AbCdEf").unwrap();
+ * assert_eq!(live.meeting_id.as_deref(), Some("9334567890123"));
+ * assert_eq!(live.passcode.as_deref(), Some("AbCdEf"));
+ * ```
+ */
+pub fn teams() -> RegexExtractor {
+    RegexExtractor::new(
+        "teams",
+        r#"https://teams\.microsoft\.com/l/meetup-join/[^\s"<>]+|https://teams\.live\.com/meet/(?P<id>\d+)(?:\?p=(?P<passcode>\w+))?"#,
+    )
+    .unwrap()
+}
+
+/**
+ * Google Meet
+ *
+ * ```
+ * use calendar_notice::meeting_link::{builtin::meet, MeetingLinkExtractor};
+ *
+ * let link = meet().extract("Join: https://meet.google.com/abc-defg-hij?authuser=0").unwrap();
+ * assert_eq!(link.url, "https://meet.google.com/abc-defg-hij");
+ * assert_eq!(link.meeting_id.as_deref(), Some("abc-defg-hij"));
+ * assert_eq!(meet().extract("https://meet.google.com/landing"), None);
+ * ```
+ */
+pub fn meet() -> RegexExtractor {
+    RegexExtractor::new(
+        "meet",
+        r"https://meet\.google\.com/(?P<id>[a-z]{3}-[a-z]{4}-[a-z]{3})",
+    )
+    .unwrap()
+}
+
+/**
+ * Webex の個人用ルームと、会議ごとのリンク
+ *
+ * ```
+ * use calendar_notice::meeting_link::{builtin::webex, MeetingLinkExtractor};
+ *
+ * let room = webex().extract("https://example.webex.com/meet/alice.smith").unwrap();
+ * assert_eq!(room.meeting_id.as_deref(), Some("alice.smith"));
+ *
+ * let meeting = webex()
+ *     .extract("https://example.webex.com/example/j.php?MTID=m1234567890abcdef")
+ *     .unwrap();
+ * assert_eq!(meeting.url, "https://example.webex.com/example/j.php?MTID=m1234567890abcdef");
+ * assert_eq!(meeting.meeting_id.as_deref(), Some("m1234567890abcdef"));
+ * ```
+ */
+pub fn webex() -> RegexExtractor {
+    RegexExtractor::new(
+        "webex",
+        r"https://[\w-]+\.webex\.com/(?:meet/|[\w-]+/j\.php\?MTID=)(?P<id>[\w.-]*\w)",
+    )
+    .unwrap()
+}
+
+/**
+ * Slack のハドル
+ *
+ * ```
+ * use calendar_notice::meeting_link::{builtin::slack_huddle, MeetingLinkExtractor};
+ *
+ * let link = slack_huddle().extract("https://app.slack.com/huddle/T0123ABCD/C0456EFGH").unwrap();
+ * assert_eq!(link.url, "https://app.slack.com/huddle/T0123ABCD/C0456EFGH");
+ * assert_eq!(link.meeting_id.as_deref(), Some("C0456EFGH"));
+ * assert_eq!(slack_huddle().extract("https://example.slack.com/archives/C0456EFGH"), None);
+ * ```
+ */
+pub fn slack_huddle() -> RegexExtractor {
+    RegexExtractor::new(
+        "slack",
+        r"https://app\.slack\.com/huddle/T\w+/(?P<id>[CDG]\w+)",
+    )
+    .unwrap()
+}
+
+/**
+ * Whereby のルーム
+ *
+ * ```
+ * use calendar_notice::meeting_link::{builtin::whereby, MeetingLinkExtractor};
+ *
+ * let link = whereby().extract("https://whereby.com/team-standup").unwrap();
+ * assert_eq!(link.meeting_id.as_deref(), Some("team-standup"));
+ * assert!(whereby().extract("https://acme.whereby.com/design-review").is_some());
+ * ```
+ */
+pub fn whereby() -> RegexExtractor {
+    RegexExtractor::new(
+        "whereby",
+        r"https://(?:[\w-]+\.)?whereby\.com/(?P<id>[\w-]*\w)",
+    )
+    .unwrap()
+}
+
+/**
+ * Jitsi Meet(meet.jit.si と 8x8.vc)
+ *
+ * ```
+ * use calendar_notice::meeting_link::{builtin::jitsi, MeetingLinkExtractor};
+ *
+ * let link = jitsi().extract("https://meet.jit.si/WeeklyRetro.").unwrap();
+ * assert_eq!(link.url, "https://meet.jit.si/WeeklyRetro");
+ * assert_eq!(link.meeting_id.as_deref(), Some("WeeklyRetro"));
+ * assert!(jitsi().extract("https://8x8.vc/vpaas-magic-cookie-abc/WeeklyRetro").is_some());
+ * ```
+ */
+pub fn jitsi() -> RegexExtractor {
+    RegexExtractor::new(
+        "jitsi",
+        r"https://(?:meet\.jit\.si|8x8\.vc(?:/[\w-]+)?)/(?P<id>[\w-]*\w)",
+    )
+    .unwrap()
+}
+
+/**
+ * GoTo Meeting
+ *
+ * ```
+ * use calendar_notice::meeting_link::{builtin::goto_meeting, MeetingLinkExtractor};
+ *
+ * let link = goto_meeting().extract("https://meet.goto.com/123456789").unwrap();
+ * assert_eq!(link.meeting_id.as_deref(), Some("123456789"));
+ * assert!(goto_meeting().extract("https://global.gotomeeting.com/join/123456789").is_some());
+ * ```
+ */
+pub fn goto_meeting() -> RegexExtractor {
+    RegexExtractor::new(
+        "gotomeeting",
+        r"https://(?:global\.gotomeeting\.com/join|meet\.goto\.com|app\.goto\.com/meeting)/(?P<id>\d+)",
+    )
+    .unwrap()
+}
+
+/**
+ * Amazon Chime
+ *
+ * ```
+ * use calendar_notice::meeting_link::{builtin::chime, MeetingLinkExtractor};
+ *
+ * let link = chime().extract("https://chime.aws/1234567890").unwrap();
+ * assert_eq!(link.meeting_id.as_deref(), Some("1234567890"));
+ * assert_eq!(chime().extract("https://chime.aws/download"), None);
+ * ```
+ */
+pub fn chime() -> RegexExtractor {
+    RegexExtractor::new("chime", r"https://chime\.aws/(?P<id>\d+)").unwrap()
+}
+
+/**
+ * Around のルーム
+ *
+ * ```
+ * use calendar_notice::meeting_link::{builtin::around, MeetingLinkExtractor};
+ *
+ * let link = around().extract("https://meet.around.co/r/design-sync").unwrap();
+ * assert_eq!(link.meeting_id.as_deref(), Some("design-sync"));
+ * ```
+ */
+pub fn around() -> RegexExtractor {
+    RegexExtractor::new(
+        "around",
+        r"https://(?:meet\.)?around\.co/r/(?P<id>[\w-]*\w)",
+    )
+    .unwrap()
+}
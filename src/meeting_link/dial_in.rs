@@ -0,0 +1,248 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::repository::models::{ConferenceEntryPoint, EventDialIn};
+
+/// 番号と、同じ行で次の番号までに書かれたワンタップ・PIN・国
+static NUMBER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?:\((?P<region>[A-Z]{2})\)[ \t]*)?(?P<number>\+\d[\d \-.()]{5,}\d)(?P<rest>[^\n+]*)",
+    )
+    .unwrap()
+});
+static ONE_TAP: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^,,(?P<id>\d+)#(?:,,,,\*?(?P<passcode>\d+)#)?").unwrap());
+static PIN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)PIN\s*[:：]?\s*(?P<id>\d[\d ]*\d)\s*#?").unwrap());
+static PASSCODE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:passcode|password|パスコード|パスワード)\s*[:：]\s*(?P<passcode>\d+)\b")
+        .unwrap()
+});
+/// 電話で入力する会議 ID。Teams は会議 ID と別に書かれる電話会議 ID を優先する
+static MEETING_IDS: LazyLock<[Regex; 2]> = LazyLock::new(|| {
+    [
+        Regex::new(r"(?i)(?:phone\s*conference\s*id|電話会議\s*ID)\s*[:：]\s*(?P<id>\d[\d ]*\d)")
+            .unwrap(),
+        Regex::new(
+            r"(?i)(?:meeting\s*id|webinar\s*id|conference\s*id|ミーティング\s*ID|ウェビナー\s*ID|会議\s*ID)\s*[:：]\s*(?P<id>\d[\d ]*\d)",
+        )
+        .unwrap(),
+    ]
+});
+
+/**
+ * 説明文から見つかった、電話で参加するための番号
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DialIn {
+    /// 書かれたままの番号
+    pub number: String,
+    /// 番号に添えられた国・地域
+    pub country: Option<String>,
+    /// 数字だけにした会議 ID(Meet は PIN)
+    pub meeting_id: String,
+    pub passcode: Option<String>,
+}
+
+/**
+ * Zoom・Teams・Meet の説明文にある電話での参加方法を抽出する。
+ * ワンタップ(+81...,,123456#)、番号の一覧、PIN 付きの番号に対応し、同じ番号は 1 つにまとめる。
+ * 会議 ID が分からない番号は、主催者の連絡先などとみなして除く
+ *
+ * ```
+ * use calendar_notice::meeting_link::dial_in::extract_dial_ins;
+ *
+ * let description = "Meeting ID: 954 2835 2872
+ * Passcode: 314159
+ * One tap mobile
+ * +16465588656,,95428352872#,,,,*314159# US (New York)
+ * Dial by your location
+ *         +1 646 558 8656 US (New York)
+ *         +81 3 4578 1488 Japan";
+ * let dial_ins = extract_dial_ins(description);
+ * assert_eq!(dial_ins.len(), 2);
+ * assert_eq!(dial_ins[0].number, "+16465588656");
+ * assert_eq!(dial_ins[0].country.as_deref(), Some("US (New York)"));
+ * assert_eq!(dial_ins[1].number, "+81 3 4578 1488");
+ * assert_eq!(dial_ins[1].country.as_deref(), Some("Japan"));
+ * assert_eq!(dial_ins[1].meeting_id, "95428352872");
+ * assert_eq!(dial_ins[1].passcode.as_deref(), Some("314159"));
+ *
+ * assert_eq!(extract_dial_ins("担当: 山田 +81 90-1234-5678"), vec![]);
+ * ```
+ */
+pub fn extract_dial_ins(text: &str) -> Vec<DialIn> {
+    // Google Meet の説明文にある、書字方向の制御文字を除く
+    let text: String = text
+        .chars()
+        .filter(|c| !('\u{202a}'..='\u{202e}').contains(c))
+        .collect();
+    let meeting_id = MEETING_IDS
+        .iter()
+        .find_map(|regex| regex.captures(&text).map(|cap| digits(&cap["id"])));
+    let passcode = PASSCODE
+        .captures(&text)
+        .map(|cap| cap["passcode"].to_string());
+
+    let mut dial_ins: Vec<DialIn> = vec![];
+    for cap in NUMBER.captures_iter(&text) {
+        let rest = &cap["rest"];
+        let (line_meeting_id, line_passcode, label) = if let Some(tap) = ONE_TAP.captures(rest) {
+            (
+                Some(tap["id"].to_string()),
+                tap.name("passcode").map(|m| m.as_str().to_string()),
+                rest[tap.get(0).unwrap().end()..].to_string(),
+            )
+        } else if let Some(pin_cap) = PIN.captures(rest) {
+            (
+                Some(digits(&pin_cap["id"])),
+                None,
+                PIN.replace(rest, "").into_owned(),
+            )
+        } else {
+            (None, None, rest.to_string())
+        };
+        let Some(meeting_id) = line_meeting_id.or(meeting_id.clone()) else {
+            continue;
+        };
+
+        let label = label.trim_matches(|c: char| c.is_whitespace() || c == ',' || c == '#');
+        let dial_in = DialIn {
+            number: cap["number"].trim().to_string(),
+            country: cap
+                .name("region")
+                .map(|m| m.as_str().to_string())
+                .or((!label.is_empty()).then(|| label.to_string())),
+            meeting_id,
+            passcode: line_passcode.or(passcode.clone()),
+        };
+        match dial_ins
+            .iter_mut()
+            .find(|existing| digits(&existing.number) == digits(&dial_in.number))
+        {
+            Some(existing) => {
+                if existing.country.is_none() {
+                    existing.country = dial_in.country;
+                }
+            }
+            None => dial_ins.push(dial_in),
+        }
+    }
+    dial_ins
+}
+
+/**
+ * 電話で参加するときに発信する tel: の URI。説明文から抽出した番号より会議情報の番号を優先する
+ */
+pub fn dial_in_uri(
+    entry_points: &[ConferenceEntryPoint],
+    dial_ins: &[EventDialIn],
+) -> Option<String> {
+    entry_points
+        .iter()
+        .find_map(ConferenceEntryPoint::one_tap)
+        .or(dial_ins.first().map(EventDialIn::one_tap))
+}
+
+/**
+ * 番号に続けて会議 ID とパスコードを入力する tel: の URI
+ *
+ * ```
+ * use calendar_notice::meeting_link::dial_in::one_tap_uri;
+ *
+ * assert_eq!(
+ *     one_tap_uri("+1 646 558 8656", Some("954 2835 2872"), Some("314159")),
+ *     "tel:+16465588656,,95428352872#,,,,*314159#"
+ * );
+ * assert_eq!(one_tap_uri("+81 3-4578-1490", Some("123456789"), None), "tel:+81345781490,,123456789#");
+ * assert_eq!(one_tap_uri("03-1234-5678", None, Some("1")), "tel:0312345678");
+ * ```
+ */
+pub fn one_tap_uri(number: &str, meeting_id: Option<&str>, passcode: Option<&str>) -> String {
+    let plus = if number.trim_start().starts_with('+') {
+        "+"
+    } else {
+        ""
+    };
+    let mut uri = format!("tel:{}{}", plus, digits(number));
+    if let Some(meeting_id) = meeting_id {
+        uri.push_str(&format!(",,{}#", digits(meeting_id)));
+        if let Some(passcode) = passcode {
+            uri.push_str(&format!(",,,,*{}#", passcode));
+        }
+    }
+    uri
+}
+
+fn digits(text: &str) -> String {
+    text.chars().filter(char::is_ascii_digit).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_teams() {
+        let description = "Microsoft Teams meeting
+Join on your computer, mobile app or room device
+Click here to join the meeting
+Meeting ID: 123 456 789 012
+Passcode: AbC123
+Or call in (audio only)
++81 3-4578-1490,,987654321#   Japan, Tokyo
++1 323-555-0166,,987654321#   United States, Los Angeles
+Phone Conference ID: 987 654 321#";
+        assert_eq!(
+            extract_dial_ins(description),
+            vec![
+                DialIn {
+                    number: "+81 3-4578-1490".to_string(),
+                    country: Some("Japan, Tokyo".to_string()),
+                    meeting_id: "987654321".to_string(),
+                    passcode: None,
+                },
+                DialIn {
+                    number: "+1 323-555-0166".to_string(),
+                    country: Some("United States, Los Angeles".to_string()),
+                    meeting_id: "987654321".to_string(),
+                    passcode: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_meet() {
+        let description = "Join with Google Meet: https://meet.google.com/abc-defg-hij
+Join by phone
+\u{202a}(US) +1 650-555-0123\u{202c} PIN: \u{202a}123 456 789\u{202c}#
+More phone numbers: https://tel.meet/abc-defg-hij?pin=123456789";
+        assert_eq!(
+            extract_dial_ins(description),
+            vec![DialIn {
+                number: "+1 650-555-0123".to_string(),
+                country: Some("US".to_string()),
+                meeting_id: "123456789".to_string(),
+                passcode: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_same_number_in_link_and_text() {
+        // HTML の本文では、ワンタップのリンクと表示文字列に同じ番号が並ぶ
+        let description =
+            " tel:+81345781490,,987654321#  +81 3-4578-1490,,987654321#   Japan, Tokyo";
+        assert_eq!(
+            extract_dial_ins(description),
+            vec![DialIn {
+                number: "+81345781490".to_string(),
+                country: Some("Japan, Tokyo".to_string()),
+                meeting_id: "987654321".to_string(),
+                passcode: None,
+            }]
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use std::str::FromStr;
+
+use regex::Regex;
+
+use crate::{env::Env, repository::models::EventMeetingLink};
+
+pub mod app_link;
+pub mod builtin;
+pub mod dial_in;
+pub mod normalize;
+
+/**
+ * 文字列から見つかった、会議に参加するためのリンク
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MeetingLink {
+    /// zoom / teams / webex など、抽出した extractor の名前
+    pub provider: String,
+    pub url: String,
+    /// 会議 ID やルーム名。サービスごとの表記のまま
+    pub meeting_id: Option<String>,
+    pub passcode: Option<String>,
+}
+
+impl From<&EventMeetingLink> for MeetingLink {
+    fn from(link: &EventMeetingLink) -> Self {
+        Self {
+            provider: link.provider.clone(),
+            url: link.url.clone(),
+            meeting_id: link.meeting_id.clone(),
+            passcode: link.passcode.clone(),
+        }
+    }
+}
+
+/**
+ * 会議サービスごとのリンクの抽出方法
+ */
+pub trait MeetingLinkExtractor: Send + Sync {
+    /// MeetingLink.provider に使う名前
+    fn provider(&self) -> &str;
+
+    /// 文字列に含まれるすべてのリンクと、その開始位置(バイト)
+    fn find_all(&self, text: &str) -> Vec<(usize, MeetingLink)>;
+
+    /// 最初に見つかったリンク
+    fn extract(&self, text: &str) -> Option<MeetingLink> {
+        self.find_all(text).into_iter().next().map(|(_, link)| link)
+    }
+}
+
+/**
+ * 正規表現で抽出する extractor。一致した部分をそのまま URL とし、
+ * 名前付きグループ id / passcode があれば会議 ID・パスコードにする
+ *
+ * ```
+ * use calendar_notice::meeting_link::{MeetingLinkExtractor, RegexExtractor};
+ *
+ * let extractor = RegexExtractor::new("intra", r"https://meet\.example\.com/(?P<id>\d+)").unwrap();
+ * let link = extractor.extract("参加: https://meet.example.com/12345 まで").unwrap();
+ * assert_eq!(link.provider, "intra");
+ * assert_eq!(link.url, "https://meet.example.com/12345");
+ * assert_eq!(link.meeting_id.as_deref(), Some("12345"));
+ * assert_eq!(link.passcode, None);
+ * ```
+ */
+#[derive(Debug, Clone)]
+pub struct RegexExtractor {
+    provider: String,
+    regex: Regex,
+}
+impl RegexExtractor {
+    pub fn new(provider: &str, pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            provider: provider.to_string(),
+            regex: Regex::new(pattern)?,
+        })
+    }
+}
+impl MeetingLinkExtractor for RegexExtractor {
+    fn provider(&self) -> &str {
+        &self.provider
+    }
+
+    fn find_all(&self, text: &str) -> Vec<(usize, MeetingLink)> {
+        self.regex
+            .captures_iter(text)
+            .map(|cap| {
+                let whole = cap.get(0).unwrap();
+                let group = |name: &str| cap.name(name).map(|m| m.as_str().to_string());
+                (
+                    whole.start(),
+                    MeetingLink {
+                        provider: self.provider.clone(),
+                        url: whole.as_str().to_string(),
+                        meeting_id: group("id"),
+                        passcode: group("passcode"),
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+/**
+ * MEETING_LINK_PATTERNS の値。"<名前>=<正規表現>" を ; 区切りで指定する。
+ * 正規表現には ; を使えない
+ *
+ * ```
+ * use calendar_notice::meeting_link::MeetingLinkPatterns;
+ *
+ * let patterns: MeetingLinkPatterns =
+ *     r"intra=https://meet\.example\.com/(?P<id>\d+); vc = https://vc\.example\.com/\w+".parse().unwrap();
+ * assert_eq!(patterns.0.len(), 2);
+ *
+ * assert!("intra".parse::<MeetingLinkPatterns>().is_err());
+ * assert!("intra=https://(".parse::<MeetingLinkPatterns>().is_err());
+ * ```
+ */
+#[derive(Debug, Clone, Default)]
+pub struct MeetingLinkPatterns(pub Vec<RegexExtractor>);
+impl FromStr for MeetingLinkPatterns {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(';')
+            .map(str::trim)
+            .filter(|pattern| !pattern.is_empty())
+            .map(|pattern| {
+                let (provider, regex) = pattern
+                    .split_once('=')
+                    .ok_or(format!("{} must be <name>=<regex>", pattern))?;
+                let provider = provider.trim();
+                if provider.is_empty() {
+                    return Err(format!("{} must be <name>=<regex>", pattern));
+                }
+                RegexExtractor::new(provider, regex.trim()).map_err(|e| e.to_string())
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .map(MeetingLinkPatterns)
+    }
+}
+
+/**
+ * 会議のリンクの extractor の一覧。先に登録したものほど優先する
+ */
+pub struct MeetingLinkRegistry {
+    extractors: Vec<Box<dyn MeetingLinkExtractor>>,
+}
+impl Default for MeetingLinkRegistry {
+    /// 組み込みの extractor だけ
+    fn default() -> Self {
+        Self {
+            extractors: builtin::builtin_extractors(),
+        }
+    }
+}
+impl MeetingLinkRegistry {
+    /**
+     * MEETING_LINK_PATTERNS の extractor を組み込みのものより優先して使う
+     */
+    pub fn from_env(env: &Env) -> Self {
+        let mut extractors: Vec<Box<dyn MeetingLinkExtractor>> = env
+            .meeting_link_patterns
+            .0
+            .iter()
+            .cloned()
+            .map(|extractor| Box::new(extractor) as Box<dyn MeetingLinkExtractor>)
+            .collect();
+        extractors.extend(builtin::builtin_extractors());
+        Self { extractors }
+    }
+
+    /// 最も低い優先度で extractor を追加する
+    pub fn register(&mut self, extractor: Box<dyn MeetingLinkExtractor>) {
+        self.extractors.push(extractor);
+    }
+
+    /**
+     * 文字列に含まれる会議のリンクを、見つかった順に重複を除いて返す。
+     * 同じ位置で複数の extractor に一致した場合は優先する方を使う
+     *
+     * ```
+     * use calendar_notice::meeting_link::MeetingLinkRegistry;
+     *
+     * let description = "Webex: https://example.webex.com/meet/alice
+     * 予備: https://meet.google.com/abc-defg-hij
+     * 再掲: https://example.webex.com/meet/alice";
+     * let links = MeetingLinkRegistry::default().extract_all(description);
+     * assert_eq!(
+     *     links.iter().map(|link| link.provider.as_str()).collect::<Vec<_>>(),
+     *     vec!["webex", "meet"]
+     * );
+     * ```
+     */
+    pub fn extract_all(&self, text: &str) -> Vec<MeetingLink> {
+        let mut found: Vec<(usize, usize, MeetingLink)> = self
+            .extractors
+            .iter()
+            .enumerate()
+            .flat_map(|(priority, extractor)| {
+                extractor
+                    .find_all(text)
+                    .into_iter()
+                    .map(move |(start, link)| (start, priority, link))
+            })
+            .collect();
+        found.sort_by_key(|(start, priority, _)| (*start, *priority));
+
+        let mut links: Vec<MeetingLink> = vec![];
+        let mut last_start = None;
+        for (start, _, link) in found {
+            if last_start == Some(start) || links.iter().any(|l| l.url == link.url) {
+                continue;
+            }
+            last_start = Some(start);
+            links.push(link);
+        }
+        links
+    }
+
+    /**
+     * 優先する順に並べた複数の文字列から、会議のリンクを重複を除いて返す。
+     * それぞれ HTML をテキストにし、リダイレクトで包まれた URL を元に戻してから探す
+     *
+     * ```
+     * use calendar_notice::meeting_link::MeetingLinkRegistry;
+     *
+     * let links = MeetingLinkRegistry::default().extract_from_sources(&[
+     *     "https://meet.google.com/abc-defg-hij",
+     *     "Room A / https://zoom.us/j/95428352872",
+     *     r#"<a href="https://www.google.com/url?q=https://meet.google.com/abc-defg-hij&amp;sa=D">Meet</a>"#,
+     * ]);
+     * assert_eq!(
+     *     links.iter().map(|link| link.url.as_str()).collect::<Vec<_>>(),
+     *     vec!["https://meet.google.com/abc-defg-hij", "https://zoom.us/j/95428352872"]
+     * );
+     * ```
+     */
+    pub fn extract_from_sources(&self, sources: &[&str]) -> Vec<MeetingLink> {
+        let mut links: Vec<MeetingLink> = vec![];
+        for source in sources {
+            for link in self.extract_all(&normalize::normalize_text(source)) {
+                if !links.iter().any(|l| l.url == link.url) {
+                    links.push(link);
+                }
+            }
+        }
+        links
+    }
+
+    /// 最初に見つかった会議のリンク
+    pub fn extract_first(&self, text: &str) -> Option<MeetingLink> {
+        self.extract_all(text).into_iter().next()
+    }
+
+    /// 会議のリンクとして扱う URL か
+    pub fn is_meeting_link(&self, url: &str) -> bool {
+        self.extractors
+            .iter()
+            .any(|extractor| extractor.extract(url).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_patterns_take_priority() {
+        let env = Env {
+            meeting_link_patterns: "zoom-gov=https://[\\w-]+\\.zoomgov\\.com/j/(?P<id>\\d+); myzoom=https://corp\\.zoom\\.us/j/(?P<id>\\d+)"
+                .parse()
+                .unwrap(),
+            ..Default::default()
+        };
+        let registry = MeetingLinkRegistry::from_env(&env);
+        let links = registry.extract_all(
+            "https://agency.zoomgov.com/j/1234567890 https://corp.zoom.us/j/95428352872",
+        );
+        assert_eq!(
+            links,
+            vec![
+                MeetingLink {
+                    provider: "zoom-gov".to_string(),
+                    url: "https://agency.zoomgov.com/j/1234567890".to_string(),
+                    meeting_id: Some("1234567890".to_string()),
+                    passcode: None,
+                },
+                MeetingLink {
+                    provider: "myzoom".to_string(),
+                    url: "https://corp.zoom.us/j/95428352872".to_string(),
+                    meeting_id: Some("95428352872".to_string()),
+                    passcode: None,
+                },
+            ]
+        );
+
+        // 設定がなければ組み込みの Zoom として抽出する
+        let link = MeetingLinkRegistry::default()
+            .extract_first("https://corp.zoom.us/j/95428352872")
+            .unwrap();
+        assert_eq!(link.provider, "zoom");
+        assert_eq!(link.url, "https://corp.zoom.us/j/95428352872");
+    }
+
+    #[test]
+    fn test_register() {
+        let mut registry = MeetingLinkRegistry::default();
+        assert!(!registry.is_meeting_link("https://vc.example.com/room"));
+        registry.register(Box::new(
+            RegexExtractor::new("vc", r"https://vc\.example\.com/(?P<id>\w+)").unwrap(),
+        ));
+        assert!(registry.is_meeting_link("https://vc.example.com/room"));
+        assert!(!registry.is_meeting_link("https://docs.example.com/agenda"));
+    }
+}
@@ -0,0 +1,154 @@
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+use reqwest::Url;
+
+/// リダイレクトが何重にも包まれていても、この回数までしか外さない
+const MAX_UNWRAP_DEPTH: usize = 3;
+
+static ENTITY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"&(#[0-9]{1,7}|#[xX][0-9a-fA-F]{1,6}|[a-zA-Z]{2,8});").unwrap());
+static ANCHOR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?is)<a\s[^>]*?href\s*=\s*["']([^"']+)["'][^>]*>"#).unwrap());
+/// 改行になるタグ
+static LINE_BREAK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<br\s*/?>|</(?:p|div|li|tr|h[1-6])\s*>").unwrap());
+static TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"</?[a-zA-Z][a-zA-Z0-9]*(?:\s[^>]*)?/?>").unwrap());
+static URL: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"https?://[^\s"'<>]+"#).unwrap());
+
+/**
+ * HTML の文字参照を文字に戻す。&nbsp; は空白にし、知らない参照はそのまま残す
+ *
+ * ```
+ * use calendar_notice::meeting_link::normalize::decode_entities;
+ *
+ * assert_eq!(
+ *     decode_entities("https://zoom.us/j/1?pwd=a&amp;uname=b&#46;&#x2F;&nbsp;&copy;"),
+ *     "https://zoom.us/j/1?pwd=a&uname=b./ &copy;"
+ * );
+ * assert_eq!(decode_entities("Q&A; R&D"), "Q&A; R&D");
+ * ```
+ */
+pub fn decode_entities(text: &str) -> String {
+    ENTITY
+        .replace_all(text, |cap: &Captures| {
+            let entity = &cap[1];
+            let decoded = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                    None => entity
+                        .strip_prefix('#')
+                        .and_then(|dec| dec.parse().ok())
+                        .and_then(char::from_u32),
+                },
+            };
+            match decoded {
+                Some(c) => c.to_string(),
+                None => cap[0].to_string(),
+            }
+        })
+        .into_owned()
+}
+
+/**
+ * HTML の本文をテキストにする。リンクは表示文字列の前に href の URL を残し、改行になるタグは改行にする。
+ * Outlook のテキストの本文にある `<https://...>` のような、タグではない山括弧は残す
+ *
+ * ```
+ * use calendar_notice::meeting_link::normalize::html_to_text;
+ *
+ * assert_eq!(
+ *     html_to_text(r#"<p>参加:<br><a href="https://zoom.us/j/1?pwd=a&amp;b=c">こちら</a></p><div>資料 &lt;PDF&gt;</div>"#),
+ *     "参加:\n https://zoom.us/j/1?pwd=a&b=c こちら\n資料 <PDF>\n"
+ * );
+ * assert_eq!(
+ *     html_to_text("Join <https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc>"),
+ *     "Join <https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc>"
+ * );
+ * ```
+ */
+pub fn html_to_text(html: &str) -> String {
+    let text = ANCHOR.replace_all(html, " $1 ");
+    let text = LINE_BREAK.replace_all(&text, "\n");
+    let text = TAG.replace_all(&text, "");
+    decode_entities(&text)
+}
+
+/**
+ * Outlook の安全なリンク(safelinks)、Google のリダイレクト、Proofpoint の URL Defense で包まれた URL を、元の URL に戻す。
+ * 包まれていなければそのまま返す
+ *
+ * ```
+ * use calendar_notice::meeting_link::normalize::unwrap_redirect;
+ *
+ * assert_eq!(
+ *     unwrap_redirect("https://nam02.safelinks.protection.outlook.com/?url=https%3A%2F%2Fzoom.us%2Fj%2F95428352872%3Fpwd%3Dabc&data=05%7C01&reserved=0"),
+ *     "https://zoom.us/j/95428352872?pwd=abc"
+ * );
+ * assert_eq!(
+ *     unwrap_redirect("https://www.google.com/url?q=https://meet.google.com/abc-defg-hij&sa=D&source=calendar"),
+ *     "https://meet.google.com/abc-defg-hij"
+ * );
+ * assert_eq!(
+ *     unwrap_redirect("https://urldefense.com/v3/__https://example.webex.com/meet/alice__;!!abc$"),
+ *     "https://example.webex.com/meet/alice"
+ * );
+ * assert_eq!(unwrap_redirect("https://zoom.us/j/1"), "https://zoom.us/j/1");
+ * ```
+ */
+pub fn unwrap_redirect(url: &str) -> String {
+    let mut url = url.to_string();
+    for _ in 0..MAX_UNWRAP_DEPTH {
+        match unwrap_once(&url) {
+            Some(unwrapped) => url = unwrapped,
+            None => break,
+        }
+    }
+    url
+}
+
+fn unwrap_once(url: &str) -> Option<String> {
+    let parsed = Url::parse(url).ok()?;
+    let host = parsed.host_str()?;
+    let query_param = |name: &str| {
+        parsed
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    if host.ends_with(".safelinks.protection.outlook.com") {
+        return query_param("url");
+    }
+    if (host == "www.google.com" || host == "google.com") && parsed.path() == "/url" {
+        return query_param("q");
+    }
+    if host == "urldefense.com" || host == "urldefense.proofpoint.com" {
+        // /v3/__<URL>__;<チェックサム>
+        let wrapped = url.split_once("/v3/__")?.1;
+        return wrapped.split_once("__;").map(|(url, _)| url.to_string());
+    }
+    None
+}
+
+/**
+ * 会議のリンクを探すためのテキストにする。HTML をテキストにし、リダイレクトで包まれた URL を元に戻す
+ *
+ * ```
+ * use calendar_notice::meeting_link::normalize::normalize_text;
+ *
+ * let description = r#"<a href="https://eur01.safelinks.protection.outlook.com/?url=https%3A%2F%2Fchime.aws%2F1234567890&amp;data=05">Chime で参加</a>"#;
+ * assert_eq!(normalize_text(description), " https://chime.aws/1234567890 Chime で参加");
+ * ```
+ */
+pub fn normalize_text(text: &str) -> String {
+    let text = html_to_text(text);
+    URL.replace_all(&text, |cap: &Captures| unwrap_redirect(&cap[0]))
+        .into_owned()
+}
@@ -0,0 +1,505 @@
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use reqwest::{StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    env::Env,
+    provider::{self, CalendarEvent, CalendarProvider, EventBatch, SyncWindow},
+    repository::models,
+    timezone::local_to_utc,
+};
+
+pub mod oauth;
+
+/// events.provider に保存する名前
+pub const MICROSOFT_PROVIDER_NAME: &str = "microsoft";
+
+/// 日時を UTC、本文をテキストで受け取る
+const PREFER_HEADER: &str =
+    r#"outlook.timezone="UTC", outlook.body-content-type="text", odata.maxpagesize=50"#;
+
+#[derive(Debug)]
+pub enum Error {
+    Reqwest(reqwest::Error),
+    TokenNotFound,
+    Unauthorized,
+    /// delta の状態が失効している(410 Gone)
+    SyncStateNotFound,
+    Http {
+        status: StatusCode,
+        body: String,
+    },
+    Parse(String),
+}
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Reqwest(e)
+    }
+}
+
+/// calendarView/delta の 1 ページ。最後のページだけが deltaLink を持つ
+#[derive(Debug, Deserialize)]
+struct DeltaPage {
+    value: Vec<GraphEvent>,
+    #[serde(rename = "@odata.nextLink")]
+    next_link: Option<String>,
+    #[serde(rename = "@odata.deltaLink")]
+    delta_link: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GraphEvent {
+    pub id: String,
+    pub subject: Option<String>,
+    pub body: Option<ItemBody>,
+    pub start: Option<DateTimeTimeZone>,
+    pub end: Option<DateTimeTimeZone>,
+    pub is_all_day: bool,
+    pub is_cancelled: bool,
+    /// free / tentative / busy / oof / workingElsewhere / unknown
+    pub show_as: Option<String>,
+    pub series_master_id: Option<String>,
+    /// 繰り返しの回の本来の開始日時
+    pub original_start: Option<DateTime<Utc>>,
+    pub location: Option<Location>,
+    pub attendees: Vec<GraphAttendee>,
+    pub organizer: Option<Recipient>,
+    pub online_meeting: Option<OnlineMeetingInfo>,
+    /// delta で削除されたイベントにだけ付く
+    #[serde(rename = "@removed")]
+    pub removed: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ItemBody {
+    pub content_type: Option<String>,
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DateTimeTimeZone {
+    /// 2026-10-20T01:00:00.0000000 のようなオフセットのない日時
+    pub date_time: String,
+    pub time_zone: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Location {
+    pub display_name: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphAttendee {
+    pub email_address: EmailAddress,
+    pub status: Option<GraphResponseStatus>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct GraphResponseStatus {
+    /// none / organizer / tentativelyAccepted / accepted / declined / notResponded
+    pub response: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recipient {
+    pub email_address: EmailAddress,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EmailAddress {
+    pub name: Option<String>,
+    pub address: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnlineMeetingInfo {
+    pub join_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct User {
+    mail: Option<String>,
+    user_principal_name: Option<String>,
+}
+
+/// Graph の response を Google Calendar の responseStatus に合わせる
+fn response_status_of(response: &str) -> Option<String> {
+    match response {
+        "accepted" | "organizer" => Some("accepted".to_string()),
+        "declined" => Some("declined".to_string()),
+        "tentativelyAccepted" => Some("tentative".to_string()),
+        "notResponded" | "none" => Some("needsAction".to_string()),
+        _ => None,
+    }
+}
+
+impl DateTimeTimeZone {
+    /// 終日の予定の日付。終日の予定の日時はその日の 0 時
+    fn date(&self) -> Option<NaiveDate> {
+        NaiveDateTime::parse_from_str(&self.date_time, "%Y-%m-%dT%H:%M:%S%.f")
+            .ok()
+            .map(|naive| naive.date())
+    }
+
+    /**
+     * UTC にする。終日の予定は日付を default_time_zone(なければシステムのタイムゾーン)の 0 時として扱う
+     */
+    fn to_utc(&self, all_day: bool, default_time_zone: Option<Tz>) -> Option<DateTime<Utc>> {
+        let naive = NaiveDateTime::parse_from_str(&self.date_time, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+        let default_to_utc = |naive| match default_time_zone {
+            Some(tz) => local_to_utc(naive, &tz),
+            None => local_to_utc(naive, &chrono::Local),
+        };
+        if all_day {
+            return Some(default_to_utc(naive.date().and_time(NaiveTime::MIN)));
+        }
+        match self.time_zone.as_deref() {
+            None | Some("UTC") => Some(naive.and_utc()),
+            Some(time_zone) => match time_zone.parse::<Tz>() {
+                Ok(tz) => Some(local_to_utc(naive, &tz)),
+                // Windows のタイムゾーン名は読めないため、既定のタイムゾーンとして扱う
+                Err(_) => Some(default_to_utc(naive)),
+            },
+        }
+    }
+}
+
+impl GraphEvent {
+    /**
+     * プロバイダーに依存しないイベントにする。開始・終了日時が読めない場合は None。
+     * self_email は参加者のうち自分を判定するためのメールアドレス
+     */
+    pub fn to_calendar_event(
+        &self,
+        default_time_zone: Option<Tz>,
+        self_email: Option<&str>,
+    ) -> Option<CalendarEvent> {
+        let start = self
+            .start
+            .as_ref()?
+            .to_utc(self.is_all_day, default_time_zone)?;
+        let end = self
+            .end
+            .as_ref()?
+            .to_utc(self.is_all_day, default_time_zone)?;
+
+        let organizer = self
+            .organizer
+            .as_ref()
+            .and_then(|organizer| organizer.email_address.address.clone());
+        let attendees = self
+            .attendees
+            .iter()
+            .filter_map(|attendee| {
+                let email = attendee.email_address.address.clone()?;
+                Some(models::Attendee {
+                    event_id: self.id.clone(),
+                    display_name: attendee.email_address.name.clone(),
+                    organizer: organizer
+                        .as_ref()
+                        .is_some_and(|organizer| organizer.eq_ignore_ascii_case(&email)),
+                    is_self: self_email
+                        .is_some_and(|self_email| self_email.eq_ignore_ascii_case(&email)),
+                    response_status: attendee
+                        .status
+                        .as_ref()
+                        .and_then(|status| status.response.as_deref())
+                        .and_then(response_status_of),
+                    email,
+                })
+            })
+            .collect();
+        // Teams 会議の参加 URL は本文になくても会議の参加方法として残す
+        let entry_points = self
+            .online_meeting
+            .as_ref()
+            .and_then(|online_meeting| online_meeting.join_url.clone())
+            .map(|uri| models::ConferenceEntryPoint {
+                event_id: self.id.clone(),
+                position: 0,
+                entry_point_type: "video".to_string(),
+                uri,
+                label: None,
+                pin: None,
+                region_code: None,
+            })
+            .into_iter()
+            .collect();
+
+        Some(CalendarEvent {
+            id: self.id.clone(),
+            summary: self.subject.clone(),
+            description: self
+                .body
+                .as_ref()
+                .and_then(|body| body.content.clone())
+                .filter(|content| !content.trim().is_empty()),
+            status: Some(if self.is_cancelled {
+                "cancelled".to_string()
+            } else {
+                "confirmed".to_string()
+            }),
+            start,
+            end,
+            all_day: self.is_all_day,
+            start_date: self
+                .is_all_day
+                .then(|| self.start.as_ref()?.date())
+                .flatten(),
+            end_date: self.is_all_day.then(|| self.end.as_ref()?.date()).flatten(),
+            recurring_event_id: self.series_master_id.clone(),
+            original_start_time: self.original_start,
+            location: self
+                .location
+                .as_ref()
+                .and_then(|location| location.display_name.clone())
+                .filter(|name| !name.trim().is_empty()),
+            event_type: Some(
+                match self.show_as.as_deref() {
+                    Some("oof") => "outOfOffice",
+                    _ => "default",
+                }
+                .to_string(),
+            ),
+            attendees,
+            entry_points,
+            ..Default::default()
+        })
+    }
+}
+
+/**
+ * 差分同期のトークンとして保存する deltaLink と、その同期の期間の終わり
+ */
+#[derive(Debug, Serialize, Deserialize)]
+struct DeltaState {
+    delta_link: String,
+    window_end: DateTime<Utc>,
+}
+
+/**
+ * Outlook / Exchange のカレンダー。Microsoft Graph の calendarView/delta で同期する
+ */
+pub struct MicrosoftProvider {
+    graph_base_url: String,
+    /// 終日の予定を読むタイムゾーン
+    default_time_zone: Option<Tz>,
+}
+impl MicrosoftProvider {
+    /// MICROSOFT_CLIENT_ID が設定されていれば使う
+    pub fn from_env(env: &Env) -> Option<Self> {
+        env.microsoft_client_id.as_ref()?;
+        Some(Self {
+            graph_base_url: env.microsoft_graph_base_url.clone(),
+            default_time_zone: env.display_timezone,
+        })
+    }
+
+    async fn get<T: DeserializeOwned>(&self, access_token: &str, url: &str) -> Result<T, Error> {
+        let response = reqwest::Client::new()
+            .get(url)
+            .bearer_auth(access_token)
+            .header("Prefer", PREFER_HEADER)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let body = response.text().await?;
+        match status {
+            status if status.is_success() => {
+                serde_json::from_str(&body).map_err(|e| Error::Parse(e.to_string()))
+            }
+            StatusCode::UNAUTHORIZED => Err(Error::Unauthorized),
+            StatusCode::GONE => Err(Error::SyncStateNotFound),
+            status => Err(Error::Http { status, body }),
+        }
+    }
+
+    /// 参加者のうち自分を判定するためのメールアドレス
+    async fn self_email(&self, access_token: &str) -> Result<Option<String>, Error> {
+        let user: User = self
+            .get(
+                access_token,
+                &format!(
+                    "{}/v1.0/me?$select=mail,userPrincipalName",
+                    self.graph_base_url
+                ),
+            )
+            .await?;
+        Ok(user.mail.or(user.user_principal_name))
+    }
+
+    /**
+     * url から nextLink を最後まで辿る。削除・キャンセルされたイベントは削除対象にする
+     */
+    async fn follow_delta(
+        &self,
+        url: String,
+        window_end: DateTime<Utc>,
+    ) -> Result<EventBatch, Error> {
+        let access_token = oauth::latest_valid_token().await?.access_token;
+        let self_email = self.self_email(&access_token).await?;
+
+        let mut batch = EventBatch::default();
+        let mut next_url = Some(url);
+        while let Some(url) = next_url.take() {
+            let page: DeltaPage = self.get(&access_token, &url).await?;
+            for event in page.value {
+                if event.removed.is_some() || event.is_cancelled {
+                    batch.deleted_ids.push(event.id);
+                    continue;
+                }
+                match event.to_calendar_event(self.default_time_zone, self_email.as_deref()) {
+                    Some(event) => batch.events.push(event),
+                    None => println!("Skip event {} without valid start or end", event.id),
+                }
+            }
+            match (page.next_link, page.delta_link) {
+                (Some(next_link), _) => next_url = Some(next_link),
+                (None, Some(delta_link)) => {
+                    batch.next_sync_token = serde_json::to_string(&DeltaState {
+                        delta_link,
+                        window_end,
+                    })
+                    .ok();
+                }
+                (None, None) => return Err(Error::Parse("Delta page has no link".to_string())),
+            }
+        }
+        Ok(batch)
+    }
+}
+
+#[async_trait]
+impl CalendarProvider for MicrosoftProvider {
+    fn name(&self) -> &'static str {
+        MICROSOFT_PROVIDER_NAME
+    }
+
+    fn calendar_id(&self) -> String {
+        format!("{}:calendarView", MICROSOFT_PROVIDER_NAME)
+    }
+
+    fn authorization_url(&self) -> Option<String> {
+        oauth::authorization_url(&Env::new())
+    }
+
+    /**
+     * calendarView/delta の最初の同期。delta の期間は最初に決めた期間のまま変わらないため、
+     * 期間が進むたびに同期し直さないよう、1 日先まで取得しておく
+     */
+    async fn list_events(&self, window: SyncWindow) -> Result<EventBatch, provider::Error> {
+        let (start, end) = window.time_range(Utc::now());
+        let window_end = end + chrono::Duration::days(1);
+        let mut url = Url::parse(&format!(
+            "{}/v1.0/me/calendarView/delta",
+            self.graph_base_url
+        ))
+        .map_err(|e| Error::Parse(e.to_string()))?;
+        url.query_pairs_mut()
+            .append_pair("startDateTime", &start.to_rfc3339())
+            .append_pair("endDateTime", &window_end.to_rfc3339());
+        Ok(self.follow_delta(url.to_string(), window_end).await?)
+    }
+
+    async fn list_changes(
+        &self,
+        sync_token: &str,
+        window: SyncWindow,
+    ) -> Result<EventBatch, provider::Error> {
+        let state: DeltaState =
+            serde_json::from_str(sync_token).map_err(|_| provider::Error::SyncTokenExpired)?;
+        // delta の期間を過ぎたら、新しい期間で同期し直す
+        if window.time_range(Utc::now()).1 > state.window_end {
+            return Err(provider::Error::SyncTokenExpired);
+        }
+        Ok(self
+            .follow_delta(state.delta_link, state.window_end)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_calendar_event() {
+        let event: GraphEvent = serde_json::from_str(
+            r#"{
+                "id": "AAMkAG1",
+                "subject": "Design review",
+                "body": { "contentType": "text", "content": "Join: https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc%40thread.v2/0" },
+                "start": { "dateTime": "2026-10-20T01:00:00.0000000", "timeZone": "UTC" },
+                "end": { "dateTime": "2026-10-20T10:30:00.0000000", "timeZone": "Asia/Tokyo" },
+                "isAllDay": false,
+                "isCancelled": false,
+                "showAs": "busy",
+                "type": "occurrence",
+                "seriesMasterId": "AAMkAG0",
+                "originalStart": "2026-10-20T01:00:00Z",
+                "location": { "displayName": "" },
+                "organizer": { "emailAddress": { "name": "Boss", "address": "boss@example.com" } },
+                "attendees": [
+                    { "type": "required", "status": { "response": "none" }, "emailAddress": { "name": "Me", "address": "ME@example.com" } },
+                    { "type": "required", "status": { "response": "accepted" }, "emailAddress": { "name": "Boss", "address": "boss@example.com" } }
+                ],
+                "onlineMeeting": { "joinUrl": "https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc%40thread.v2/0" }
+            }"#,
+        )
+        .unwrap();
+        let event = event
+            .to_calendar_event(None, Some("me@example.com"))
+            .unwrap();
+
+        assert_eq!(event.start.to_rfc3339(), "2026-10-20T01:00:00+00:00");
+        assert_eq!(event.end.to_rfc3339(), "2026-10-20T01:30:00+00:00");
+        assert_eq!(event.recurring_event_id.as_deref(), Some("AAMkAG0"));
+        assert_eq!(event.location, None);
+        assert_eq!(event.event_type.as_deref(), Some("default"));
+        assert_eq!(
+            event
+                .attendees
+                .iter()
+                .map(|a| (a.is_self, a.organizer, a.response_status.as_deref()))
+                .collect::<Vec<_>>(),
+            vec![
+                (true, false, Some("needsAction")),
+                (false, true, Some("accepted"))
+            ]
+        );
+        assert_eq!(event.entry_points[0].entry_point_type, "video");
+    }
+
+    #[test]
+    fn test_all_day_and_out_of_office() {
+        let event: GraphEvent = serde_json::from_str(
+            r#"{
+                "id": "AAMkAG2",
+                "subject": "Vacation",
+                "start": { "dateTime": "2026-10-20T00:00:00.0000000", "timeZone": "UTC" },
+                "end": { "dateTime": "2026-10-21T00:00:00.0000000", "timeZone": "UTC" },
+                "isAllDay": true,
+                "showAs": "oof"
+            }"#,
+        )
+        .unwrap();
+        let tokyo: Tz = "Asia/Tokyo".parse().unwrap();
+        let event = event.to_calendar_event(Some(tokyo), None).unwrap();
+
+        assert!(event.all_day);
+        assert_eq!(event.start.to_rfc3339(), "2026-10-19T15:00:00+00:00");
+        assert_eq!(event.end.to_rfc3339(), "2026-10-20T15:00:00+00:00");
+        assert_eq!(event.event_type.as_deref(), Some("outOfOffice"));
+    }
+}
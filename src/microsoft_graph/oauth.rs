@@ -0,0 +1,228 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
+use warp::Filter;
+
+use crate::{
+    env::Env,
+    oauth::{is_token_expired::is_token_expired, OAuthResponse},
+    provider::{self, SyncWindow},
+    repository::{
+        self,
+        models::{OAuthToken, OAuthTokenUpdate},
+    },
+};
+
+use super::{Error, MicrosoftProvider, MICROSOFT_PROVIDER_NAME};
+
+const AUTH_REDIRECT_PATH: &str = "auth_microsoft";
+/// 予定の読み取りと、参加者のうち自分を判定するためのユーザー情報。offline_access でリフレッシュトークンを受け取る
+const SCOPE: &str = "offline_access User.Read Calendars.Read";
+const CODE_VERIFIER_LENGTH: usize = 64;
+
+/// 認可画面を開いてからリダイレクトされるまでの、state ごとの code_verifier
+static PENDING_CODE_VERIFIERS: LazyLock<Mutex<HashMap<String, String>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn redirect_uri(env: &Env) -> String {
+    format!("{}:{}/{AUTH_REDIRECT_PATH}", env.base_url, env.port)
+}
+
+fn endpoint(env: &Env, name: &str) -> String {
+    format!(
+        "{}/{}/oauth2/v2.0/{}",
+        env.microsoft_login_base_url, env.microsoft_tenant, name
+    )
+}
+
+/**
+ * PKCE の code_verifier と、その SHA-256 を base64url にした code_challenge
+ *
+ * ```
+ * use calendar_notice::microsoft_graph::oauth::code_challenge_of;
+ *
+ * // RFC 7636 Appendix B の例
+ * assert_eq!(
+ *     code_challenge_of("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+ *     "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+ * );
+ * ```
+ */
+pub fn code_challenge_of(code_verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()))
+}
+
+/**
+ * 認可画面の URL。リダイレクトで使う code_verifier を state と紐づけて覚えておく。
+ * MICROSOFT_CLIENT_ID が未設定の場合は None
+ */
+pub fn authorization_url(env: &Env) -> Option<String> {
+    let client_id = env.microsoft_client_id.as_ref()?;
+    let state = uuid::Uuid::new_v4().to_string();
+    let code_verifier: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(CODE_VERIFIER_LENGTH)
+        .map(char::from)
+        .collect();
+    let code_challenge = code_challenge_of(&code_verifier);
+    PENDING_CODE_VERIFIERS
+        .lock()
+        .unwrap()
+        .insert(state.clone(), code_verifier);
+
+    let mut url = Url::parse(&endpoint(env, "authorize")).ok()?;
+    url.query_pairs_mut()
+        .append_pair("client_id", client_id)
+        .append_pair("response_type", "code")
+        .append_pair("redirect_uri", &redirect_uri(env))
+        .append_pair("response_mode", "query")
+        .append_pair("scope", SCOPE)
+        .append_pair("state", &state)
+        .append_pair("code_challenge", &code_challenge)
+        .append_pair("code_challenge_method", "S256");
+    Some(url.to_string())
+}
+
+/// トークンエンドポイントに要求する。クライアントシークレットのないパブリック クライアントとして扱う
+async fn request_token(env: &Env, params: &[(&str, &str)]) -> Result<OAuthResponse, Error> {
+    let client_id = env
+        .microsoft_client_id
+        .as_deref()
+        .ok_or(Error::TokenNotFound)?;
+    let mut form = vec![("client_id", client_id), ("scope", SCOPE)];
+    form.extend_from_slice(params);
+
+    let response = reqwest::Client::new()
+        .post(endpoint(env, "token"))
+        .form(&form)
+        .send()
+        .await?;
+    let status = response.status();
+    let body = response.text().await?;
+    if !status.is_success() {
+        return Err(Error::Http { status, body });
+    }
+    OAuthResponse::parse(&body).map_err(|e| Error::Parse(e.to_string()))
+}
+
+/**
+ * リダイレクトで受け取った認可コードをトークンに交換して保存する
+ */
+pub async fn exchange_code(code: &str, state: &str) -> Result<OAuthToken, Error> {
+    let code_verifier = PENDING_CODE_VERIFIERS
+        .lock()
+        .unwrap()
+        .remove(state)
+        .ok_or(Error::Parse(format!("Unknown state: {}", state)))?;
+
+    let env = Env::new();
+    let redirect_uri = redirect_uri(&env);
+    let response = request_token(
+        &env,
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", &redirect_uri),
+            ("code_verifier", &code_verifier),
+        ],
+    )
+    .await?;
+
+    let oauth_token = OAuthToken {
+        id: uuid::Uuid::new_v4().to_string(),
+        access_token: response.access_token,
+        expires_in: Some(response.expires_in as i64),
+        refresh_token: response.refresh_token,
+        scope: Some(response.scope),
+        token_type: Some(response.token_type),
+        created_at: chrono::Utc::now(),
+        updated_at: chrono::Utc::now(),
+        provider: MICROSOFT_PROVIDER_NAME.to_string(),
+    };
+    repository::oauth_token::create(oauth_token.clone())
+        .map_err(|e| Error::Parse(e.to_string()))?;
+    Ok(oauth_token)
+}
+
+/**
+ * 最新のトークンを返す。期限切れの場合はリフレッシュしてから返す
+ */
+pub async fn latest_valid_token() -> Result<OAuthToken, Error> {
+    let latest_token = repository::oauth_token::find_latest_of(MICROSOFT_PROVIDER_NAME)
+        .map_err(|e| Error::Parse(e.to_string()))?
+        .ok_or(Error::TokenNotFound)?;
+    if !is_token_expired(&latest_token, chrono::Utc::now()) {
+        return Ok(latest_token);
+    }
+
+    let refresh_token = latest_token
+        .refresh_token
+        .clone()
+        .ok_or(Error::TokenNotFound)?;
+    let response = request_token(
+        &Env::new(),
+        &[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &refresh_token),
+        ],
+    )
+    .await?;
+    repository::oauth_token::update(
+        latest_token.id.clone(),
+        OAuthTokenUpdate {
+            access_token: Some(response.access_token),
+            expires_in: Some(response.expires_in as i64),
+            // Microsoft はリフレッシュのたびに新しいリフレッシュトークンを返す
+            refresh_token: response.refresh_token.or(Some(refresh_token)),
+            scope: Some(response.scope),
+            token_type: Some(response.token_type),
+            updated_at: chrono::Utc::now(),
+        },
+    )
+    .map_err(|e| Error::Parse(e.to_string()))?;
+
+    repository::oauth_token::find_latest_of(MICROSOFT_PROVIDER_NAME)
+        .map_err(|e| Error::Parse(e.to_string()))?
+        .ok_or(Error::TokenNotFound)
+}
+
+pub fn routes() -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::path(AUTH_REDIRECT_PATH)
+        .and(warp::query::<HashMap<String, String>>())
+        .and_then(handle_oauth_redirect)
+}
+
+async fn handle_oauth_redirect(
+    params: HashMap<String, String>,
+) -> Result<impl warp::Reply, warp::Rejection> {
+    let (Some(code), Some(state)) = (params.get("code"), params.get("state")) else {
+        return Ok(format!(
+            "Failed to get code: {}",
+            params
+                .get("error_description")
+                .map(String::as_str)
+                .unwrap_or_default()
+        ));
+    };
+    if let Err(e) = exchange_code(code, state).await {
+        println!("Failed to get Microsoft token: {:?}", e);
+        return Ok("Failed to get token".to_string());
+    }
+    println!("Success to get Microsoft token!");
+
+    let env = Env::new();
+    if let Some(provider) = MicrosoftProvider::from_env(&env) {
+        provider::sync_events(&provider, SyncWindow::from_env(&env))
+            .await
+            .unwrap_or_else(|e| {
+                println!("Failed to sync events in handle_oauth_redirect: {:?}", e);
+            });
+    }
+    Ok("Ok handle_oauth_redirect".to_string())
+}
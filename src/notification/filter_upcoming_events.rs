@@ -1,33 +1,68 @@
-use crate::repository::models::Event;
+use crate::{
+    env::Env,
+    location::TravelTimeRules,
+    repository::models::{Attendee, Event},
+};
 
 pub fn filter_upcoming_events(events: Vec<Event>) -> Vec<Event> {
     let now = chrono::Local::now();
+    let travel_time_rules = Env::new().travel_time_rules;
     let upcoming_events: Vec<Event> = events
         .into_iter()
-        .filter(|event| filter_by_start_time(event, now))
+        .filter(|event| filter_by_start_time(event, now, &travel_time_rules))
         .collect();
 
     upcoming_events
 }
 
+/**
+ * 自分が辞退したイベントを除く
+ */
+pub fn exclude_declined_events(events: Vec<Event>, self_attendees: &[Attendee]) -> Vec<Event> {
+    events
+        .into_iter()
+        .filter(|event| {
+            !self_attendees.iter().any(|attendee| {
+                attendee.event_id == event.id
+                    && attendee.response_status.as_deref() == Some("declined")
+            })
+        })
+        .collect()
+}
+
+/**
+ * 不在の期間中に始まる予定を除く
+ */
+pub fn exclude_out_of_office_events(events: Vec<Event>, out_of_office: &[Event]) -> Vec<Event> {
+    events
+        .into_iter()
+        .filter(|event| {
+            !out_of_office.iter().any(|absence| {
+                absence.id != event.id
+                    && absence.start_datetime <= event.start_datetime
+                    && event.start_datetime < absence.end_datetime
+            })
+        })
+        .collect()
+}
+
+/**
+ * 通知時刻を過ぎたか。対面の予定は場所までの移動時間だけ早める
+ */
 fn filter_by_start_time(
     Event {
         start_datetime,
         notification_enabled,
         notification_sec_from_start,
+        location,
         ..
     }: &Event,
     now: chrono::DateTime<chrono::Local>,
+    travel_time_rules: &TravelTimeRules,
 ) -> bool {
-    let start_time =
-        chrono::DateTime::parse_from_rfc3339(start_datetime.as_str()).unwrap_or_else(|e| {
-            panic!(
-                "Error occurred when parsing start time in filter_by_start_time: {:?}",
-                e
-            )
-        });
-    let notification_sec_from_start = *notification_sec_from_start as i64;
-    start_time.signed_duration_since(now).num_seconds() < notification_sec_from_start
+    let notification_sec_from_start = *notification_sec_from_start as i64
+        + travel_time_rules.travel_minutes(location.as_deref()) * 60;
+    start_datetime.signed_duration_since(now).num_seconds() < notification_sec_from_start
         && *notification_enabled
 }
 
@@ -39,38 +74,151 @@ mod tests {
 
     // テスト用の簡略化されたEvent構造体
 
+    /// 予定と同じ +09:00 の 2023-08-01 12:00。システムのタイムゾーンによらず同じ時刻にする
+    fn now_naive_utc() -> chrono::NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2023, 8, 1)
+            .unwrap()
+            .and_hms_opt(3, 0, 0)
+            .unwrap()
+    }
+
+    fn start(value: &str) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .to_utc()
+    }
+
     #[test]
+    #[allow(clippy::bool_assert_comparison, clippy::useless_conversion)]
     fn test_filter_by_start_time() {
-        let now = chrono::Local
-            .with_ymd_and_hms(2023, 8, 1, 12, 0, 0)
-            .unwrap();
+        let now = chrono::Local.from_utc_datetime(&now_naive_utc());
         let duration = 60 * 10;
 
         let event1 = Event {
-            start_datetime: "2023-08-01T12:10:00+09:00".to_string(), // + NOTIFICATION_INTERVAL_SEC
+            start_datetime: start("2023-08-01T12:10:00+09:00"), // + NOTIFICATION_INTERVAL_SEC
             notification_sec_from_start: duration.into(),
             notification_enabled: true,
             ..Default::default()
         };
-        let result1 = filter_by_start_time(&event1, now);
+        let result1 = filter_by_start_time(&event1, now, &TravelTimeRules::default());
         assert_eq!(result1, false);
 
         let event2 = Event {
-            start_datetime: "2023-08-01T12:09:59+09:00".to_string(), // + duration - 1
+            start_datetime: start("2023-08-01T12:09:59+09:00"), // + duration - 1
             notification_sec_from_start: (duration).into(),
             notification_enabled: true,
             ..Default::default()
         };
-        let result2 = filter_by_start_time(&event2.into(), now);
+        let result2 = filter_by_start_time(&event2.into(), now, &TravelTimeRules::default());
         assert_eq!(result2, true);
 
         let event3 = Event {
-            start_datetime: "2023-08-01T12:10:00+09:00".to_string(), // + NOTIFICATION_INTERVAL_SEC
+            start_datetime: start("2023-08-01T12:10:00+09:00"), // + NOTIFICATION_INTERVAL_SEC
             notification_sec_from_start: duration.into(),
             notification_enabled: false,
             ..Default::default()
         };
-        let result3 = filter_by_start_time(&event3, now);
+        let result3 = filter_by_start_time(&event3, now, &TravelTimeRules::default());
         assert_eq!(result3, false);
     }
+
+    #[test]
+    fn test_filter_by_start_time_with_travel_time() {
+        let now = chrono::Local.from_utc_datetime(&now_naive_utc());
+        let rules: TravelTimeRules = "Office B=15".parse().unwrap();
+        let event = |location: &str| Event {
+            start_datetime: start("2023-08-01T12:20:00+09:00"),
+            notification_sec_from_start: 60 * 10,
+            notification_enabled: true,
+            location: Some(location.to_string()),
+            ..Default::default()
+        };
+
+        // 10 分前 + 移動 15 分 で、25 分前から通知する
+        assert!(filter_by_start_time(&event("Office B 会議室"), now, &rules));
+        assert!(!filter_by_start_time(
+            &event("Office A 会議室"),
+            now,
+            &rules
+        ));
+        assert!(!filter_by_start_time(
+            &event("https://meet.google.com/abc-defg-hij"),
+            now,
+            &rules
+        ));
+    }
+
+    #[test]
+    fn test_exclude_declined_events() {
+        let events = vec!["accepted", "declined", "no_attendees"]
+            .into_iter()
+            .map(|id| Event {
+                id: id.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let self_attendees = vec![
+            Attendee {
+                event_id: "accepted".to_string(),
+                is_self: true,
+                response_status: Some("accepted".to_string()),
+                ..Default::default()
+            },
+            Attendee {
+                event_id: "declined".to_string(),
+                is_self: true,
+                response_status: Some("declined".to_string()),
+                ..Default::default()
+            },
+        ];
+
+        let result = exclude_declined_events(events, &self_attendees);
+
+        assert_eq!(
+            result.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            vec!["accepted", "no_attendees"]
+        );
+    }
+
+    #[test]
+    fn test_exclude_out_of_office_events() {
+        let event = |id: &str, start_at: &str, end_at: &str| Event {
+            id: id.to_string(),
+            start_datetime: start(start_at),
+            end_datetime: start(end_at),
+            ..Default::default()
+        };
+        let absence = Event {
+            event_type: Some("outOfOffice".to_string()),
+            ..event(
+                "absence",
+                "2023-08-01T13:00:00+09:00",
+                "2023-08-01T15:00:00+09:00",
+            )
+        };
+        let events = vec![
+            event(
+                "before",
+                "2023-08-01T12:00:00+09:00",
+                "2023-08-01T13:00:00+09:00",
+            ),
+            event(
+                "during",
+                "2023-08-01T13:00:00+09:00",
+                "2023-08-01T13:30:00+09:00",
+            ),
+            event(
+                "after",
+                "2023-08-01T15:00:00+09:00",
+                "2023-08-01T15:30:00+09:00",
+            ),
+        ];
+
+        let result = exclude_out_of_office_events(events, &[absence]);
+
+        assert_eq!(
+            result.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            vec!["before", "after"]
+        );
+    }
 }
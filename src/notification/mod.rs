@@ -1,42 +1,108 @@
 use std::{io, process::Command};
 
-use filter_upcoming_events::filter_upcoming_events;
+pub use filter_upcoming_events::{
+    exclude_declined_events, exclude_out_of_office_events, filter_upcoming_events,
+};
+pub use settings::{apply_notification_settings, NotificationSettings};
 
-use crate::repository::{
-    self,
-    models::{Event, EventFindMany, EventUpdate},
+use crate::{
+    env::Env,
+    google_calendar::event_type::EventType,
+    launcher::{LaunchCommand, Launcher},
+    location,
+    meeting_link::{dial_in::dial_in_uri, MeetingLink, MeetingLinkRegistry},
+    repository::{
+        self,
+        models::{
+            Attendee, ConferenceEntryPoint, Event, EventDialIn, EventFindMany, EventMeetingLink,
+            EventResource, EventUpdate,
+        },
+    },
+    timezone::DisplayTimezone,
 };
 mod filter_upcoming_events;
+mod settings;
 
 const NOTIFICATION_INTERVAL_SEC: u16 = 60;
-pub const NOTIFICATION_PERIOD_DAYS: i64 = 7;
+/// ダイアログのボタンは 3 つまでなので、収まらない操作はこのボタンから一覧で選ぶ
+const MORE_ACTIONS: &str = "その他";
 
 pub fn spawn_notification_cron() {
     tokio::spawn(async {
         loop {
-            let now = chrono::Local::now();
+            let now = chrono::Utc::now();
+            let notification_period_days = Env::new().notification_period_days;
+            let until = now + chrono::Duration::days(notification_period_days);
             let events = repository::event::find_many(EventFindMany {
-                from: Some(now.to_rfc3339()),
-                to: Some((now + chrono::Duration::days(NOTIFICATION_PERIOD_DAYS)).to_rfc3339()),
+                from: Some(now),
+                to: Some(until),
                 ..Default::default()
             });
 
             match events {
                 Ok(events) => {
                     let upcoming_events = filter_upcoming_events(events);
-                    for event in upcoming_events {
-                        notify(event.clone()).unwrap_or_else(|e| {
-                            println!("Failed to notify event {}: {}", event.id, e)
+                    let self_attendees = repository::attendee::find_self_many(
+                        upcoming_events.iter().map(|e| e.id.clone()).collect(),
+                    )
+                    .unwrap_or_else(|e| {
+                        println!("Failed to get attendees: {:?}", e);
+                        vec![]
+                    });
+                    let upcoming_events = exclude_declined_events(upcoming_events, &self_attendees);
+                    let out_of_office = repository::event::find_out_of_office(now, until)
+                        .unwrap_or_else(|e| {
+                            println!("Failed to get out of office events: {:?}", e);
+                            vec![]
                         });
+                    let upcoming_events =
+                        exclude_out_of_office_events(upcoming_events, &out_of_office);
+                    for event in upcoming_events {
+                        // 集中時間は会議ではないため、開始のお知らせだけ出す
+                        if EventType::of(&event) == EventType::FocusTime {
+                            notify_focus_time(&event).unwrap_or_else(|e| {
+                                println!("Failed to notify focus time {}: {}", event.id, e)
+                            });
+                            mute(&event);
+                            continue;
+                        }
 
-                        repository::event::update(
-                            event.id.clone(),
-                            EventUpdate {
-                                notification_enabled: Some(false),
-                                ..Default::default()
-                            },
+                        let attendees = repository::attendee::find_by_event_id(&event.id)
+                            .unwrap_or_else(|e| {
+                                println!("Failed to get attendees: {:?}", e);
+                                vec![]
+                            });
+                        let entry_points =
+                            repository::conference_entry_point::find_by_event_id(&event.id)
+                                .unwrap_or_else(|e| {
+                                    println!("Failed to get entry points: {:?}", e);
+                                    vec![]
+                                });
+                        let meeting_links = repository::meeting_link::find_by_event_id(&event.id)
+                            .unwrap_or_else(|e| {
+                                println!("Failed to get meeting links: {:?}", e);
+                                vec![]
+                            });
+                        let dial_ins = repository::dial_in::find_by_event_id(&event.id)
+                            .unwrap_or_else(|e| {
+                                println!("Failed to get dial-ins: {:?}", e);
+                                vec![]
+                            });
+                        let resources = repository::event_resource::find_by_event_id(&event.id)
+                            .unwrap_or_else(|e| {
+                                println!("Failed to get resources: {:?}", e);
+                                vec![]
+                            });
+                        notify(
+                            event.clone(),
+                            &attendees,
+                            &entry_points,
+                            &meeting_links,
+                            &dial_ins,
+                            &resources,
                         )
-                        .unwrap_or_else(|e| println!("Failed to update notification: {}", e));
+                        .unwrap_or_else(|e| println!("Failed to notify event {}: {}", event.id, e));
+                        mute(&event);
                     }
                 }
                 Err(e) => println!("Failed to get events: {:?}", e),
@@ -50,26 +116,92 @@ pub fn spawn_notification_cron() {
     });
 }
 
-fn notify(event: Event) -> Result<(), io::Error> {
+/**
+ * 一度通知した予定は再度通知しない
+ */
+fn mute(event: &Event) {
+    repository::event::update(
+        event.id.clone(),
+        EventUpdate {
+            notification_enabled: Some(false),
+            ..Default::default()
+        },
+    )
+    .unwrap_or_else(|e| println!("Failed to update notification: {}", e));
+}
+
+/**
+ * 集中時間の開始を通知センターに表示する
+ */
+fn notify_focus_time(event: &Event) -> Result<(), io::Error> {
+    let end = DisplayTimezone::from_env(&Env::new()).convert(event.end_datetime);
+    let script = format!(
+        r#"display notification {} with title "集中時間が始まります""#,
+        applescript_string(&format!(
+            "{} 〜{}",
+            event.summary.clone().unwrap_or("集中時間".to_string()),
+            end.format("%H:%M")
+        )),
+    );
+    Command::new("osascript").arg("-e").arg(script).output()?;
+    Ok(())
+}
+
+fn notify(
+    event: Event,
+    attendees: &[Attendee],
+    entry_points: &[ConferenceEntryPoint],
+    meeting_links: &[EventMeetingLink],
+    dial_ins: &[EventDialIn],
+    resources: &[EventResource],
+) -> Result<(), io::Error> {
     // ビープ音を鳴らす
     Command::new("osascript").arg("-e").arg("beep").output()?;
 
     // イベントの内容をダイアログで表示
     let join = "会議に参加";
-    let cancel = "キャンセル";
+    let open_agenda = "資料を開く";
+    let dial_in = "電話で参加";
+    let agenda = resources.first();
+    let dial_in_uri = dial_in_uri(entry_points, dial_ins);
+    let other_actions: Vec<&str> = [
+        agenda.map(|_| open_agenda),
+        dial_in_uri.as_ref().map(|_| dial_in),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    let buttons = dialog_buttons(join, &other_actions)
+        .iter()
+        .map(|button| applescript_string(button))
+        .collect::<Vec<_>>()
+        .join(",");
     let dialog_script = format!(
         r#"
                 tell application "System Events"
-                    set theResponse to display dialog "{}" with title "{}" buttons {{"{}","{}"}} default button "{}"
+                    set theResponse to display dialog {} with title {} buttons {{{}}} default button {}
                     set theButton to button returned of theResponse
                     return theButton
                 end tell
                 "#,
-        event.description.unwrap_or("".to_string()),
-        event.summary.unwrap_or("[タイトル未設定]".to_string()),
-        cancel,
-        join,
-        join
+        applescript_string(
+            &[
+                location_summary(event.location.as_deref()).unwrap_or("".to_string()),
+                event.description.clone().unwrap_or("".to_string()),
+                attendance_summary(attendees).unwrap_or("".to_string()),
+                dial_in_summary(entry_points, dial_ins).unwrap_or("".to_string()),
+                resource_summary(resources).unwrap_or("".to_string()),
+            ]
+            .join("\n\n")
+        ),
+        applescript_string(
+            &event
+                .summary
+                .clone()
+                .unwrap_or("[タイトル未設定]".to_string())
+        ),
+        buttons,
+        applescript_string(join)
     );
     let button_result = Command::new("osascript")
         .arg("-e")
@@ -82,44 +214,386 @@ fn notify(event: Event) -> Result<(), io::Error> {
         return Ok(());
     }
 
-    // Teams を開く、なければ Zoom を開く、 なければ Meet を開く
-    match event {
-        Event {
-            teams_link: Some(link),
-            ..
-        } => open_with_browser(&link)?,
-        Event {
-            zoom_link: Some(link),
-            ..
-        } => {
-            Command::new("open")
-                .arg("-a")
-                .arg("zoom.us")
-                .arg(link)
-                .output()?;
+    let env = Env::new();
+    let launcher = Launcher::from_env(&env);
+
+    let mut action = String::from_utf8_lossy(&button_result.stdout)
+        .trim()
+        .to_string();
+    if action == MORE_ACTIONS {
+        let list = other_actions
+            .iter()
+            .map(|action| applescript_string(action))
+            .collect::<Vec<_>>()
+            .join(",");
+        let choose_script = format!(
+            r#"choose from list {{{}}} with title {}"#,
+            list,
+            applescript_string(MORE_ACTIONS)
+        );
+        let choose_result = Command::new("osascript")
+            .arg("-e")
+            .arg(choose_script)
+            .output()?;
+        action = String::from_utf8_lossy(&choose_result.stdout)
+            .trim()
+            .to_string();
+    }
+
+    // 資料を開く場合・電話で参加する場合は会議のリンクを開かない
+    if let Some(agenda) = agenda {
+        if action == open_agenda {
+            return launcher.browser_command(&agenda.url, None).run();
         }
-        Event {
-            hangout_link: Some(link),
-            ..
-        } => open_with_browser(&link)?,
-        _ => {
-            println!("No link for meeting found")
+    }
+    if let Some(uri) = dial_in_uri {
+        if action == dial_in {
+            return LaunchCommand::System(uri).run();
         }
     }
+    // その他の一覧でキャンセルされた場合は false が返る
+    if action != join {
+        return Ok(());
+    }
+
+    let registry = MeetingLinkRegistry::from_env(&env);
+    match join_link(&event, entry_points, meeting_links, &registry) {
+        Some(link) => launcher.open(&link)?,
+        None => println!("No link for meeting found"),
+    }
 
     Ok(())
 }
 
-fn open_with_browser(link: &str) -> Result<(), io::Error> {
-    let script = format!(
-        r#"
-        tell application "Brave Browser"
-            activate
-            open location "{}"
-        end tell
-        "#,
-        link
+/**
+ * AppleScript の文字列リテラルにする。予定のタイトルや説明文に含まれる \ と " をエスケープする
+ */
+fn applescript_string(text: &str) -> String {
+    format!(r#""{}""#, text.replace('\\', r"\\").replace('"', r#"\""#))
+}
+
+/**
+ * ダイアログのボタン。キャンセル、会議への参加以外の操作、会議への参加の順に並べる
+ */
+fn dialog_buttons<'a>(join: &'a str, other_actions: &[&'a str]) -> Vec<&'a str> {
+    let cancel = "キャンセル";
+    match other_actions {
+        [] => vec![cancel, join],
+        [action] => vec![cancel, action, join],
+        _ => vec![cancel, MORE_ACTIONS, join],
+    }
+}
+
+/**
+ * 参加者の出欠をまとめる。自分が未回答の場合はその旨も表示する
+ */
+fn attendance_summary(attendees: &[Attendee]) -> Option<String> {
+    if attendees.is_empty() {
+        return None;
+    }
+
+    let count = |status: &str| {
+        attendees
+            .iter()
+            .filter(|a| a.response_status.as_deref() == Some(status))
+            .count()
+    };
+    let mut summary = format!(
+        "出欠: 参加 {} / 未定 {} / 辞退 {} / 未回答 {}",
+        count("accepted"),
+        count("tentative"),
+        count("declined"),
+        count("needsAction"),
     );
-    Command::new("osascript").arg("-e").arg(script).output()?;
-    Ok(())
+    if attendees
+        .iter()
+        .any(|a| a.is_self && a.response_status.as_deref() == Some("needsAction"))
+    {
+        summary.push_str("\nまだ出欠を回答していません");
+    }
+    Some(summary)
+}
+
+/**
+ * 添付ファイルと説明文のリンクの一覧
+ */
+fn resource_summary(resources: &[EventResource]) -> Option<String> {
+    if resources.is_empty() {
+        return None;
+    }
+    let lines: Vec<String> = resources
+        .iter()
+        .map(|resource| match &resource.title {
+            Some(title) => format!("{}: {}", title, resource.url),
+            None => resource.url.clone(),
+        })
+        .collect();
+    Some(format!("資料:\n{}", lines.join("\n")))
+}
+
+/**
+ * 場所と、それを開くためのリンク
+ */
+fn location_summary(place: Option<&str>) -> Option<String> {
+    let place = place?.trim();
+    if place.is_empty() {
+        return None;
+    }
+    let link = location::location_link(place);
+    if link == place {
+        return Some(format!("場所: {}", place));
+    }
+    Some(format!("場所: {}\n{}", place, link))
+}
+
+/**
+ * 説明文から抽出したリンクより優先する、会議情報のビデオ会議 URL
+ */
+fn video_uri(entry_points: &[ConferenceEntryPoint]) -> Option<&str> {
+    entry_points
+        .iter()
+        .find(|entry_point| entry_point.entry_point_type == "video")
+        .map(|entry_point| entry_point.uri.as_str())
+}
+
+/**
+ * 参加に使う会議のリンク。会議情報のビデオ会議 URL、Teams、Zoom、Meet、その他の会議のリンクの順に優先する。
+ * 保存時に抽出したリンクがあれば、そのパスコードも使う
+ */
+fn join_link(
+    event: &Event,
+    entry_points: &[ConferenceEntryPoint],
+    meeting_links: &[EventMeetingLink],
+    registry: &MeetingLinkRegistry,
+) -> Option<MeetingLink> {
+    let url = video_uri(entry_points)
+        .or(event.teams_link.as_deref())
+        .or(event.zoom_link.as_deref())
+        .or(event.hangout_link.as_deref())
+        .or(meeting_links.first().map(|link| link.url.as_str()))?;
+    let link = meeting_links
+        .iter()
+        .find(|link| link.url == url)
+        .map(MeetingLink::from)
+        .or_else(|| registry.extract_first(url))
+        .unwrap_or(MeetingLink {
+            url: url.to_string(),
+            ..Default::default()
+        });
+    Some(link)
+}
+
+/**
+ * 電話で参加するための番号と PIN の一覧。会議情報の番号の後に、説明文から抽出した番号を並べる
+ */
+fn dial_in_summary(
+    entry_points: &[ConferenceEntryPoint],
+    dial_ins: &[EventDialIn],
+) -> Option<String> {
+    let numbers: Vec<String> = entry_points
+        .iter()
+        .filter_map(ConferenceEntryPoint::dial_in_text)
+        .chain(dial_ins.iter().map(EventDialIn::text))
+        .collect();
+    if numbers.is_empty() {
+        return None;
+    }
+    Some(format!("電話で参加:\n{}", numbers.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attendee(status: &str, is_self: bool) -> Attendee {
+        Attendee {
+            response_status: Some(status.to_string()),
+            is_self,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_attendance_summary() {
+        assert_eq!(attendance_summary(&[]), None);
+
+        let attendees = vec![
+            attendee("accepted", false),
+            attendee("accepted", false),
+            attendee("declined", false),
+            attendee("needsAction", true),
+        ];
+        assert_eq!(
+            attendance_summary(&attendees),
+            Some(
+                "出欠: 参加 2 / 未定 0 / 辞退 1 / 未回答 1\nまだ出欠を回答していません".to_string()
+            )
+        );
+    }
+
+    fn entry_point(entry_point_type: &str, uri: &str) -> ConferenceEntryPoint {
+        ConferenceEntryPoint {
+            entry_point_type: entry_point_type.to_string(),
+            uri: uri.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_video_uri_and_dial_in_summary() {
+        assert_eq!(video_uri(&[]), None);
+        assert_eq!(dial_in_summary(&[], &[]), None);
+
+        let entry_points = vec![
+            entry_point("more", "https://tel.meet/abc-defg-hij"),
+            entry_point("video", "https://meet.google.com/abc-defg-hij"),
+            ConferenceEntryPoint {
+                pin: Some("123456789".to_string()),
+                ..entry_point("phone", "tel:+1-555-0100")
+            },
+        ];
+        assert_eq!(
+            video_uri(&entry_points),
+            Some("https://meet.google.com/abc-defg-hij")
+        );
+        assert_eq!(
+            dial_in_summary(&entry_points, &[]),
+            Some("電話で参加:\n+1-555-0100 PIN: 123456789".to_string())
+        );
+    }
+
+    #[test]
+    fn test_dial_ins() {
+        let dial_ins = vec![EventDialIn {
+            number: "+81 3 4578 1488".to_string(),
+            country: Some("Japan".to_string()),
+            meeting_id: "95428352872".to_string(),
+            passcode: Some("314159".to_string()),
+            ..Default::default()
+        }];
+        assert_eq!(
+            dial_in_summary(&[], &dial_ins),
+            Some(
+                "電話で参加:\n+81 3 4578 1488 (Japan) 会議 ID: 95428352872# パスコード: 314159#"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            dial_in_uri(&[], &dial_ins).as_deref(),
+            Some("tel:+81345781488,,95428352872#,,,,*314159#")
+        );
+
+        // 会議情報の番号を優先する
+        let entry_points = vec![
+            entry_point("video", "https://meet.google.com/abc-defg-hij"),
+            ConferenceEntryPoint {
+                pin: Some("123456789".to_string()),
+                ..entry_point("phone", "tel:+1-555-0100")
+            },
+        ];
+        assert_eq!(
+            dial_in_uri(&entry_points, &dial_ins).as_deref(),
+            Some("tel:+15550100,,123456789#")
+        );
+        assert_eq!(dial_in_uri(&[], &[]), None);
+    }
+
+    #[test]
+    fn test_applescript_string() {
+        assert_eq!(applescript_string("会議に参加"), r#""会議に参加""#);
+        assert_eq!(
+            applescript_string(r#"Say "hi" at C:\share"#),
+            r#""Say \"hi\" at C:\\share""#
+        );
+    }
+
+    #[test]
+    fn test_dialog_buttons() {
+        assert_eq!(
+            dialog_buttons("会議に参加", &[]),
+            vec!["キャンセル", "会議に参加"]
+        );
+        assert_eq!(
+            dialog_buttons("会議に参加", &["電話で参加"]),
+            vec!["キャンセル", "電話で参加", "会議に参加"]
+        );
+        assert_eq!(
+            dialog_buttons("会議に参加", &["資料を開く", "電話で参加"]),
+            vec!["キャンセル", "その他", "会議に参加"]
+        );
+    }
+
+    #[test]
+    fn test_join_link() {
+        let registry = MeetingLinkRegistry::default();
+        let event = Event {
+            zoom_link: Some("https://zoom.us/j/95428352872".to_string()),
+            hangout_link: Some("https://meet.google.com/abc-defg-hij".to_string()),
+            ..Default::default()
+        };
+        let meeting_links = vec![EventMeetingLink {
+            provider: "zoom".to_string(),
+            url: "https://zoom.us/j/95428352872".to_string(),
+            meeting_id: Some("95428352872".to_string()),
+            passcode: Some("314159".to_string()),
+            ..Default::default()
+        }];
+        let link = join_link(&event, &[], &meeting_links, &registry).unwrap();
+        assert_eq!(link.provider, "zoom");
+        assert_eq!(link.passcode.as_deref(), Some("314159"));
+
+        // 会議情報のビデオ会議 URL を優先し、保存したリンクになければ抽出し直す
+        let entry_points = vec![entry_point("video", "https://us02web.zoom.us/j/1234567890")];
+        let link = join_link(&event, &entry_points, &meeting_links, &registry).unwrap();
+        assert_eq!(link.url, "https://zoom.us/j/1234567890");
+        assert_eq!(link.passcode, None);
+
+        let unknown = vec![entry_point("video", "https://vc.example.com/room")];
+        let link = join_link(&Event::default(), &unknown, &[], &registry).unwrap();
+        assert_eq!(link.provider, "");
+        assert_eq!(link.url, "https://vc.example.com/room");
+
+        assert_eq!(join_link(&Event::default(), &[], &[], &registry), None);
+    }
+
+    #[test]
+    fn test_location_summary() {
+        assert_eq!(location_summary(None), None);
+        assert_eq!(location_summary(Some(" ")), None);
+        assert_eq!(
+            location_summary(Some("https://zoom.us/j/123")),
+            Some("場所: https://zoom.us/j/123".to_string())
+        );
+        assert_eq!(
+            location_summary(Some("Office B")),
+            Some(
+                "場所: Office B\nhttps://www.google.com/maps/search/?api=1&query=Office+B"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_resource_summary() {
+        assert_eq!(resource_summary(&[]), None);
+
+        let resources = vec![
+            EventResource {
+                url: "https://docs.google.com/document/d/abc".to_string(),
+                title: Some("アジェンダ".to_string()),
+                ..Default::default()
+            },
+            EventResource {
+                url: "https://example.com/slides".to_string(),
+                ..Default::default()
+            },
+        ];
+        assert_eq!(
+            resource_summary(&resources),
+            Some(
+                "資料:\nアジェンダ: https://docs.google.com/document/d/abc\nhttps://example.com/slides"
+                    .to_string()
+            )
+        );
+    }
 }
@@ -0,0 +1,54 @@
+use crate::repository::{
+    self,
+    models::{Event, EventUpdate, SeriesNotificationSetting},
+};
+
+/// 通知設定の変更。None の項目は変更しない
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NotificationSettings {
+    pub enabled: Option<bool>,
+    pub sec_from_start: Option<i32>,
+}
+
+/**
+ * イベントの通知設定を変更する。
+ * 繰り返し予定のインスタンスで series が true の場合はシリーズ全体に適用し、
+ * 以降の同期で追加されるインスタンスにも引き継ぐ
+ */
+pub fn apply_notification_settings(
+    event: &Event,
+    settings: NotificationSettings,
+    series: bool,
+) -> Result<(), String> {
+    match (&event.recurring_event_id, series) {
+        (Some(recurring_event_id), true) => {
+            let current = repository::series_notification_setting::find(recurring_event_id)
+                .map_err(|e| format!("Failed to get series notification setting: {:?}", e))?;
+            // 通知済みのインスタンスは notification_enabled が false のため、未設定のシリーズは通知する扱いにする
+            let (enabled, sec_from_start) = match current {
+                Some(current) => (
+                    current.notification_enabled,
+                    current.notification_sec_from_start,
+                ),
+                None => (true, event.notification_sec_from_start),
+            };
+            repository::series_notification_setting::apply(SeriesNotificationSetting {
+                recurring_event_id: recurring_event_id.clone(),
+                notification_enabled: settings.enabled.unwrap_or(enabled),
+                notification_sec_from_start: settings.sec_from_start.unwrap_or(sec_from_start),
+                updated_at: chrono::Utc::now(),
+            })
+            .map(|_| ())
+            .map_err(|e| format!("Failed to apply series notification setting: {:?}", e))
+        }
+        _ => repository::event::update(
+            event.id.clone(),
+            EventUpdate {
+                notification_enabled: settings.enabled,
+                notification_sec_from_start: settings.sec_from_start,
+                ..Default::default()
+            },
+        )
+        .map_err(|e| format!("Failed to update notification setting: {:?}", e)),
+    }
+}
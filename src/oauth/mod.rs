@@ -9,18 +9,23 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     env::Env,
-    google_calendar::sync_events,
+    export,
+    google_calendar::{
+        rsvp::{has_events_scope, EVENTS_SCOPE},
+        sync_events, watch, SyncWindow, GOOGLE_PROVIDER_NAME,
+    },
+    microsoft_graph,
     oauth::oauth_secret::OAuthSecret,
     repository::{
         self,
         models::{OAuthToken, OAuthTokenUpdate},
     },
+    sync::SyncHandle,
+    timezone::DisplayTimezone,
 };
 
 const AUTH_REDIRECT_PATH: &str = "auth";
 
-use warp::reject::Reject;
-
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OAuthResponse {
     pub access_token: String,
@@ -36,41 +41,66 @@ impl OAuthResponse {
     }
 }
 
-#[derive(Debug)]
-struct ReqwestError;
-
-impl Reject for ReqwestError {}
-
-impl From<reqwest::Error> for ReqwestError {
-    fn from(_: reqwest::Error) -> Self {
-        ReqwestError
-    }
+pub fn to_oauth_on_browser() {
+    open_on_browser(&oauth_url(&Env::new()));
 }
 
-pub fn to_oauth_on_browser() {
+/**
+ * 認可画面をブラウザで開く
+ */
+pub fn open_on_browser(url: &str) {
     println!("Open browser to get OAuth token...");
 
     thread::sleep(Duration::from_secs(2));
 
-    let Env { port, base_url, .. } = Env::new();
+    open::that(url).expect("Failed to open URL in browser");
+}
 
-    let oauth_url = format!("https://accounts.google.com/o/oauth2/auth?client_id=121773230254-om9bag3ku8958qmeiv2qa42ddjjfot3d.apps.googleusercontent.com&redirect_uri={}:{}/{AUTH_REDIRECT_PATH}&response_type=code&scope=https://www.googleapis.com/auth/calendar.readonly&access_type=offline&state=random_state_string",base_url, port);
+/**
+ * 保存済みのトークンに予定の変更に必要なスコープがなければ、認可し直す
+ */
+pub fn reauthorize_if_scope_missing() {
+    if let Ok(Some(oauth_token)) = repository::oauth_token::find_latest() {
+        if !has_events_scope(&oauth_token) {
+            to_oauth_on_browser();
+        }
+    }
+}
 
-    open::that(oauth_url).expect("Failed to open URL in browser");
+pub fn oauth_url(env: &Env) -> String {
+    let Env {
+        port,
+        base_url,
+        google_oauth_base_url,
+        ..
+    } = env;
+
+    format!("{google_oauth_base_url}/o/oauth2/auth?client_id=121773230254-om9bag3ku8958qmeiv2qa42ddjjfot3d.apps.googleusercontent.com&redirect_uri={}:{}/{AUTH_REDIRECT_PATH}&response_type=code&scope={EVENTS_SCOPE}&access_type=offline&prompt=consent&state=random_state_string",base_url, port)
 }
 
-pub fn spawn_redirect_server() {
+pub fn spawn_redirect_server(sync_handle: SyncHandle) {
     tokio::spawn(async {
-        let port = Env::new().port;
-        let routes = warp::path(AUTH_REDIRECT_PATH)
-            .and(warp::query::<std::collections::HashMap<String, String>>())
-            .and_then(handle_oauth_redirect);
+        let env = Env::new();
+        let port = env.port;
 
         // println!("HTTP server starting at {}", port.clone());
-        warp::serve(routes).run(([127, 0, 0, 1], port)).await;
+        warp::serve(
+            routes()
+                .or(microsoft_graph::oauth::routes())
+                .or(watch::routes(sync_handle))
+                .or(export::routes(DisplayTimezone::from_env(&env))),
+        )
+        .run(([127, 0, 0, 1], port))
+        .await;
     });
 }
 
+pub fn routes() -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::path(AUTH_REDIRECT_PATH)
+        .and(warp::query::<std::collections::HashMap<String, String>>())
+        .and_then(handle_oauth_redirect)
+}
+
 async fn handle_oauth_redirect(
     params: std::collections::HashMap<String, String>,
 ) -> Result<impl warp::Reply, warp::Rejection> {
@@ -82,19 +112,22 @@ async fn handle_oauth_redirect(
                 let oauth_token = OAuthToken {
                     id: uuid::Uuid::new_v4().to_string(),
                     access_token: response.access_token.clone(),
-                    expires_in: Some(response.expires_in.to_string()),
+                    expires_in: Some(response.expires_in as i64),
                     refresh_token: response.refresh_token.clone(),
                     scope: Some(response.scope.clone()),
                     token_type: Some(response.token_type.clone()),
-                    created_at: chrono::Local::now().to_rfc3339(),
-                    updated_at: chrono::Local::now().to_rfc3339(),
+                    created_at: chrono::Utc::now(),
+                    updated_at: chrono::Utc::now(),
+                    provider: GOOGLE_PROVIDER_NAME.to_string(),
                 };
                 let _ = repository::oauth_token::create(oauth_token.clone());
                 println!("Success to get token!");
 
-                sync_events(oauth_token).await.unwrap_or_else(|e| {
-                    println!("Failed to sync events in handle_oauth_redirect: {:?}", e);
-                });
+                sync_events(oauth_token, SyncWindow::from_env(&Env::new()))
+                    .await
+                    .unwrap_or_else(|e| {
+                        println!("Failed to sync events in handle_oauth_redirect: {:?}", e);
+                    });
             }
             Err(e) => {
                 println!("Recv error: {:?}", e.to_string());
@@ -113,7 +146,7 @@ async fn request_access_token_by_redirect(code: String) -> Result<String, reqwes
         client_id,
         client_secret,
         token_uri,
-    } = OAuthSecret::get_from_file("oauth_secret.json")
+    } = OAuthSecret::get_from_file(&Env::new().oauth_secret_path)
         .unwrap_or_else(|e| panic!("Failed to get OAuthSecret from file: {}", e));
 
     let Env { port, base_url, .. } = Env::new();
@@ -147,7 +180,7 @@ async fn request_access_token_by_refresh_token(
         client_id,
         client_secret,
         token_uri,
-    } = OAuthSecret::get_from_file("oauth_secret.json")
+    } = OAuthSecret::get_from_file(&Env::new().oauth_secret_path)
         .unwrap_or_else(|e| panic!("Failed to get OAuthSecret from file: {}", e));
 
     let mut body = HashMap::new();
@@ -175,11 +208,11 @@ pub async fn refresh_and_save_token(id: String, refresh_token: String) {
         Ok(response) => {
             let token_update = OAuthTokenUpdate {
                 access_token: Some(response.access_token),
-                expires_in: Some(response.expires_in.to_string()),
+                expires_in: Some(response.expires_in as i64),
                 refresh_token: response.refresh_token,
                 scope: Some(response.scope),
                 token_type: Some(response.token_type),
-                updated_at: chrono::Local::now().to_rfc3339(),
+                updated_at: chrono::Utc::now(),
             };
             let _ = repository::oauth_token::update(id, token_update);
             // println!("Success to get token! by refresh token");
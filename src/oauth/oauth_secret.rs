@@ -37,7 +37,7 @@ mod tests {
   "#;
 
         let mut file = File::create(file_path).expect("Failed to create test file");
-        file.write(test_json.as_bytes())
+        file.write_all(test_json.as_bytes())
             .expect("Failed to write test data to file");
 
         file_path
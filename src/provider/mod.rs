@@ -0,0 +1,739 @@
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::{
+    caldav::{self, CalDavProvider},
+    env::Env,
+    google_calendar::{
+        self, create::NewEvent, event_type::EventType, extract_resource_links, extract_teams_link,
+        extract_zoom_link, rsvp::ResponseStatus, GoogleProvider,
+    },
+    ics::{self, IcsProvider},
+    meeting_link::{dial_in::extract_dial_ins, normalize::normalize_text, MeetingLinkRegistry},
+    microsoft_graph::{self, MicrosoftProvider, MICROSOFT_PROVIDER_NAME},
+    oauth,
+    repository::{
+        self,
+        models::{self, Event, EventFindMany, EventUpdate, SeriesNotificationSetting, SyncState},
+    },
+};
+
+#[derive(Debug)]
+pub enum Error {
+    /// 認可されていない。プロバイダーの認可画面をブラウザで開く必要がある
+    AuthorizationRequired(&'static str),
+    /// トークンに必要なスコープが含まれていない。再認可が必要
+    InsufficientScope,
+    /// 差分同期のトークンが失効している。全件同期し直す
+    SyncTokenExpired,
+    /// レート制限を受けた。次回の同期で取得し直す
+    RateLimited,
+    /// プロバイダーが対応していない操作
+    Unsupported(&'static str),
+    /// イベントの同期元のプロバイダーが設定されていない
+    UnknownProvider(String),
+    Repository(String),
+    Google(google_calendar::Error),
+    CalDav(caldav::Error),
+    Ics(ics::Error),
+    Microsoft(microsoft_graph::Error),
+}
+impl From<google_calendar::Error> for Error {
+    fn from(e: google_calendar::Error) -> Self {
+        match e {
+            google_calendar::Error::TokenNotFound => {
+                Error::AuthorizationRequired(google_calendar::GOOGLE_PROVIDER_NAME)
+            }
+            google_calendar::Error::InsufficientScope => Error::InsufficientScope,
+            google_calendar::Error::RateLimited { .. } => Error::RateLimited,
+            e => Error::Google(e),
+        }
+    }
+}
+
+impl From<caldav::Error> for Error {
+    fn from(e: caldav::Error) -> Self {
+        Error::CalDav(e)
+    }
+}
+
+impl From<ics::Error> for Error {
+    fn from(e: ics::Error) -> Self {
+        Error::Ics(e)
+    }
+}
+
+impl From<microsoft_graph::Error> for Error {
+    fn from(e: microsoft_graph::Error) -> Self {
+        match e {
+            // リフレッシュトークンが失効している場合も認可し直す
+            microsoft_graph::Error::TokenNotFound | microsoft_graph::Error::Unauthorized => {
+                Error::AuthorizationRequired(MICROSOFT_PROVIDER_NAME)
+            }
+            microsoft_graph::Error::SyncStateNotFound => Error::SyncTokenExpired,
+            e => Error::Microsoft(e),
+        }
+    }
+}
+
+/**
+ * 同期対象の期間。現在時刻から look_back 前 〜 look_ahead 後までのイベントを取得する
+ */
+#[derive(Debug, Clone, Copy)]
+pub struct SyncWindow {
+    pub look_back: chrono::Duration,
+    pub look_ahead: chrono::Duration,
+}
+impl SyncWindow {
+    pub fn from_env(env: &Env) -> Self {
+        Self {
+            look_back: chrono::Duration::seconds(env.sync_look_back_sec),
+            look_ahead: chrono::Duration::days(env.sync_look_ahead_days),
+        }
+    }
+
+    pub fn time_range(&self, now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+        (now - self.look_back, now + self.look_ahead)
+    }
+}
+
+/**
+ * プロバイダーに依存しないイベント。日時は UTC に変換済み
+ */
+#[derive(Debug, Clone, Default)]
+pub struct CalendarEvent {
+    pub id: String,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    /// confirmed / tentative / cancelled
+    pub status: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub all_day: bool,
+    /// 終日の予定の、カレンダー上の開始日と終了日(最終日の翌日)
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub recurring_event_id: Option<String>,
+    pub original_start_time: Option<DateTime<Utc>>,
+    pub location: Option<String>,
+    /// default / outOfOffice / focusTime / workingLocation など
+    pub event_type: Option<String>,
+    /// 予定に設定されたリマインダーが開始の何秒前か。新しく保存するときの通知設定に使う
+    pub reminder_sec_from_start: Option<i32>,
+    /// プロバイダーが発行した Google Meet のリンク
+    pub hangout_link: Option<String>,
+    pub attendees: Vec<models::Attendee>,
+    pub entry_points: Vec<models::ConferenceEntryPoint>,
+    /// 添付ファイル。説明文のリンクは保存時に加える
+    pub attachments: Vec<models::EventResource>,
+}
+impl CalendarEvent {
+    /**
+     * 新しく保存するイベント。通知設定はシリーズの設定、予定のリマインダー、予定の種類ごとの既定値の順に使う
+     */
+    pub fn to_event(
+        &self,
+        provider: &str,
+        series_setting: Option<&SeriesNotificationSetting>,
+    ) -> Event {
+        let (default_enabled, default_sec_from_start) =
+            EventType::from_api(self.event_type.as_deref()).default_notification(self.all_day);
+        Event {
+            id: self.id.clone(),
+            summary: self.summary.clone(),
+            description: self.description.clone(),
+            status: self.status.clone(),
+            hangout_link: self.hangout_link.clone(),
+            zoom_link: self.link_in_sources(extract_zoom_link),
+            teams_link: self.link_in_sources(extract_teams_link),
+            start_datetime: self.start,
+            end_datetime: self.end,
+            notification_enabled: series_setting
+                .map(|setting| setting.notification_enabled)
+                .unwrap_or(default_enabled || self.reminder_sec_from_start.is_some()),
+            notification_sec_from_start: series_setting
+                .map(|setting| setting.notification_sec_from_start)
+                .or(self.reminder_sec_from_start)
+                .unwrap_or(default_sec_from_start),
+            recurring_event_id: self.recurring_event_id.clone(),
+            original_start_time: self.original_start_time,
+            all_day: self.all_day,
+            location: self.location.clone(),
+            event_type: self.event_type.clone(),
+            provider: provider.to_string(),
+            start_date: self.start_date,
+            end_date: self.end_date,
+        }
+    }
+
+    /**
+     * 保存済みのイベントの更新内容。通知設定は変えない
+     */
+    pub fn to_event_update(&self) -> EventUpdate {
+        EventUpdate {
+            summary: self.summary.clone(),
+            description: self.description.clone(),
+            status: self.status.clone(),
+            hangout_link: self.hangout_link.clone(),
+            zoom_link: self.link_in_sources(extract_zoom_link),
+            teams_link: self.link_in_sources(extract_teams_link),
+            start_datetime: Some(self.start),
+            end_datetime: Some(self.end),
+            recurring_event_id: self.recurring_event_id.clone(),
+            original_start_time: self.original_start_time,
+            all_day: Some(self.all_day),
+            location: self.location.clone(),
+            event_type: self.event_type.clone(),
+            start_date: self.start_date,
+            end_date: self.end_date,
+            ..Default::default()
+        }
+    }
+
+    /**
+     * 会議のリンクを探す文字列。会議情報のビデオ会議 URL、場所、説明文の順に優先する
+     */
+    fn link_sources(&self) -> Vec<&str> {
+        self.entry_points
+            .iter()
+            .filter(|entry_point| entry_point.entry_point_type == "video")
+            .map(|entry_point| entry_point.uri.as_str())
+            .chain(self.location.as_deref())
+            .chain(self.description.as_deref())
+            .collect()
+    }
+
+    /// 優先する順に探して、最初に見つかったリンク
+    fn link_in_sources(&self, extract: fn(&str) -> Option<String>) -> Option<String> {
+        self.link_sources()
+            .into_iter()
+            .find_map(|source| extract(&normalize_text(source)))
+    }
+
+    /**
+     * 会議情報・場所・説明文にある会議のリンク
+     */
+    fn meeting_links(&self, registry: &MeetingLinkRegistry) -> Vec<models::EventMeetingLink> {
+        registry
+            .extract_from_sources(&self.link_sources())
+            .into_iter()
+            .enumerate()
+            .map(|(position, link)| models::EventMeetingLink {
+                event_id: self.id.clone(),
+                position: position as i32,
+                provider: link.provider,
+                url: link.url,
+                meeting_id: link.meeting_id,
+                passcode: link.passcode,
+            })
+            .collect()
+    }
+
+    /**
+     * 説明文にある、電話で参加するための番号
+     */
+    fn dial_ins(&self) -> Vec<models::EventDialIn> {
+        self.description
+            .as_deref()
+            .map(|description| extract_dial_ins(&normalize_text(description)))
+            .unwrap_or_default()
+            .into_iter()
+            .enumerate()
+            .map(|(position, dial_in)| models::EventDialIn {
+                event_id: self.id.clone(),
+                position: position as i32,
+                number: dial_in.number,
+                country: dial_in.country,
+                meeting_id: dial_in.meeting_id,
+                passcode: dial_in.passcode,
+            })
+            .collect()
+    }
+
+    /**
+     * 添付ファイルと、説明文にある会議以外のリンク。添付ファイルと同じリンクは除く
+     */
+    fn resources(&self, registry: &MeetingLinkRegistry) -> Vec<models::EventResource> {
+        let mut resources = self.attachments.clone();
+        let description_links = self
+            .description
+            .as_deref()
+            .map(|description| extract_resource_links(&normalize_text(description)))
+            .unwrap_or_default();
+        for url in description_links {
+            if registry.is_meeting_link(&url) {
+                continue;
+            }
+            if !resources.iter().any(|resource| resource.url == url) {
+                resources.push(models::EventResource {
+                    source: "description".to_string(),
+                    url,
+                    ..Default::default()
+                });
+            }
+        }
+        resources
+            .into_iter()
+            .enumerate()
+            .map(|(position, resource)| models::EventResource {
+                event_id: self.id.clone(),
+                position: position as i32,
+                ..resource
+            })
+            .collect()
+    }
+}
+
+/**
+ * 一度の取得結果
+ */
+#[derive(Debug, Default)]
+pub struct EventBatch {
+    pub events: Vec<CalendarEvent>,
+    /// 差分同期で削除・キャンセルされたイベントの ID
+    pub deleted_ids: Vec<String>,
+    /// 次回の差分同期に使うトークン。None の場合は次回も全件同期する
+    pub next_sync_token: Option<String>,
+}
+
+/**
+ * 予定を取得・変更するカレンダーのサービス
+ */
+#[async_trait]
+pub trait CalendarProvider: Send + Sync {
+    /// events.provider に保存する名前
+    fn name(&self) -> &'static str;
+
+    /// 差分同期のトークンを保存するカレンダーの ID
+    fn calendar_id(&self) -> String;
+
+    /// 同期期間内のイベントをすべて取得する
+    async fn list_events(&self, window: SyncWindow) -> Result<EventBatch, Error>;
+
+    /// sync_token の発行以降に変更されたイベントを取得する。失効している場合は Error::SyncTokenExpired。
+    /// window は変更された繰り返し予定を展開する期間
+    async fn list_changes(&self, sync_token: &str, window: SyncWindow)
+        -> Result<EventBatch, Error>;
+
+    /// OAuth の認可画面の URL。認可が不要なプロバイダーは None
+    fn authorization_url(&self) -> Option<String> {
+        None
+    }
+
+    /// 変更通知を登録・更新する。対応していなければ何もしない
+    async fn watch(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// 自分の出欠を変更し、変更後の参加者を返す
+    async fn respond(
+        &self,
+        _event_id: &str,
+        _status: ResponseStatus,
+    ) -> Result<Vec<models::Attendee>, Error> {
+        Err(Error::Unsupported("respond"))
+    }
+
+    /// 「明日 12:30 にランチ」のような文章からイベントを作成する
+    async fn quick_add(&self, _text: &str) -> Result<CalendarEvent, Error> {
+        Err(Error::Unsupported("quick_add"))
+    }
+
+    async fn create(&self, _new_event: &NewEvent) -> Result<CalendarEvent, Error> {
+        Err(Error::Unsupported("create"))
+    }
+}
+
+/**
+ * 設定されているプロバイダー。先頭を予定の作成に使う
+ */
+pub fn configured_providers(env: &Env) -> Vec<Box<dyn CalendarProvider>> {
+    let mut providers: Vec<Box<dyn CalendarProvider>> = vec![Box::new(GoogleProvider::default())];
+    if let Some(microsoft) = MicrosoftProvider::from_env(env) {
+        providers.push(Box::new(microsoft));
+    }
+    if let Some(caldav) = CalDavProvider::from_env(env) {
+        providers.push(Box::new(caldav));
+    }
+    for ics in IcsProvider::from_env(env) {
+        providers.push(Box::new(ics));
+    }
+    providers
+}
+
+fn provider_by_name(name: &str) -> Result<Box<dyn CalendarProvider>, Error> {
+    configured_providers(&Env::new())
+        .into_iter()
+        .find(|provider| provider.name() == name)
+        .ok_or(Error::UnknownProvider(name.to_string()))
+}
+
+/**
+ * プロバイダーの認可画面をブラウザで開く
+ */
+pub fn authorize_on_browser(name: &str) {
+    let url = provider_by_name(name)
+        .ok()
+        .and_then(|provider| provider.authorization_url());
+    match url {
+        Some(url) => oauth::open_on_browser(&url),
+        None => println!("{} does not need authorization", name),
+    }
+}
+
+/**
+ * 設定されているすべてのプロバイダーを全件同期する。失敗したプロバイダーがあっても残りは同期し、最初のエラーを返す
+ */
+pub async fn sync_all(window: SyncWindow) -> Result<(), Error> {
+    let mut result = Ok(());
+    for provider in configured_providers(&Env::new()) {
+        if let Err(e) = sync_events(provider.as_ref(), window).await {
+            println!("Failed to sync {}: {:?}", provider.name(), e);
+            result = result.and(Err(e));
+        }
+    }
+    result
+}
+
+/**
+ * 設定されているすべてのプロバイダーの、前回の同期以降の変更を同期する。
+ * レート制限を受けたプロバイダーは失敗にせず、トークンを進めないまま次回の同期に回す
+ */
+pub async fn sync_all_changes(window: SyncWindow) -> Result<(), Error> {
+    let mut result = Ok(());
+    for provider in configured_providers(&Env::new()) {
+        match sync_changed_events(provider.as_ref(), window).await {
+            Ok(()) => {}
+            Err(Error::RateLimited) => {
+                println!(
+                    "{} is rate limited. Sync again on the next tick",
+                    provider.name()
+                )
+            }
+            Err(e) => {
+                println!("Failed to sync changes of {}: {:?}", provider.name(), e);
+                result = result.and(Err(e));
+            }
+        }
+    }
+    result
+}
+
+/**
+ * 設定されているすべてのプロバイダーの変更通知を登録・更新する
+ */
+pub async fn watch_all() {
+    for provider in configured_providers(&Env::new()) {
+        provider
+            .watch()
+            .await
+            .unwrap_or_else(|e| println!("Failed to watch {}: {:?}", provider.name(), e));
+    }
+}
+
+/**
+ * 同期期間内のイベントをすべて同期する
+ */
+pub async fn sync_events(provider: &dyn CalendarProvider, window: SyncWindow) -> Result<(), Error> {
+    let batch = provider.list_events(window).await?;
+    save_batch(provider, batch)
+}
+
+/**
+ * 保存済みのトークンで差分だけを同期する。トークンがない、または失効している場合は全件同期する
+ */
+pub async fn sync_changed_events(
+    provider: &dyn CalendarProvider,
+    window: SyncWindow,
+) -> Result<(), Error> {
+    let sync_token = repository::sync_state::find(&provider.calendar_id())
+        .map_err(|e| Error::Repository(e.to_string()))?
+        .and_then(|sync_state| sync_state.sync_token);
+    let Some(sync_token) = sync_token else {
+        return sync_events(provider, window).await;
+    };
+
+    match provider.list_changes(&sync_token, window).await {
+        Ok(batch) => save_batch(provider, batch),
+        Err(Error::SyncTokenExpired) => {
+            save_sync_token(provider, None);
+            sync_events(provider, window).await
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn save_batch(provider: &dyn CalendarProvider, batch: EventBatch) -> Result<(), Error> {
+    // 削除・保存できなかった場合は、次回も同じ変更を取得できるようトークンを保存しない
+    if !batch.deleted_ids.is_empty() {
+        repository::event::delete_many(batch.deleted_ids).map_err(|e| {
+            Error::Repository(format!("Failed to delete cancelled events: {:?}", e))
+        })?;
+    }
+    save_events(provider.name(), &batch.events).map_err(Error::Repository)?;
+    save_sync_token(provider, batch.next_sync_token);
+    Ok(())
+}
+
+fn save_sync_token(provider: &dyn CalendarProvider, sync_token: Option<String>) {
+    repository::sync_state::upsert(SyncState {
+        calendar_id: provider.calendar_id(),
+        sync_token,
+        updated_at: Utc::now(),
+    })
+    .unwrap_or_else(|e| println!("Failed to save sync token: {:?}", e));
+}
+
+/**
+ * 取得・作成したイベントを保存する。既存のイベントは更新し、新しいイベントには既定の通知設定を使う
+ */
+pub fn save_events(provider: &str, items: &[CalendarEvent]) -> Result<(), String> {
+    let duplicated_events = repository::event::find_many(EventFindMany {
+        ids_in: Some(items.iter().map(|event| event.id.clone()).collect()),
+        ..Default::default()
+    })
+    .map_err(|e| format!("Failed to get stored events: {:?}", e))?;
+
+    // すでに存在するイベントは、events を更新する
+    for event in &duplicated_events {
+        if let Some(item) = items.iter().find(|item| item.id == event.id) {
+            repository::event::update(event.id.clone(), item.to_event_update())
+                .map_err(|e| format!("Failed to update event {}: {:?}", event.id, e))?;
+        }
+    }
+
+    // 新規イベントは、events を作成する
+    let new_events = items.iter().filter(|event| {
+        !duplicated_events
+            .iter()
+            .any(|duplicated_event| duplicated_event.id == event.id)
+    });
+
+    // 繰り返し予定の新しいインスタンスは、シリーズの通知設定を引き継ぐ
+    let series_settings = repository::series_notification_setting::find_many(
+        new_events
+            .clone()
+            .filter_map(|event| event.recurring_event_id.clone())
+            .collect(),
+    )
+    .unwrap_or_else(|e| {
+        println!("Failed to get series notification settings: {:?}", e);
+        vec![]
+    });
+
+    let event_creates: Vec<Event> = new_events
+        .map(|event| {
+            let series_setting = series_settings.iter().find(|setting| {
+                Some(&setting.recurring_event_id) == event.recurring_event_id.as_ref()
+            });
+            event.to_event(provider, series_setting)
+        })
+        .collect();
+    if let Err(e) = repository::event::create_many(event_creates) {
+        return Err(format!("Failed to create events: {:?}", e));
+    }
+
+    // 参加者・会議の参加方法・会議のリンク・電話番号・資料は新規・既存に関わらず置き換える
+    let registry = MeetingLinkRegistry::from_env(&Env::new());
+    for event in items {
+        repository::attendee::replace_for_event(&event.id, event.attendees.clone())
+            .unwrap_or_else(|e| println!("Failed to save attendees of {}: {:?}", event.id, e));
+        repository::conference_entry_point::replace_for_event(
+            &event.id,
+            event.entry_points.clone(),
+        )
+        .unwrap_or_else(|e| println!("Failed to save entry points of {}: {:?}", event.id, e));
+        repository::meeting_link::replace_for_event(&event.id, event.meeting_links(&registry))
+            .unwrap_or_else(|e| println!("Failed to save meeting links of {}: {:?}", event.id, e));
+        repository::dial_in::replace_for_event(&event.id, event.dial_ins())
+            .unwrap_or_else(|e| println!("Failed to save dial-ins of {}: {:?}", event.id, e));
+        repository::event_resource::replace_for_event(&event.id, event.resources(&registry))
+            .unwrap_or_else(|e| println!("Failed to save resources of {}: {:?}", event.id, e));
+    }
+
+    Ok(())
+}
+
+/**
+ * 出欠の回答後の参加者を保存する。辞退した場合は通知もしない
+ */
+pub fn save_response(
+    event_id: &str,
+    status: ResponseStatus,
+    attendees: Vec<models::Attendee>,
+) -> Result<(), String> {
+    repository::attendee::replace_for_event(event_id, attendees).map_err(|e| e.to_string())?;
+
+    if status == ResponseStatus::Declined {
+        repository::event::update(
+            event_id.to_string(),
+            EventUpdate {
+                notification_enabled: Some(false),
+                ..Default::default()
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    }
+
+    Ok(())
+}
+
+/**
+ * イベントの同期元のプロバイダーで出欠を回答し、保存済みの参加者を更新する
+ */
+pub async fn respond(event_id: &str, status: ResponseStatus) -> Result<(), Error> {
+    let event = repository::event::find_many(EventFindMany {
+        ids_in: Some(vec![event_id.to_string()]),
+        ..Default::default()
+    })
+    .map_err(|e| Error::Repository(e.to_string()))?
+    .into_iter()
+    .next()
+    .ok_or(Error::Repository(format!(
+        "Event {} is not found",
+        event_id
+    )))?;
+
+    let attendees = provider_by_name(&event.provider)?
+        .respond(event_id, status)
+        .await?;
+    save_response(event_id, status, attendees).map_err(Error::Repository)
+}
+
+/**
+ * 先頭のプロバイダーで文章からイベントを作成し、次の同期を待たずに保存する
+ */
+pub async fn quick_add(text: &str) -> Result<CalendarEvent, Error> {
+    let provider = default_provider()?;
+    let event = provider.quick_add(text).await?;
+    save_events(provider.name(), std::slice::from_ref(&event)).map_err(Error::Repository)?;
+    Ok(event)
+}
+
+/**
+ * 先頭のプロバイダーでイベントを作成し、次の同期を待たずに保存する
+ */
+pub async fn create(new_event: &NewEvent) -> Result<CalendarEvent, Error> {
+    let provider = default_provider()?;
+    let event = provider.create(new_event).await?;
+    save_events(provider.name(), std::slice::from_ref(&event)).map_err(Error::Repository)?;
+    Ok(event)
+}
+
+fn default_provider() -> Result<Box<dyn CalendarProvider>, Error> {
+    configured_providers(&Env::new())
+        .into_iter()
+        .next()
+        .ok_or(Error::Unsupported("create"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resources() {
+        let event = CalendarEvent {
+            id: "event".to_string(),
+            description: Some(
+                "https://docs.google.com/document/d/abc https://zoom.us/j/1 https://example.com/notes https://example.webex.com/meet/alice"
+                    .to_string(),
+            ),
+            attachments: vec![models::EventResource {
+                source: "attachment".to_string(),
+                url: "https://docs.google.com/document/d/abc".to_string(),
+                title: Some("Agenda".to_string()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(
+            event
+                .resources(&MeetingLinkRegistry::default())
+                .iter()
+                .map(|r| (
+                    r.event_id.as_str(),
+                    r.position,
+                    r.source.as_str(),
+                    r.url.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "event",
+                    0,
+                    "attachment",
+                    "https://docs.google.com/document/d/abc"
+                ),
+                ("event", 1, "description", "https://example.com/notes"),
+            ]
+        );
+
+        assert_eq!(
+            event
+                .meeting_links(&MeetingLinkRegistry::default())
+                .iter()
+                .map(|l| (l.position, l.provider.as_str(), l.url.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, "zoom", "https://zoom.us/j/1"),
+                (1, "webex", "https://example.webex.com/meet/alice"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_links_from_location_and_entry_points() {
+        let event = CalendarEvent {
+            id: "event".to_string(),
+            location: Some("会議室 A / https://us02web.zoom.us/j/95428352872".to_string()),
+            description: Some(
+                r#"<p><a href="https://nam02.safelinks.protection.outlook.com/?url=https%3A%2F%2Fteams.microsoft.com%2Fl%2Fmeetup-join%2F19%253ameeting_abc&amp;data=05">Teams で参加</a></p>
+<p>資料: <a href="https://nam02.safelinks.protection.outlook.com/?url=https%3A%2F%2Fexample.com%2Fnotes&amp;data=05">notes</a></p>"#
+                    .to_string(),
+            ),
+            entry_points: vec![
+                models::ConferenceEntryPoint {
+                    entry_point_type: "phone".to_string(),
+                    uri: "tel:+81-3-1234-5678".to_string(),
+                    ..Default::default()
+                },
+                models::ConferenceEntryPoint {
+                    entry_point_type: "video".to_string(),
+                    uri: "https://meet.google.com/abc-defg-hij".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+        let registry = MeetingLinkRegistry::default();
+        assert_eq!(
+            event
+                .meeting_links(&registry)
+                .iter()
+                .map(|l| l.url.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "https://meet.google.com/abc-defg-hij",
+                "https://zoom.us/j/95428352872",
+                "https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc",
+            ]
+        );
+        assert_eq!(
+            event.link_in_sources(extract_zoom_link).as_deref(),
+            Some("https://zoom.us/j/95428352872")
+        );
+        assert_eq!(
+            event.link_in_sources(extract_teams_link).as_deref(),
+            Some("https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc")
+        );
+        assert_eq!(
+            event
+                .resources(&registry)
+                .iter()
+                .map(|r| r.url.as_str())
+                .collect::<Vec<_>>(),
+            vec!["https://example.com/notes"]
+        );
+    }
+}
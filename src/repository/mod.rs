@@ -4,9 +4,13 @@ use std::time::Duration;
 
 use diesel::{
     connection::SimpleConnection,
+    dsl,
+    query_builder::{InsertStatement, IntoUpdateTarget},
+    query_dsl::methods::ExecuteDsl,
     r2d2::{ConnectionManager, Pool, PooledConnection},
-    SqliteConnection,
+    Connection, Insertable, RunQueryDsl, SqliteConnection, Table,
 };
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
 
 use crate::env::Env;
 
@@ -54,12 +58,54 @@ fn get_connection() -> PooledConnection<ConnectionManager<SqliteConnection>> {
     pool.get().unwrap()
 }
 
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/**
+ * 未適用のマイグレーションを適用する
+ */
+pub fn run_migrations() -> Result<(), String> {
+    get_connection()
+        .run_pending_migrations(MIGRATIONS)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to run migrations: {}", e))
+}
+
+/**
+ * イベントに紐づく行(参加者・参加方法など)を、1 つのトランザクションで置き換える。
+ * rows_of_event(table からそのイベントの行を絞り込んだもの)を削除し、rows を保存する
+ */
+fn replace_for_event<Tab, Target, T>(
+    table: Tab,
+    rows_of_event: Target,
+    rows: Vec<T>,
+) -> Result<(), std::io::Error>
+where
+    Tab: Table,
+    Target: IntoUpdateTarget,
+    dsl::delete<Target>: ExecuteDsl<SqliteConnection>,
+    Vec<T>: Insertable<Tab>,
+    InsertStatement<Tab, <Vec<T> as Insertable<Tab>>::Values>: ExecuteDsl<SqliteConnection>,
+{
+    let result = get_connection().transaction(|conn| {
+        diesel::delete(rows_of_event).execute(conn)?;
+        if rows.is_empty() {
+            return Ok(0);
+        }
+        diesel::insert_into(table).values(rows).execute(conn)
+    });
+
+    // TODO エラー定義
+    result.map(|_| ()).map_err(std::io::Error::other)
+}
+
 pub mod event {
     use diesel::{
-        query_dsl::methods::FilterDsl, result, ExpressionMethods, QueryDsl, RunQueryDsl,
-        SelectableHelper,
+        query_dsl::methods::FilterDsl, result, BoolExpressionMethods, EscapeExpressionMethods,
+        ExpressionMethods, QueryDsl, RunQueryDsl, SelectableHelper, TextExpressionMethods,
     };
 
+    use chrono::{DateTime, Utc};
+
     use crate::schema::events;
 
     use super::models::{Event, EventFindMany, EventUpdate};
@@ -85,6 +131,44 @@ pub mod event {
         query_builder.load(&mut super::get_connection())
     }
 
+    /**
+     * 期間に重なる不在の予定
+     */
+    pub fn find_out_of_office(
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<Event>, result::Error> {
+        let query_builder = FilterDsl::filter(
+            events::table.select(Event::as_select()),
+            events::event_type
+                .eq("outOfOffice")
+                .and(events::start_datetime.lt(to))
+                .and(events::end_datetime.gt(from)),
+        );
+        query_builder
+            .order(events::start_datetime.asc())
+            .load(&mut super::get_connection())
+    }
+
+    /**
+     * ID が prefix で始まるイベントの ID
+     */
+    pub fn find_ids_starting_with(prefix: &str) -> Result<Vec<String>, result::Error> {
+        // LIKE の % と _ はそのまま比較する
+        let pattern = format!(
+            "{}%",
+            prefix
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        );
+        FilterDsl::filter(
+            events::table.select(events::id),
+            events::id.like(pattern).escape('\\'),
+        )
+        .load(&mut super::get_connection())
+    }
+
     pub fn create_many(events: Vec<Event>) -> Result<(), std::io::Error> {
         let result = diesel::insert_into(events::table)
             .values(&events)
@@ -93,7 +177,7 @@ pub mod event {
         match result {
             Ok(_) => Ok(()),
             // TODO エラー定義
-            Err(e) => Err(std::io::Error::new(std::io::ErrorKind::Other, e)),
+            Err(e) => Err(std::io::Error::other(e)),
         }
     }
 
@@ -105,7 +189,18 @@ pub mod event {
         match result {
             Ok(_) => Ok(()),
             // TODO エラー定義
-            Err(e) => Err(std::io::Error::new(std::io::ErrorKind::Other, e)),
+            Err(e) => Err(std::io::Error::other(e)),
+        }
+    }
+
+    pub fn delete_many(ids: Vec<String>) -> Result<(), std::io::Error> {
+        let result = diesel::delete(FilterDsl::filter(events::table, events::id.eq_any(ids)))
+            .execute(&mut super::get_connection());
+
+        match result {
+            Ok(_) => Ok(()),
+            // TODO エラー定義
+            Err(e) => Err(std::io::Error::other(e)),
         }
     }
 }
@@ -113,7 +208,9 @@ pub mod event {
 pub mod oauth_token {
     use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
 
-    use crate::{repository::models::OAuthToken, schema::oauth_tokens};
+    use crate::{
+        google_calendar::GOOGLE_PROVIDER_NAME, repository::models::OAuthToken, schema::oauth_tokens,
+    };
 
     use super::{get_connection, models::OAuthTokenUpdate};
 
@@ -125,7 +222,7 @@ pub mod oauth_token {
         match result {
             Ok(_) => Ok(()),
             // TODO エラー定義
-            Err(e) => Err(std::io::Error::new(std::io::ErrorKind::Other, e)),
+            Err(e) => Err(std::io::Error::other(e)),
         }
     }
 
@@ -137,20 +234,338 @@ pub mod oauth_token {
         match result {
             Ok(_) => Ok(()),
             // TODO エラー定義
-            Err(e) => Err(std::io::Error::new(std::io::ErrorKind::Other, e)),
+            Err(e) => Err(std::io::Error::other(e)),
         }
     }
 
+    /**
+     * 最新の Google のトークン
+     */
     pub fn find_latest() -> Result<Option<OAuthToken>, std::io::Error> {
-        let result = oauth_tokens::table
+        find_latest_of(GOOGLE_PROVIDER_NAME)
+    }
+
+    /**
+     * プロバイダーの最新のトークン
+     */
+    pub fn find_latest_of(provider: &str) -> Result<Option<OAuthToken>, std::io::Error> {
+        oauth_tokens::table
+            .filter(oauth_tokens::provider.eq(provider))
             .order(oauth_tokens::created_at.desc())
             .first::<OAuthToken>(&mut get_connection())
             .optional()
-            .expect("Error loading oauth token");
+            // TODO エラー定義
+            .map_err(std::io::Error::other)
+    }
+}
+
+pub mod watch_channel {
+    use diesel::{result, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+    use crate::{repository::models::WatchChannel, schema::watch_channels};
+
+    use super::get_connection;
+
+    pub fn create(watch_channel: WatchChannel) -> Result<(), std::io::Error> {
+        let result = diesel::insert_into(watch_channels::table)
+            .values(&watch_channel)
+            .execute(&mut get_connection());
+
+        match result {
+            Ok(_) => Ok(()),
+            // TODO エラー定義
+            Err(e) => Err(std::io::Error::other(e)),
+        }
+    }
+
+    pub fn find(id: &str) -> Result<Option<WatchChannel>, result::Error> {
+        watch_channels::table
+            .find(id)
+            .first::<WatchChannel>(&mut get_connection())
+            .optional()
+    }
+
+    /**
+     * 期限が until より後のチャンネルのうち、最も期限が遅いものを返す
+     */
+    pub fn find_active(
+        calendar_id: &str,
+        until: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<WatchChannel>, result::Error> {
+        watch_channels::table
+            .filter(watch_channels::calendar_id.eq(calendar_id))
+            .filter(watch_channels::expiration.gt(until))
+            .order(watch_channels::expiration.desc())
+            .first::<WatchChannel>(&mut get_connection())
+            .optional()
+    }
+
+    pub fn find_many(calendar_id: &str) -> Result<Vec<WatchChannel>, result::Error> {
+        watch_channels::table
+            .filter(watch_channels::calendar_id.eq(calendar_id))
+            .load::<WatchChannel>(&mut get_connection())
+    }
+
+    pub fn delete(id: &str) -> Result<(), std::io::Error> {
+        let result = diesel::delete(watch_channels::table.find(id)).execute(&mut get_connection());
+
+        match result {
+            Ok(_) => Ok(()),
+            // TODO エラー定義
+            Err(e) => Err(std::io::Error::other(e)),
+        }
+    }
+}
+
+pub mod sync_state {
+    use diesel::{result, OptionalExtension, QueryDsl, RunQueryDsl};
+
+    use crate::{repository::models::SyncState, schema::sync_states};
+
+    use super::get_connection;
+
+    pub fn find(calendar_id: &str) -> Result<Option<SyncState>, result::Error> {
+        sync_states::table
+            .find(calendar_id)
+            .first::<SyncState>(&mut get_connection())
+            .optional()
+    }
+
+    pub fn upsert(sync_state: SyncState) -> Result<(), std::io::Error> {
+        let result = diesel::insert_into(sync_states::table)
+            .values(&sync_state)
+            .on_conflict(sync_states::calendar_id)
+            .do_update()
+            .set(&sync_state)
+            .execute(&mut get_connection());
 
         match result {
-            Some(result) => Ok(Some(result)),
-            None => Ok(None),
+            Ok(_) => Ok(()),
+            // TODO エラー定義
+            Err(e) => Err(std::io::Error::other(e)),
         }
     }
 }
+
+pub mod series_notification_setting {
+    use diesel::{result, Connection, ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+    use crate::{
+        repository::models::SeriesNotificationSetting,
+        schema::{events, series_notification_settings},
+    };
+
+    use super::get_connection;
+
+    pub fn find(
+        recurring_event_id: &str,
+    ) -> Result<Option<SeriesNotificationSetting>, result::Error> {
+        series_notification_settings::table
+            .find(recurring_event_id)
+            .first::<SeriesNotificationSetting>(&mut get_connection())
+            .optional()
+    }
+
+    pub fn find_many(
+        recurring_event_ids: Vec<String>,
+    ) -> Result<Vec<SeriesNotificationSetting>, result::Error> {
+        series_notification_settings::table
+            .filter(series_notification_settings::recurring_event_id.eq_any(recurring_event_ids))
+            .load::<SeriesNotificationSetting>(&mut get_connection())
+    }
+
+    /**
+     * シリーズの通知設定を保存し、保存済みのインスタンスにも適用する。
+     * 適用したインスタンスの数を返す
+     */
+    pub fn apply(setting: SeriesNotificationSetting) -> Result<usize, std::io::Error> {
+        let result = get_connection().transaction(|conn| {
+            diesel::insert_into(series_notification_settings::table)
+                .values(&setting)
+                .on_conflict(series_notification_settings::recurring_event_id)
+                .do_update()
+                .set(&setting)
+                .execute(conn)?;
+            diesel::update(
+                events::table.filter(events::recurring_event_id.eq(&setting.recurring_event_id)),
+            )
+            .set((
+                events::notification_enabled.eq(setting.notification_enabled),
+                events::notification_sec_from_start.eq(setting.notification_sec_from_start),
+            ))
+            .execute(conn)
+        });
+
+        // TODO エラー定義
+        result.map_err(std::io::Error::other)
+    }
+}
+
+pub mod attendee {
+    use diesel::{result, ExpressionMethods, QueryDsl, RunQueryDsl};
+
+    use crate::{repository::models::Attendee, schema::attendees};
+
+    use super::get_connection;
+
+    /**
+     * イベントの参加者を attendees で置き換える
+     */
+    pub fn replace_for_event(
+        event_id: &str,
+        attendees: Vec<Attendee>,
+    ) -> Result<(), std::io::Error> {
+        super::replace_for_event(
+            attendees::table,
+            attendees::table.filter(attendees::event_id.eq(event_id)),
+            attendees,
+        )
+    }
+
+    /**
+     * 主催者を先頭に、メールアドレス順で返す
+     */
+    pub fn find_by_event_id(event_id: &str) -> Result<Vec<Attendee>, result::Error> {
+        attendees::table
+            .filter(attendees::event_id.eq(event_id))
+            .order((attendees::organizer.desc(), attendees::email.asc()))
+            .load::<Attendee>(&mut get_connection())
+    }
+
+    /**
+     * 各イベントでの自分自身の参加情報を返す
+     */
+    pub fn find_self_many(event_ids: Vec<String>) -> Result<Vec<Attendee>, result::Error> {
+        attendees::table
+            .filter(attendees::event_id.eq_any(event_ids))
+            .filter(attendees::is_self.eq(true))
+            .load::<Attendee>(&mut get_connection())
+    }
+
+    /**
+     * まだ返答していない(needsAction)イベントの ID を返す
+     */
+    pub fn find_needs_action_event_ids() -> Result<Vec<String>, result::Error> {
+        attendees::table
+            .filter(attendees::is_self.eq(true))
+            .filter(attendees::response_status.eq("needsAction"))
+            .select(attendees::event_id)
+            .load::<String>(&mut get_connection())
+    }
+}
+
+pub mod conference_entry_point {
+    use diesel::{result, ExpressionMethods, QueryDsl, RunQueryDsl};
+
+    use crate::{repository::models::ConferenceEntryPoint, schema::conference_entry_points};
+
+    use super::get_connection;
+
+    /**
+     * イベントの参加方法を entry_points で置き換える
+     */
+    pub fn replace_for_event(
+        event_id: &str,
+        entry_points: Vec<ConferenceEntryPoint>,
+    ) -> Result<(), std::io::Error> {
+        super::replace_for_event(
+            conference_entry_points::table,
+            conference_entry_points::table.filter(conference_entry_points::event_id.eq(event_id)),
+            entry_points,
+        )
+    }
+
+    pub fn find_by_event_id(event_id: &str) -> Result<Vec<ConferenceEntryPoint>, result::Error> {
+        conference_entry_points::table
+            .filter(conference_entry_points::event_id.eq(event_id))
+            .order(conference_entry_points::position.asc())
+            .load::<ConferenceEntryPoint>(&mut get_connection())
+    }
+}
+
+pub mod event_resource {
+    use diesel::{result, ExpressionMethods, QueryDsl, RunQueryDsl};
+
+    use crate::{repository::models::EventResource, schema::event_resources};
+
+    use super::get_connection;
+
+    /**
+     * イベントの資料を resources で置き換える
+     */
+    pub fn replace_for_event(
+        event_id: &str,
+        resources: Vec<EventResource>,
+    ) -> Result<(), std::io::Error> {
+        super::replace_for_event(
+            event_resources::table,
+            event_resources::table.filter(event_resources::event_id.eq(event_id)),
+            resources,
+        )
+    }
+
+    pub fn find_by_event_id(event_id: &str) -> Result<Vec<EventResource>, result::Error> {
+        event_resources::table
+            .filter(event_resources::event_id.eq(event_id))
+            .order(event_resources::position.asc())
+            .load::<EventResource>(&mut get_connection())
+    }
+}
+
+pub mod meeting_link {
+    use diesel::{result, ExpressionMethods, QueryDsl, RunQueryDsl};
+
+    use crate::{repository::models::EventMeetingLink, schema::meeting_links};
+
+    use super::get_connection;
+
+    /**
+     * イベントの会議のリンクを meeting_links で置き換える
+     */
+    pub fn replace_for_event(
+        event_id: &str,
+        meeting_links: Vec<EventMeetingLink>,
+    ) -> Result<(), std::io::Error> {
+        super::replace_for_event(
+            meeting_links::table,
+            meeting_links::table.filter(meeting_links::event_id.eq(event_id)),
+            meeting_links,
+        )
+    }
+
+    pub fn find_by_event_id(event_id: &str) -> Result<Vec<EventMeetingLink>, result::Error> {
+        meeting_links::table
+            .filter(meeting_links::event_id.eq(event_id))
+            .order(meeting_links::position.asc())
+            .load::<EventMeetingLink>(&mut get_connection())
+    }
+}
+
+pub mod dial_in {
+    use diesel::{result, ExpressionMethods, QueryDsl, RunQueryDsl};
+
+    use crate::{repository::models::EventDialIn, schema::dial_ins};
+
+    use super::get_connection;
+
+    /**
+     * イベントの電話で参加するための番号を dial_ins で置き換える
+     */
+    pub fn replace_for_event(
+        event_id: &str,
+        dial_ins: Vec<EventDialIn>,
+    ) -> Result<(), std::io::Error> {
+        super::replace_for_event(
+            dial_ins::table,
+            dial_ins::table.filter(dial_ins::event_id.eq(event_id)),
+            dial_ins,
+        )
+    }
+
+    pub fn find_by_event_id(event_id: &str) -> Result<Vec<EventDialIn>, result::Error> {
+        dial_ins::table
+            .filter(dial_ins::event_id.eq(event_id))
+            .order(dial_ins::position.asc())
+            .load::<EventDialIn>(&mut get_connection())
+    }
+}
@@ -1,7 +1,14 @@
+use chrono::{DateTime, NaiveDate, Utc};
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::schema::{events, oauth_tokens};
+use crate::google_calendar::GOOGLE_PROVIDER_NAME;
+use crate::meeting_link::dial_in::one_tap_uri;
+use crate::schema::{
+    attendees, conference_entry_points, dial_ins, event_resources, events, meeting_links,
+    oauth_tokens, series_notification_settings, sync_states, watch_channels,
+};
+use crate::timezone::DisplayTimezone;
 
 #[derive(
     Debug,
@@ -13,7 +20,6 @@ use crate::schema::{events, oauth_tokens};
     Serialize,
     Deserialize,
     Clone,
-    Default,
 )]
 #[diesel(table_name = oauth_tokens)]
 #[diesel(check_for_backend(diesel::sqlite::Sqlite))]
@@ -21,23 +27,42 @@ use crate::schema::{events, oauth_tokens};
 pub struct OAuthToken {
     pub id: String,
     pub access_token: String,
-    pub expires_in: Option<String>,
+    /// 有効期間(秒)。updated_at から数える
+    pub expires_in: Option<i64>,
     pub refresh_token: Option<String>,
     pub scope: Option<String>,
     pub token_type: Option<String>,
-    pub created_at: String,
-    pub updated_at: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// トークンを発行したプロバイダー(google / microsoft)
+    pub provider: String,
+}
+/// プロバイダーの指定がなければ Google のトークン
+impl Default for OAuthToken {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            access_token: String::new(),
+            expires_in: None,
+            refresh_token: None,
+            scope: None,
+            token_type: None,
+            created_at: DateTime::default(),
+            updated_at: DateTime::default(),
+            provider: GOOGLE_PROVIDER_NAME.to_string(),
+        }
+    }
 }
 
 #[derive(Queryable, AsChangeset)]
 #[diesel(table_name = oauth_tokens)]
 pub struct OAuthTokenUpdate {
     pub access_token: Option<String>,
-    pub expires_in: Option<String>,
+    pub expires_in: Option<i64>,
     pub refresh_token: Option<String>,
     pub scope: Option<String>,
     pub token_type: Option<String>,
-    pub updated_at: String,
+    pub updated_at: DateTime<Utc>,
 }
 
 #[derive(
@@ -64,16 +89,52 @@ pub struct Event {
     pub hangout_link: Option<String>,
     pub zoom_link: Option<String>,
     pub teams_link: Option<String>,
-    pub start_datetime: String,
-    pub end_datetime: String,
+    pub start_datetime: DateTime<Utc>,
+    pub end_datetime: DateTime<Utc>,
     pub notification_enabled: bool,
     pub notification_sec_from_start: i32,
+    /// 繰り返し予定のインスタンスの場合、シリーズのイベント ID
+    pub recurring_event_id: Option<String>,
+    /// 繰り返し予定のインスタンスの、本来の開始日時
+    pub original_start_time: Option<DateTime<Utc>>,
+    /// 終日の予定。開始・終了はその日の 0 時
+    pub all_day: bool,
+    /// 会議室・住所、またはオンライン会議の URL
+    pub location: Option<String>,
+    /// default / outOfOffice / focusTime / workingLocation など
+    pub event_type: Option<String>,
+    /// 同期元のカレンダープロバイダー(google など)
+    pub provider: String,
+    /// 終日の予定の、カレンダー上の開始日
+    pub start_date: Option<NaiveDate>,
+    /// 終日の予定の、カレンダー上の終了日(最終日の翌日)
+    pub end_date: Option<NaiveDate>,
+}
+impl Event {
+    /**
+     * 終日の予定の開始日と終了日(最終日の翌日)。保存した日付を使い、
+     * 日付を保存する前に同期した予定だけは表示タイムゾーンの日付にする
+     */
+    pub fn all_day_dates(
+        &self,
+        display_timezone: &DisplayTimezone,
+    ) -> Option<(NaiveDate, NaiveDate)> {
+        if !self.all_day {
+            return None;
+        }
+        Some((
+            self.start_date
+                .unwrap_or(display_timezone.convert(self.start_datetime).date_naive()),
+            self.end_date
+                .unwrap_or(display_timezone.convert(self.end_datetime).date_naive()),
+        ))
+    }
 }
 
 #[derive(Default)]
 pub struct EventFindMany {
-    pub from: Option<String>,
-    pub to: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
     pub ids_in: Option<Vec<String>>,
 }
 
@@ -86,8 +147,298 @@ pub struct EventUpdate {
     pub hangout_link: Option<String>,
     pub zoom_link: Option<String>,
     pub teams_link: Option<String>,
-    pub start_datetime: Option<String>,
-    pub end_datetime: Option<String>,
+    pub start_datetime: Option<DateTime<Utc>>,
+    pub end_datetime: Option<DateTime<Utc>>,
     pub notification_enabled: Option<bool>,
     pub notification_sec_from_start: Option<i32>,
+    pub recurring_event_id: Option<String>,
+    pub original_start_time: Option<DateTime<Utc>>,
+    pub all_day: Option<bool>,
+    pub location: Option<String>,
+    pub event_type: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+/**
+ * Google Calendar の events.watch で登録した通知チャンネル
+ */
+#[derive(
+    Debug, Queryable, Selectable, Identifiable, Insertable, Serialize, Deserialize, Clone, Default,
+)]
+#[diesel(table_name = watch_channels)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct WatchChannel {
+    pub id: String,
+    pub calendar_id: String,
+    pub resource_id: String,
+    pub resource_uri: Option<String>,
+    /// 通知が自分の登録したチャンネルからのものか検証するためのトークン
+    pub token: String,
+    pub expiration: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/**
+ * 差分同期に使う syncToken をカレンダーごとに保持する
+ */
+#[derive(
+    Debug,
+    Queryable,
+    Selectable,
+    Identifiable,
+    Insertable,
+    AsChangeset,
+    Serialize,
+    Deserialize,
+    Clone,
+    Default,
+)]
+#[diesel(table_name = sync_states)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+#[diesel(primary_key(calendar_id))]
+// 失効したトークンを None で消せるよう、更新時も NULL を書き込む
+#[diesel(treat_none_as_null = true)]
+pub struct SyncState {
+    pub calendar_id: String,
+    pub sync_token: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(
+    Debug, Queryable, Selectable, Insertable, Serialize, Deserialize, Clone, Default, PartialEq,
+)]
+#[diesel(table_name = attendees)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct Attendee {
+    pub event_id: String,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub organizer: bool,
+    /// 自分自身かどうか
+    pub is_self: bool,
+    // TODO enum にできるか？
+    /// needsAction / declined / tentative / accepted
+    pub response_status: Option<String>,
+}
+
+/**
+ * 繰り返し予定のシリーズ全体の通知設定。以降の同期で追加されるインスタンスにも引き継ぐ
+ */
+#[derive(Debug, Queryable, Selectable, Insertable, AsChangeset, Clone, PartialEq)]
+#[diesel(table_name = series_notification_settings)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct SeriesNotificationSetting {
+    pub recurring_event_id: String,
+    pub notification_enabled: bool,
+    pub notification_sec_from_start: i32,
+    pub updated_at: DateTime<Utc>,
+}
+
+/**
+ * 会議への参加方法。Google Calendar の conferenceData.entryPoints に対応する
+ */
+#[derive(
+    Debug, Queryable, Selectable, Insertable, Serialize, Deserialize, Clone, Default, PartialEq,
+)]
+#[diesel(table_name = conference_entry_points)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ConferenceEntryPoint {
+    pub event_id: String,
+    /// API から返された順番
+    pub position: i32,
+    /// video / phone / sip / more
+    pub entry_point_type: String,
+    pub uri: String,
+    pub label: Option<String>,
+    pub pin: Option<String>,
+    pub region_code: Option<String>,
+}
+impl ConferenceEntryPoint {
+    /**
+     * 電話で参加する場合の番号と PIN
+     *
+     * ```
+     * use calendar_notice::repository::models::ConferenceEntryPoint;
+     *
+     * let entry_point = ConferenceEntryPoint {
+     *     entry_point_type: "phone".to_string(),
+     *     uri: "tel:+81-3-1234-5678".to_string(),
+     *     label: Some("+81 3-1234-5678".to_string()),
+     *     pin: Some("123456789".to_string()),
+     *     region_code: Some("JP".to_string()),
+     *     ..Default::default()
+     * };
+     * assert_eq!(
+     *     entry_point.dial_in_text(),
+     *     Some("+81 3-1234-5678 (JP) PIN: 123456789".to_string())
+     * );
+     * ```
+     */
+    pub fn dial_in_text(&self) -> Option<String> {
+        if self.entry_point_type != "phone" {
+            return None;
+        }
+
+        let number = self
+            .label
+            .clone()
+            .unwrap_or(self.uri.trim_start_matches("tel:").to_string());
+        let mut text = number;
+        if let Some(region_code) = &self.region_code {
+            text.push_str(&format!(" ({})", region_code));
+        }
+        if let Some(pin) = &self.pin {
+            text.push_str(&format!(" PIN: {}", pin));
+        }
+        Some(text)
+    }
+
+    /**
+     * 電話で参加する場合に、PIN まで続けて発信する tel: の URI
+     *
+     * ```
+     * use calendar_notice::repository::models::ConferenceEntryPoint;
+     *
+     * let entry_point = ConferenceEntryPoint {
+     *     entry_point_type: "phone".to_string(),
+     *     uri: "tel:+1-555-0100".to_string(),
+     *     pin: Some("123456789".to_string()),
+     *     ..Default::default()
+     * };
+     * assert_eq!(entry_point.one_tap().as_deref(), Some("tel:+15550100,,123456789#"));
+     * ```
+     */
+    pub fn one_tap(&self) -> Option<String> {
+        if self.entry_point_type != "phone" {
+            return None;
+        }
+        Some(one_tap_uri(
+            self.uri.trim_start_matches("tel:"),
+            self.pin.as_deref(),
+            None,
+        ))
+    }
+}
+
+/**
+ * 予定の資料。添付ファイルと、説明文にある会議以外のリンク
+ */
+#[derive(
+    Debug, Queryable, Selectable, Insertable, Serialize, Deserialize, Clone, Default, PartialEq,
+)]
+#[diesel(table_name = event_resources)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct EventResource {
+    pub event_id: String,
+    /// 添付ファイル、説明文のリンクの順
+    pub position: i32,
+    /// attachment / description
+    pub source: String,
+    pub url: String,
+    pub title: Option<String>,
+    pub mime_type: Option<String>,
+}
+impl EventResource {
+    /**
+     * 一覧に表示する名前。タイトルがなければ URL
+     */
+    pub fn display_title(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.url)
+    }
+}
+
+/**
+ * 説明文などから抽出した、会議に参加するためのリンク
+ */
+#[derive(
+    Debug, Queryable, Selectable, Insertable, Serialize, Deserialize, Clone, Default, PartialEq,
+)]
+#[diesel(table_name = meeting_links)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct EventMeetingLink {
+    pub event_id: String,
+    /// 見つかった順番
+    pub position: i32,
+    /// zoom / teams / webex など
+    pub provider: String,
+    pub url: String,
+    pub meeting_id: Option<String>,
+    pub passcode: Option<String>,
+}
+
+/**
+ * 説明文から抽出した、電話で参加するための番号
+ */
+#[derive(
+    Debug, Queryable, Selectable, Insertable, Serialize, Deserialize, Clone, Default, PartialEq,
+)]
+#[diesel(table_name = dial_ins)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct EventDialIn {
+    pub event_id: String,
+    /// 見つかった順番
+    pub position: i32,
+    /// 説明文に書かれたままの番号
+    pub number: String,
+    /// 番号に添えられた国・地域
+    pub country: Option<String>,
+    /// 番号の後に入力する会議 ID。数字だけにしたもの
+    pub meeting_id: String,
+    pub passcode: Option<String>,
+}
+impl EventDialIn {
+    /**
+     * 番号・国・会議 ID・パスコード
+     *
+     * ```
+     * use calendar_notice::repository::models::EventDialIn;
+     *
+     * let dial_in = EventDialIn {
+     *     number: "+81 3 4578 1488".to_string(),
+     *     country: Some("Japan".to_string()),
+     *     meeting_id: "95428352872".to_string(),
+     *     passcode: Some("314159".to_string()),
+     *     ..Default::default()
+     * };
+     * assert_eq!(
+     *     dial_in.text(),
+     *     "+81 3 4578 1488 (Japan) 会議 ID: 95428352872# パスコード: 314159#"
+     * );
+     * ```
+     */
+    pub fn text(&self) -> String {
+        let mut text = self.number.clone();
+        if let Some(country) = &self.country {
+            text.push_str(&format!(" ({})", country));
+        }
+        text.push_str(&format!(" 会議 ID: {}#", self.meeting_id));
+        if let Some(passcode) = &self.passcode {
+            text.push_str(&format!(" パスコード: {}#", passcode));
+        }
+        text
+    }
+
+    /**
+     * 会議 ID とパスコードまで続けて発信する tel: の URI
+     *
+     * ```
+     * use calendar_notice::repository::models::EventDialIn;
+     *
+     * let dial_in = EventDialIn {
+     *     number: "+81 3 4578 1488".to_string(),
+     *     meeting_id: "95428352872".to_string(),
+     *     passcode: Some("314159".to_string()),
+     *     ..Default::default()
+     * };
+     * assert_eq!(dial_in.one_tap(), "tel:+81345781488,,95428352872#,,,,*314159#");
+     * ```
+     */
+    pub fn one_tap(&self) -> String {
+        one_tap_uri(
+            &self.number,
+            Some(&self.meeting_id),
+            self.passcode.as_deref(),
+        )
+    }
 }
@@ -1,5 +1,50 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    attendees (event_id, email) {
+        event_id -> Text,
+        email -> Text,
+        display_name -> Nullable<Text>,
+        organizer -> Bool,
+        is_self -> Bool,
+        response_status -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    conference_entry_points (event_id, position) {
+        event_id -> Text,
+        position -> Integer,
+        entry_point_type -> Text,
+        uri -> Text,
+        label -> Nullable<Text>,
+        pin -> Nullable<Text>,
+        region_code -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    dial_ins (event_id, position) {
+        event_id -> Text,
+        position -> Integer,
+        number -> Text,
+        country -> Nullable<Text>,
+        meeting_id -> Text,
+        passcode -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    event_resources (event_id, position) {
+        event_id -> Text,
+        position -> Integer,
+        source -> Text,
+        url -> Text,
+        title -> Nullable<Text>,
+        mime_type -> Nullable<Text>,
+    }
+}
+
 diesel::table! {
     events (id) {
         id -> Text,
@@ -9,10 +54,29 @@ diesel::table! {
         hangout_link -> Nullable<Text>,
         zoom_link -> Nullable<Text>,
         teams_link -> Nullable<Text>,
-        start_datetime -> Timestamp,
-        end_datetime -> Timestamp,
+        start_datetime -> TimestamptzSqlite,
+        end_datetime -> TimestamptzSqlite,
         notification_enabled -> Bool,
         notification_sec_from_start -> Integer,
+        recurring_event_id -> Nullable<Text>,
+        original_start_time -> Nullable<TimestamptzSqlite>,
+        all_day -> Bool,
+        location -> Nullable<Text>,
+        event_type -> Nullable<Text>,
+        provider -> Text,
+        start_date -> Nullable<Date>,
+        end_date -> Nullable<Date>,
+    }
+}
+
+diesel::table! {
+    meeting_links (event_id, position) {
+        event_id -> Text,
+        position -> Integer,
+        provider -> Text,
+        url -> Text,
+        meeting_id -> Nullable<Text>,
+        passcode -> Nullable<Text>,
     }
 }
 
@@ -20,16 +84,60 @@ diesel::table! {
     oauth_tokens (id) {
         id -> Text,
         access_token -> Text,
-        expires_in -> Nullable<Timestamp>,
+        expires_in -> Nullable<BigInt>,
         refresh_token -> Nullable<Text>,
         scope -> Nullable<Text>,
         token_type -> Nullable<Text>,
-        created_at -> Timestamp,
-        updated_at -> Timestamp,
+        created_at -> TimestamptzSqlite,
+        updated_at -> TimestamptzSqlite,
+        provider -> Text,
+    }
+}
+
+diesel::table! {
+    series_notification_settings (recurring_event_id) {
+        recurring_event_id -> Text,
+        notification_enabled -> Bool,
+        notification_sec_from_start -> Integer,
+        updated_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::table! {
+    sync_states (calendar_id) {
+        calendar_id -> Text,
+        sync_token -> Nullable<Text>,
+        updated_at -> TimestamptzSqlite,
     }
 }
 
+diesel::table! {
+    watch_channels (id) {
+        id -> Text,
+        calendar_id -> Text,
+        resource_id -> Text,
+        resource_uri -> Nullable<Text>,
+        token -> Text,
+        expiration -> TimestamptzSqlite,
+        created_at -> TimestamptzSqlite,
+    }
+}
+
+diesel::joinable!(attendees -> events (event_id));
+diesel::joinable!(conference_entry_points -> events (event_id));
+diesel::joinable!(dial_ins -> events (event_id));
+diesel::joinable!(event_resources -> events (event_id));
+diesel::joinable!(meeting_links -> events (event_id));
+
 diesel::allow_tables_to_appear_in_same_query!(
+    attendees,
+    conference_entry_points,
+    dial_ins,
+    event_resources,
     events,
+    meeting_links,
     oauth_tokens,
+    series_notification_settings,
+    sync_states,
+    watch_channels,
 );
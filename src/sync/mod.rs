@@ -0,0 +1,175 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    sync::{broadcast, mpsc, oneshot},
+    time::{self, Instant, MissedTickBehavior},
+};
+
+use crate::{
+    env::Env,
+    provider::{self, sync_all, sync_all_changes, watch_all, SyncWindow},
+};
+
+const STATUS_CHANNEL_CAPACITY: usize = 16;
+
+/// 同期ループへの要求
+#[derive(Debug, Clone, Copy)]
+pub enum SyncCommand {
+    /// 設定された期間で同期する。前回の同期のトークンがあれば変更だけを同期する
+    SyncNow,
+    /// 前回の同期以降の変更だけを同期する
+    SyncChanges,
+    /// 指定した期間で全件同期する
+    SyncWindow(SyncWindow),
+}
+
+/// 同期の経過
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncStatus {
+    Started,
+    Succeeded,
+    Failed(String),
+}
+
+/// subscribe したコンポーネントに配信される、どの要求による同期かを添えた経過
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncUpdate {
+    /// SyncHandle::request が返した ID。定期同期は None
+    pub request_id: Option<u64>,
+    pub status: SyncStatus,
+}
+
+/// 同期ループに送る要求。reply があれば、その要求の結果を返す
+struct SyncRequest {
+    id: u64,
+    command: SyncCommand,
+    reply: Option<oneshot::Sender<SyncStatus>>,
+}
+
+/**
+ * 同期ループへの要求の送信と、同期結果の購読を行うハンドル
+ */
+#[derive(Clone)]
+pub struct SyncHandle {
+    commands: mpsc::UnboundedSender<SyncRequest>,
+    status: broadcast::Sender<SyncUpdate>,
+    next_request_id: Arc<AtomicU64>,
+}
+impl SyncHandle {
+    /// 同期を要求し、経過の SyncUpdate::request_id と照合する ID を返す。同期ループが終了している場合は Err
+    pub fn request(&self, command: SyncCommand) -> Result<u64, String> {
+        self.send(command, None)
+    }
+
+    fn send(
+        &self,
+        command: SyncCommand,
+        reply: Option<oneshot::Sender<SyncStatus>>,
+    ) -> Result<u64, String> {
+        let id = self.next_request_id.fetch_add(1, Ordering::Relaxed);
+        self.commands
+            .send(SyncRequest { id, command, reply })
+            .map_err(|_| "Sync loop is not running".to_string())?;
+        Ok(id)
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<SyncUpdate> {
+        self.status.subscribe()
+    }
+}
+
+/**
+ * SYNC_INTERVAL_SEC ごとの同期と、要求を受けた同期を行うループを起動する。
+ * 起動直後は同期しないため、必要であれば SyncCommand::SyncNow を送る
+ */
+pub fn spawn_sync_loop() -> SyncHandle {
+    let (command_tx, mut command_rx) = mpsc::unbounded_channel();
+    let (status_tx, _) = broadcast::channel(STATUS_CHANNEL_CAPACITY);
+    let handle = SyncHandle {
+        commands: command_tx,
+        status: status_tx.clone(),
+        next_request_id: Arc::new(AtomicU64::new(0)),
+    };
+
+    tokio::spawn(async move {
+        let env = Env::new();
+        let period = Duration::from_secs(env.sync_interval_sec);
+        let mut interval = time::interval_at(Instant::now() + period, period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        // 認可画面を開いたプロバイダー
+        let mut auth_requested = HashSet::new();
+
+        loop {
+            let (request_id, command, reply) = tokio::select! {
+                _ = interval.tick() => (None, SyncCommand::SyncNow, None),
+                request = command_rx.recv() => match request {
+                    Some(SyncRequest { id, command, reply }) => (Some(id), command, reply),
+                    None => break,
+                },
+            };
+
+            let _ = status_tx.send(SyncUpdate {
+                request_id,
+                status: SyncStatus::Started,
+            });
+            let status = match run_sync(command).await {
+                Ok(_) => {
+                    auth_requested.clear();
+                    SyncStatus::Succeeded
+                }
+                Err(provider::Error::AuthorizationRequired(name)) => {
+                    // 認証が完了するまで、ループのたびにブラウザを開かない
+                    if auth_requested.insert(name) {
+                        tokio::task::spawn_blocking(move || provider::authorize_on_browser(name));
+                    }
+                    SyncStatus::Failed(format!("OAuth token of {} is not found", name))
+                }
+                Err(e) => SyncStatus::Failed(format!("{:?}", e)),
+            };
+            if let Some(reply) = reply {
+                let _ = reply.send(status.clone());
+            }
+            let _ = status_tx.send(SyncUpdate { request_id, status });
+
+            // 要求を受けて同期した場合も、次の定期同期は 1 周期後にする
+            interval.reset();
+        }
+    });
+
+    handle
+}
+
+async fn run_sync(command: SyncCommand) -> Result<(), provider::Error> {
+    let window = SyncWindow::from_env(&Env::new());
+    match command {
+        SyncCommand::SyncNow => {
+            // 変更通知がなくても、差分同期で削除されたイベントを反映する
+            sync_all_changes(window).await?;
+            // 変更通知のチャンネルを登録・更新する。通知が届かなくてもポーリングで同期される
+            watch_all().await;
+            Ok(())
+        }
+        SyncCommand::SyncChanges => sync_all_changes(window).await,
+        SyncCommand::SyncWindow(window) => sync_all(window).await,
+    }
+}
+
+/**
+ * 同期を要求し、その要求の結果が出るまで待つ。定期同期など、他の同期の結果は無視する
+ */
+pub async fn request_and_wait(handle: &SyncHandle, command: SyncCommand) -> SyncStatus {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    if let Err(e) = handle.send(command, Some(reply_tx)) {
+        return SyncStatus::Failed(e);
+    }
+    reply_rx
+        .await
+        .unwrap_or(SyncStatus::Failed("Sync loop is stopped".to_string()))
+}
@@ -0,0 +1,269 @@
+use chrono::{
+    DateTime, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc,
+};
+use chrono_tz::Tz;
+
+use crate::env::Env;
+
+/**
+ * 表示に使うタイムゾーン。DISPLAY_TIMEZONE が未設定の場合はシステムのタイムゾーン
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DisplayTimezone {
+    #[default]
+    System,
+    Named(Tz),
+}
+impl DisplayTimezone {
+    pub fn from_env(env: &Env) -> Self {
+        match env.display_timezone {
+            Some(tz) => DisplayTimezone::Named(tz),
+            None => DisplayTimezone::System,
+        }
+    }
+
+    pub fn convert(&self, utc: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            DisplayTimezone::System => utc.with_timezone(&chrono::Local).fixed_offset(),
+            DisplayTimezone::Named(tz) => utc.with_timezone(tz).fixed_offset(),
+        }
+    }
+
+    /// 表示タイムゾーンでの、その日の 0 時
+    pub fn start_of_day(&self, utc: DateTime<Utc>) -> DateTime<Utc> {
+        let date = self.convert(utc).date_naive();
+        match self {
+            DisplayTimezone::System => local_to_utc(date.and_time(NaiveTime::MIN), &chrono::Local),
+            DisplayTimezone::Named(tz) => local_to_utc(date.and_time(NaiveTime::MIN), tz),
+        }
+    }
+}
+
+/**
+ * オフセット付きの RFC3339 の日時を UTC にする。不正な値の場合は None
+ *
+ * ```
+ * use calendar_notice::timezone::parse_rfc3339;
+ *
+ * let utc = parse_rfc3339("2026-10-19T12:00:00+09:00").unwrap();
+ * assert_eq!(utc.to_rfc3339(), "2026-10-19T03:00:00+00:00");
+ * assert_eq!(parse_rfc3339("2026-10-19 12:00"), None);
+ * ```
+ */
+pub fn parse_rfc3339(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|date_time| date_time.to_utc())
+}
+
+/**
+ * 地域の日時を UTC にする。
+ * 夏時間の開始で存在しない時刻は切り替え後の時刻に、終了で重複する時刻は早い方に寄せる
+ */
+pub fn local_to_utc<Tz: TimeZone>(naive: NaiveDateTime, tz: &Tz) -> DateTime<Utc> {
+    match tz.from_local_datetime(&naive) {
+        LocalResult::Single(date_time) => date_time.to_utc(),
+        LocalResult::Ambiguous(earliest, _) => earliest.to_utc(),
+        LocalResult::None => {
+            // 切り替えの幅は通常 1 時間のため、1 時間ずらした時刻と同じ UTC オフセット差で戻す
+            let shifted = naive + chrono::Duration::hours(1);
+            match tz.from_local_datetime(&shifted) {
+                LocalResult::Single(date_time) | LocalResult::Ambiguous(date_time, _) => {
+                    date_time.to_utc()
+                }
+                LocalResult::None => Utc.from_utc_datetime(&naive),
+            }
+        }
+    }
+}
+
+/**
+ * Google Calendar の dateTime を UTC にする。
+ * オフセットがない場合は time_zone(なければシステムのタイムゾーン)の時刻として扱う
+ */
+pub fn parse_date_time(date_time: &str, time_zone: Option<Tz>) -> Option<DateTime<Utc>> {
+    if let Some(utc) = parse_rfc3339(date_time) {
+        return Some(utc);
+    }
+    let naive = NaiveDateTime::parse_from_str(date_time, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+    Some(match time_zone {
+        Some(tz) => local_to_utc(naive, &tz),
+        None => local_to_utc(naive, &chrono::Local),
+    })
+}
+
+/**
+ * 終日の予定の date を、time_zone(なければシステムのタイムゾーン)での 0 時として UTC にする
+ */
+pub fn parse_date(date: &str, time_zone: Option<Tz>) -> Option<DateTime<Utc>> {
+    let naive = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()?
+        .and_time(NaiveTime::MIN);
+    Some(match time_zone {
+        Some(tz) => local_to_utc(naive, &tz),
+        None => local_to_utc(naive, &chrono::Local),
+    })
+}
+
+/**
+ * 予定の期間を表示用に整形する。終日の予定は、タイムゾーンを変換せずにカレンダー上の日付だけを表示する
+ */
+pub fn format_range(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    all_day_dates: Option<(NaiveDate, NaiveDate)>,
+    display_timezone: &DisplayTimezone,
+) -> String {
+    if let Some((start_date, end_date)) = all_day_dates {
+        // 終了日は翌日のため、最終日は 1 日前
+        let last_day = end_date - chrono::Duration::days(1);
+        if last_day <= start_date {
+            return format!("{} 終日", start_date.format("%Y-%m-%d"));
+        }
+        return format!(
+            "{} 〜 {} 終日",
+            start_date.format("%Y-%m-%d"),
+            last_day.format("%Y-%m-%d")
+        );
+    }
+    let start = display_timezone.convert(start);
+    let end = display_timezone.convert(end);
+    if start.date_naive() == end.date_naive() {
+        return format!(
+            "{} 〜 {} ({})",
+            start.format("%Y-%m-%d %H:%M"),
+            end.format("%H:%M"),
+            start.format("%:z")
+        );
+    }
+    format!(
+        "{} 〜 {} ({})",
+        start.format("%Y-%m-%d %H:%M"),
+        end.format("%Y-%m-%d %H:%M"),
+        start.format("%:z")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        parse_rfc3339(value).unwrap()
+    }
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_date_time_with_offset() {
+        // 他のタイムゾーンで作成された予定も UTC に揃える
+        assert_eq!(
+            parse_date_time("2026-10-20T10:00:00+05:30", None),
+            Some(utc("2026-10-20T04:30:00Z"))
+        );
+        assert_eq!(
+            parse_date_time("2026-10-20T10:00:00", Some(chrono_tz::America::Los_Angeles)),
+            Some(utc("2026-10-20T17:00:00Z"))
+        );
+        assert_eq!(parse_date_time("tomorrow", None), None);
+    }
+
+    #[test]
+    fn test_local_to_utc_across_dst() {
+        let new_york = chrono_tz::America::New_York;
+        let naive = |value: &str| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap();
+
+        // 夏時間の開始で存在しない 2:30 は、切り替え後の 3:30 (EDT) にする
+        assert_eq!(
+            local_to_utc(naive("2026-03-08 02:30"), &new_york),
+            utc("2026-03-08T07:30:00Z")
+        );
+        // 夏時間の終了で 2 回ある 1:30 は、早い方 (EDT) にする
+        assert_eq!(
+            local_to_utc(naive("2026-11-01 01:30"), &new_york),
+            utc("2026-11-01T05:30:00Z")
+        );
+        assert_eq!(
+            local_to_utc(naive("2026-11-01 03:00"), &new_york),
+            utc("2026-11-01T08:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_parse_date_on_dst_change() {
+        let london = Some(chrono_tz::Europe::London);
+        let start = parse_date("2026-03-29", london).unwrap();
+        let end = parse_date("2026-03-30", london).unwrap();
+        assert_eq!(start, utc("2026-03-29T00:00:00Z"));
+        assert_eq!(end, utc("2026-03-29T23:00:00Z"));
+        assert_eq!(end - start, chrono::Duration::hours(23));
+    }
+
+    #[test]
+    fn test_display_timezone() {
+        let new_york = DisplayTimezone::Named(chrono_tz::America::New_York);
+        assert_eq!(
+            new_york.convert(utc("2026-11-01T05:30:00Z")).to_rfc3339(),
+            "2026-11-01T01:30:00-04:00"
+        );
+        assert_eq!(
+            new_york.convert(utc("2026-11-01T06:30:00Z")).to_rfc3339(),
+            "2026-11-01T01:30:00-05:00"
+        );
+        assert_eq!(
+            new_york.start_of_day(utc("2026-11-01T12:00:00Z")),
+            utc("2026-11-01T04:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_format_range() {
+        let tokyo = DisplayTimezone::Named(chrono_tz::Asia::Tokyo);
+        assert_eq!(
+            format_range(
+                utc("2026-10-20T04:30:00Z"),
+                utc("2026-10-20T05:00:00Z"),
+                None,
+                &tokyo
+            ),
+            "2026-10-20 13:30 〜 14:00 (+09:00)"
+        );
+        assert_eq!(
+            format_range(
+                utc("2026-10-19T15:00:00Z"),
+                utc("2026-10-20T15:00:00Z"),
+                Some((date("2026-10-20"), date("2026-10-21"))),
+                &tokyo
+            ),
+            "2026-10-20 終日"
+        );
+        assert_eq!(
+            format_range(
+                utc("2026-10-19T15:00:00Z"),
+                utc("2026-10-22T15:00:00Z"),
+                Some((date("2026-10-20"), date("2026-10-23"))),
+                &tokyo
+            ),
+            "2026-10-20 〜 2026-10-22 終日"
+        );
+    }
+
+    #[test]
+    fn test_format_range_all_day_in_other_timezone() {
+        // 東京のカレンダーの終日予定を、ニューヨークの表示タイムゾーンで表示しても日付がずれない
+        let start = parse_date("2026-10-19", Some(chrono_tz::Asia::Tokyo)).unwrap();
+        let end = parse_date("2026-10-20", Some(chrono_tz::Asia::Tokyo)).unwrap();
+        let new_york = DisplayTimezone::Named(chrono_tz::America::New_York);
+        assert_eq!(
+            format_range(
+                start,
+                end,
+                Some((date("2026-10-19"), date("2026-10-20"))),
+                &new_york
+            ),
+            "2026-10-19 終日"
+        );
+    }
+}
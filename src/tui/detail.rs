@@ -0,0 +1,217 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Widget, Wrap},
+};
+
+use crate::{
+    env::Env,
+    location,
+    repository::{self, models},
+    timezone::{self, DisplayTimezone},
+};
+
+/// 選択中のイベントの詳細
+pub struct EventDetail {
+    pub event: models::Event,
+    pub attendees: Vec<models::Attendee>,
+    pub entry_points: Vec<models::ConferenceEntryPoint>,
+    /// 説明文から抽出した会議のリンク
+    pub meeting_links: Vec<models::EventMeetingLink>,
+    /// 説明文から抽出した電話で参加するための番号
+    pub dial_ins: Vec<models::EventDialIn>,
+    /// 添付ファイルと説明文のリンク。先頭を資料として開く
+    pub resources: Vec<models::EventResource>,
+    pub display_timezone: DisplayTimezone,
+    /// 場所までの移動時間(分)。通知はこの分だけ早まる
+    pub travel_minutes: i64,
+}
+
+impl EventDetail {
+    pub fn load(event: &models::Event, display_timezone: DisplayTimezone) -> Self {
+        let attendees = repository::attendee::find_by_event_id(&event.id).unwrap_or_else(|e| {
+            println!("Failed to get attendees: {:?}", e);
+            vec![]
+        });
+
+        let entry_points = repository::conference_entry_point::find_by_event_id(&event.id)
+            .unwrap_or_else(|e| {
+                println!("Failed to get entry points: {:?}", e);
+                vec![]
+            });
+
+        let meeting_links =
+            repository::meeting_link::find_by_event_id(&event.id).unwrap_or_else(|e| {
+                println!("Failed to get meeting links: {:?}", e);
+                vec![]
+            });
+
+        let dial_ins = repository::dial_in::find_by_event_id(&event.id).unwrap_or_else(|e| {
+            println!("Failed to get dial-ins: {:?}", e);
+            vec![]
+        });
+
+        let resources =
+            repository::event_resource::find_by_event_id(&event.id).unwrap_or_else(|e| {
+                println!("Failed to get resources: {:?}", e);
+                vec![]
+            });
+
+        let travel_minutes = Env::new()
+            .travel_time_rules
+            .travel_minutes(event.location.as_deref());
+
+        Self {
+            event: event.clone(),
+            attendees,
+            entry_points,
+            meeting_links,
+            dial_ins,
+            resources,
+            display_timezone,
+            travel_minutes,
+        }
+    }
+
+    fn self_response_status(&self) -> Option<&str> {
+        self.attendees
+            .iter()
+            .find(|attendee| attendee.is_self)
+            .and_then(|attendee| attendee.response_status.as_deref())
+    }
+}
+
+fn response_status_label(response_status: Option<&str>) -> Span<'static> {
+    match response_status {
+        Some("accepted") => "参加".green(),
+        Some("tentative") => "未定".yellow(),
+        Some("declined") => "辞退".red(),
+        Some("needsAction") => "未回答".magenta(),
+        _ => "-".into(),
+    }
+}
+
+impl Widget for &EventDetail {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let event = &self.event;
+        let title = event
+            .summary
+            .clone()
+            .unwrap_or("[タイトル未設定]".to_string());
+        let block = Block::bordered()
+            .title(format!(" {} ", title).bold())
+            .border_set(border::PLAIN);
+
+        let mut lines = vec![Line::from(timezone::format_range(
+            event.start_datetime,
+            event.end_datetime,
+            event.all_day_dates(&self.display_timezone),
+            &self.display_timezone,
+        ))];
+
+        if let Some(place) = &event.location {
+            lines.push(Line::from(vec!["場所: ".bold(), place.clone().into()]));
+            lines.push(Line::from(location::location_link(place).fg(Color::Cyan)));
+        }
+
+        let mut notification = vec![if event.notification_enabled {
+            format!("通知: {} 分前", event.notification_sec_from_start / 60).into()
+        } else {
+            "通知: オフ".fg(Color::DarkGray)
+        }];
+        if event.notification_enabled && self.travel_minutes > 0 {
+            notification.push(format!(" + 移動 {} 分", self.travel_minutes).into());
+        }
+        if event.recurring_event_id.is_some() {
+            notification.push(" (繰り返し予定)".fg(Color::DarkGray));
+        }
+        lines.push(Line::from(notification));
+
+        let video_uris = self
+            .entry_points
+            .iter()
+            .filter(|entry_point| entry_point.entry_point_type == "video")
+            .map(|entry_point| &entry_point.uri);
+        let description_links = [&event.teams_link, &event.zoom_link, &event.hangout_link]
+            .into_iter()
+            .flatten()
+            .chain(
+                self.meeting_links
+                    .iter()
+                    .map(|meeting_link| &meeting_link.url),
+            );
+        let mut links: Vec<&String> = vec![];
+        for link in video_uris.chain(description_links) {
+            if !links.contains(&link) {
+                links.push(link);
+            }
+        }
+        for link in links {
+            lines.push(Line::from(link.clone().fg(Color::Cyan)));
+        }
+
+        if !self.resources.is_empty() {
+            lines.push(Line::from(vec![
+                "資料:".bold(),
+                " <O> で先頭を開く".fg(Color::DarkGray),
+            ]));
+            for resource in &self.resources {
+                let mut spans = vec![format!("  {}", resource.display_title()).into()];
+                if resource.title.is_some() {
+                    spans.push(" ".into());
+                    spans.push(resource.url.clone().fg(Color::Cyan));
+                }
+                lines.push(Line::from(spans));
+            }
+        }
+
+        let dial_ins: Vec<String> = self
+            .entry_points
+            .iter()
+            .filter_map(models::ConferenceEntryPoint::dial_in_text)
+            .chain(self.dial_ins.iter().map(models::EventDialIn::text))
+            .collect();
+        if !dial_ins.is_empty() {
+            lines.push(Line::from(vec![
+                "電話で参加:".bold(),
+                " <P> で先頭に発信".fg(Color::DarkGray),
+            ]));
+            for dial_in in dial_ins {
+                lines.push(Line::from(format!("  {}", dial_in)));
+            }
+        }
+
+        if !self.attendees.is_empty() {
+            lines.push(Line::from(vec![
+                "自分の出欠: ".into(),
+                response_status_label(self.self_response_status()),
+            ]));
+            lines.push(Line::from("参加者:".bold()));
+            for attendee in &self.attendees {
+                let name = attendee
+                    .display_name
+                    .clone()
+                    .unwrap_or(attendee.email.clone());
+                let mut spans = vec![
+                    "  ".into(),
+                    response_status_label(attendee.response_status.as_deref()),
+                    " ".into(),
+                    Span::raw(name),
+                ];
+                if attendee.organizer {
+                    spans.push(" (主催者)".fg(Color::DarkGray));
+                }
+                lines.push(Line::from(spans));
+            }
+        }
+
+        Paragraph::new(lines)
+            .style(Style::default())
+            .wrap(Wrap { trim: false })
+            .block(block)
+            .render(area, buf);
+    }
+}
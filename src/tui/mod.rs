@@ -1,44 +1,44 @@
-use chrono::Timelike;
 use ui::UI;
 
-use crate::repository::{
-    self,
-    models::{self, EventFindMany},
+use crate::{
+    env::Env,
+    repository::{
+        self,
+        models::{self, EventFindMany},
+    },
+    sync::SyncHandle,
+    timezone::DisplayTimezone,
 };
 
+mod detail;
 mod ui;
 
-pub fn show_tui() {
+pub fn show_tui(sync_handle: SyncHandle) {
     let mut terminal = ratatui::init();
     let events = fetch_today_events();
     let mut ui = UI {
         events,
+        display_timezone: DisplayTimezone::from_env(&Env::new()),
         ..Default::default()
     };
 
-    let _ = ui.run(&mut terminal, fetch_today_events);
+    let _ = ui.run(&mut terminal, fetch_today_events, sync_handle);
 
     ratatui::restore();
 }
 
 fn fetch_today_events() -> Vec<models::Event> {
-    let start_of_today = chrono::Local::now()
-        .with_hour(0)
-        .unwrap()
-        .with_minute(0)
-        .unwrap()
-        .with_second(0)
-        .unwrap();
-    let tomorrow = start_of_today + chrono::Duration::days(1);
-
-    let events = repository::event::find_many(EventFindMany {
-        from: Some(start_of_today.to_rfc3339()),
-        to: Some(tomorrow.to_rfc3339()),
+    // 表示タイムゾーンでの今日。夏時間の切り替え日は 24 時間ではないため、翌日の 0 時を求め直す
+    let display_timezone = DisplayTimezone::from_env(&Env::new());
+    let start_of_today = display_timezone.start_of_day(chrono::Utc::now());
+    let tomorrow = display_timezone.start_of_day(start_of_today + chrono::Duration::hours(36));
+
+    repository::event::find_many(EventFindMany {
+        from: Some(start_of_today),
+        to: Some(tomorrow),
         ..Default::default()
     })
     .expect("Failed to find events.")
     .into_iter()
-    .collect::<Vec<models::Event>>();
-
-    events
+    .collect::<Vec<models::Event>>()
 }
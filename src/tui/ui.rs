@@ -1,4 +1,3 @@
-use chrono::prelude::*;
 use std::{
     io,
     sync::mpsc::{self, Receiver, Sender},
@@ -10,18 +9,30 @@ use std::{
 use crossterm::event::{self, Event, KeyCode, KeyEventKind};
 use ratatui::{
     buffer::Buffer,
-    layout::{Alignment, Constraint, Rect},
+    layout::{Alignment, Constraint, Layout, Rect},
     style::{Color, Style, Stylize},
     symbols::border,
     text::Line,
     widgets::{
         block::{Position, Title},
-        Block, Cell, Row, Table, Widget,
+        Block, Cell, Paragraph, Row, Table, Widget,
     },
     DefaultTerminal, Frame,
 };
 
-use crate::repository::{self, models};
+use crate::{
+    env::Env,
+    google_calendar::{event_type::EventType, rsvp::ResponseStatus},
+    launcher::{LaunchCommand, Launcher},
+    meeting_link::dial_in::dial_in_uri,
+    notification::{apply_notification_settings, NotificationSettings},
+    provider,
+    repository::{self, models},
+    sync::{SyncCommand, SyncHandle, SyncStatus, SyncUpdate},
+    timezone::DisplayTimezone,
+};
+
+use super::detail::EventDetail;
 
 // const UI_REFRESH_INTERVAL_SEC: u64 = 60; // TODO
 const UI_REFRESH_INTERVAL_SEC: u64 = 5;
@@ -30,6 +41,13 @@ const UI_REFRESH_INTERVAL_SEC: u64 = 5;
 pub struct UI {
     pub events: Vec<repository::models::Event>,
     pub selected_event_id: Option<String>,
+    pub detail: Option<EventDetail>,
+    pub display_timezone: DisplayTimezone,
+    pub sync_status: Option<SyncStatus>,
+    /// 直前の操作の結果
+    pub message: Option<String>,
+    /// 入力中のクイック追加の文章。入力中でなければ None
+    pub quick_add_input: Option<String>,
     pub exit: bool,
 }
 impl UI {
@@ -37,7 +55,12 @@ impl UI {
         &mut self,
         terminal: &mut DefaultTerminal,
         fetch_events: fn() -> Vec<models::Event>,
+        sync_handle: SyncHandle,
     ) -> io::Result<()> {
+        let mut sync_status_rx = sync_handle.subscribe();
+        // 出欠の回答など、バックグラウンドで実行した操作の結果
+        let (message_tx, message_rx): (Sender<String>, Receiver<String>) = mpsc::channel();
+
         // イベント更新用のチャンネルを設定
         let (tx, rx): (Sender<()>, Receiver<()>) = mpsc::channel();
 
@@ -57,13 +80,29 @@ impl UI {
 
             // キー入力イベントをノンブロッキングでチェック
             if event::poll(Duration::from_millis(100)).unwrap() {
-                self.handle_key_events()?;
+                self.handle_key_events(&sync_handle, fetch_events, &message_tx)?;
+            }
+
+            // 同期の経過を反映し、同期が終わったらイベントを読み直す
+            while let Ok(SyncUpdate { status, .. }) = sync_status_rx.try_recv() {
+                if status == SyncStatus::Succeeded {
+                    self.events = fetch_events();
+                    self.load_detail();
+                }
+                self.sync_status = Some(status);
+            }
+
+            while let Ok(message) = message_rx.try_recv() {
+                self.message = Some(message);
+                self.events = fetch_events();
+                self.load_detail();
             }
 
             // 定期的なイベント更新をチェック
             if rx.try_recv().is_ok() {
                 let events = fetch_events();
                 self.events = events;
+                self.load_detail();
             }
 
             // 現在の UI 状態に基づいて画面を描画
@@ -77,16 +116,48 @@ impl UI {
         frame.render_widget(self, frame.area());
     }
 
-    fn handle_key_events(&mut self) -> io::Result<bool> {
+    fn handle_key_events(
+        &mut self,
+        sync_handle: &SyncHandle,
+        fetch_events: fn() -> Vec<models::Event>,
+        message_tx: &Sender<String>,
+    ) -> io::Result<bool> {
         match event::read()? {
+            Event::Key(key_event)
+                if key_event.kind == KeyEventKind::Press && self.quick_add_input.is_some() =>
+            {
+                self.handle_quick_add_input(key_event.code, message_tx);
+            }
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
                 match key_event.code {
                     KeyCode::Char(c) if c.is_ascii_digit() => {
                         let num = c.to_digit(10).unwrap_or(0);
                         if num > 0 && num <= self.events.len() as u32 {
                             self.selected_event_id = Some(self.events[num as usize - 1].id.clone());
+                            self.load_detail();
                         }
                     }
+                    KeyCode::Char('s') => {
+                        if let Err(e) = sync_handle.request(SyncCommand::SyncNow) {
+                            self.sync_status = Some(SyncStatus::Failed(e));
+                        }
+                    }
+                    // 繰り返し予定の場合はシリーズ全体の通知を切り替える
+                    KeyCode::Char(c @ ('m' | 'u')) => {
+                        self.set_notification_enabled(c == 'u');
+                        self.events = fetch_events();
+                        self.load_detail();
+                    }
+                    KeyCode::Char('n') => self.quick_add_input = Some(String::new()),
+                    KeyCode::Char('o') => self.open_agenda(),
+                    KeyCode::Char('p') => self.dial_in(),
+                    KeyCode::Char('a') => self.respond(ResponseStatus::Accepted, message_tx),
+                    KeyCode::Char('d') => self.respond(ResponseStatus::Declined, message_tx),
+                    KeyCode::Char('t') => self.respond(ResponseStatus::Tentative, message_tx),
+                    KeyCode::Esc => {
+                        self.selected_event_id = None;
+                        self.detail = None;
+                    }
                     KeyCode::Char('q') => {
                         self.selected_event_id = None;
                         self.exit()
@@ -99,6 +170,117 @@ impl UI {
         Ok(true)
     }
 
+    /// 選択中のイベントの詳細を読み直す
+    fn load_detail(&mut self) {
+        self.detail = self.selected_event_id.as_ref().and_then(|id| {
+            self.events
+                .iter()
+                .find(|event| &event.id == id)
+                .map(|event| EventDetail::load(event, self.display_timezone))
+        });
+    }
+
+    fn set_notification_enabled(&mut self, enabled: bool) {
+        let Some(detail) = &self.detail else {
+            return;
+        };
+        let settings = NotificationSettings {
+            enabled: Some(enabled),
+            ..Default::default()
+        };
+        self.message = Some(
+            match apply_notification_settings(&detail.event, settings, true) {
+                Ok(_) if enabled => "通知をオンにしました".to_string(),
+                Ok(_) => "通知をオフにしました".to_string(),
+                Err(e) => e,
+            },
+        );
+    }
+
+    /// 選択中のイベントの資料(添付ファイル、なければ説明文のリンク)を開く
+    fn open_agenda(&mut self) {
+        let Some(detail) = &self.detail else {
+            return;
+        };
+        self.message = Some(match detail.resources.first() {
+            Some(agenda) => match Launcher::from_env(&Env::new())
+                .browser_command(&agenda.url, None)
+                .run()
+            {
+                Ok(_) => format!("資料を開きました: {}", agenda.display_title()),
+                Err(e) => format!("資料を開けませんでした: {}", e),
+            },
+            None => "資料がありません".to_string(),
+        });
+    }
+
+    /// 選択中のイベントに電話で参加する。会議 ID とパスコードまで続けて発信する
+    fn dial_in(&mut self) {
+        let Some(detail) = &self.detail else {
+            return;
+        };
+        self.message = Some(match dial_in_uri(&detail.entry_points, &detail.dial_ins) {
+            Some(uri) => match LaunchCommand::System(uri.clone()).run() {
+                Ok(_) => format!("発信しました: {}", uri),
+                Err(e) => format!("発信できませんでした: {}", e),
+            },
+            None => "電話番号がありません".to_string(),
+        });
+    }
+
+    /// クイック追加の文章を編集し、Enter でバックグラウンドで予定を作成する
+    fn handle_quick_add_input(&mut self, code: KeyCode, message_tx: &Sender<String>) {
+        let Some(input) = self.quick_add_input.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => self.quick_add_input = None,
+            KeyCode::Enter if !input.trim().is_empty() => {
+                let text = input.trim().to_string();
+                let message_tx = message_tx.clone();
+                self.quick_add_input = None;
+                self.message = Some("予定を作成中...".to_string());
+                tokio::spawn(async move {
+                    let message = match provider::quick_add(&text).await {
+                        Ok(event) => {
+                            format!("予定を作成しました: {}", event.summary.unwrap_or_default())
+                        }
+                        Err(provider::Error::InsufficientScope) => {
+                            "予定の編集を許可するため、再起動して認可し直してください".to_string()
+                        }
+                        Err(e) => format!("予定の作成に失敗しました: {:?}", e),
+                    };
+                    let _ = message_tx.send(message);
+                });
+            }
+            _ => {}
+        }
+    }
+
+    /// 選択中のイベントへの出欠をバックグラウンドで回答する
+    fn respond(&mut self, status: ResponseStatus, message_tx: &Sender<String>) {
+        let Some(detail) = &self.detail else {
+            return;
+        };
+        let event_id = detail.event.id.clone();
+        let message_tx = message_tx.clone();
+        self.message = Some("出欠を送信中...".to_string());
+        tokio::spawn(async move {
+            let message = match provider::respond(&event_id, status).await {
+                Ok(_) => "出欠を回答しました".to_string(),
+                Err(provider::Error::InsufficientScope) => {
+                    "予定の編集を許可するため、再起動して認可し直してください".to_string()
+                }
+                Err(e) => format!("出欠の回答に失敗しました: {:?}", e),
+            };
+            let _ = message_tx.send(message);
+        });
+    }
+
     fn exit(&mut self) {
         self.exit = true;
     }
@@ -106,11 +288,54 @@ impl UI {
 
 impl Widget for &UI {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        let title = Title::from(" 本日の予定 ".bold());
+        // 今日の勤務場所は見出しに出す
+        let working_location = self
+            .events
+            .iter()
+            .find(|event| EventType::of(event) == EventType::WorkingLocation)
+            .and_then(|event| event.location.clone().or(event.summary.clone()));
+        let title = Title::from(match working_location {
+            Some(working_location) => {
+                format!(" 本日の予定 (勤務場所: {}) ", working_location).bold()
+            }
+            None => " 本日の予定 ".bold(),
+        });
+        let sync_status = match &self.sync_status {
+            Some(SyncStatus::Started) => "同期中...".to_string(),
+            Some(SyncStatus::Succeeded) => "同期完了".to_string(),
+            Some(SyncStatus::Failed(reason)) => format!("同期失敗: {}", reason),
+            None => "".to_string(),
+        };
         let instructions = Title::from(Line::from(vec![
             // TODO ショートカットキーの説明を追加
+            " Select ".into(),
+            "<1-9>".blue().bold(),
+            " Close ".into(),
+            "<Esc>".blue().bold(),
+            " Accept ".into(),
+            "<A>".blue().bold(),
+            " Decline ".into(),
+            "<D>".blue().bold(),
+            " Tentative ".into(),
+            "<T>".blue().bold(),
+            " Agenda ".into(),
+            "<O>".blue().bold(),
+            " Dial in ".into(),
+            "<P>".blue().bold(),
+            " New ".into(),
+            "<N>".blue().bold(),
+            " Mute ".into(),
+            "<M>".blue().bold(),
+            " Unmute ".into(),
+            "<U>".blue().bold(),
+            " Sync ".into(),
+            "<S>".blue().bold(),
             " Quit ".into(),
             "<Q> ".blue().bold(),
+            sync_status.into(),
+            " ".into(),
+            self.message.clone().unwrap_or_default().into(),
+            " ".into(),
         ]));
         let block = Block::bordered()
             .title(title.alignment(Alignment::Center))
@@ -127,17 +352,30 @@ impl Widget for &UI {
         let header = Row::new(header_cells).style(Style::default().bg(Color::DarkGray));
 
         let rows = self.events.iter().enumerate().map(|(index, event)| {
-            let datetime = DateTime::parse_from_rfc3339(&event.start_datetime)
-                .expect("Invalid datetime format");
+            let datetime = match event.all_day_dates(&self.display_timezone) {
+                Some((start_date, _)) => format!("{} 終日", start_date.format("%m-%d")),
+                None => self
+                    .display_timezone
+                    .convert(event.start_datetime)
+                    .format("%m-%d %H-%M")
+                    .to_string(),
+            };
             let cells = [
                 Cell::from((index + 1).to_string()),
-                Cell::from(datetime.format("%m-%d %H-%M").to_string()),
-                Cell::from(
+                Cell::from(datetime),
+                Cell::from(format!(
+                    "{}{}",
+                    match EventType::of(event) {
+                        EventType::OutOfOffice => "[不在] ",
+                        EventType::FocusTime => "[集中] ",
+                        EventType::WorkingLocation => "[勤務場所] ",
+                        EventType::Default | EventType::Other => "",
+                    },
                     event
                         .summary
                         .clone()
-                        .unwrap_or("[タイトル未設定]".to_string()),
-                ),
+                        .unwrap_or("[タイトル未設定]".to_string())
+                )),
             ];
 
             Row::new(cells)
@@ -154,6 +392,31 @@ impl Widget for &UI {
         .header(header)
         .block(block);
 
-        table.render(area, buf);
+        // クイック追加の入力中は、一覧の下に入力欄を表示する
+        let area =
+            match &self.quick_add_input {
+                Some(input) => {
+                    let [area, input_area] =
+                        Layout::vertical([Constraint::Min(0), Constraint::Length(3)]).areas(area);
+                    Paragraph::new(input.as_str())
+                        .block(Block::bordered().title(
+                            " 予定を追加 (例: 明日 12:30 ランチ) Enter で作成 / Esc で中止 ",
+                        ))
+                        .render(input_area, buf);
+                    area
+                }
+                None => area,
+            };
+
+        match &self.detail {
+            Some(detail) => {
+                let [table_area, detail_area] =
+                    Layout::vertical([Constraint::Percentage(50), Constraint::Percentage(50)])
+                        .areas(area);
+                table.render(table_area, buf);
+                detail.render(detail_area, buf);
+            }
+            None => table.render(area, buf),
+        }
     }
 }
@@ -0,0 +1,319 @@
+//! Radicale 風の CalDAV サーバーを warp で用意し、カレンダーの探索 → calendar-query での全件同期 →
+//! sync-collection での差分同期までを確認する
+
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use calendar_notice::{
+    caldav::{self, CalDavProvider},
+    env::Env,
+    notification::filter_upcoming_events,
+    provider::{self, sync_changed_events, CalendarProvider, SyncWindow},
+    repository::{
+        self,
+        models::{EventFindMany, SyncState},
+    },
+};
+use common::setup_env;
+use warp::{http::StatusCode, Filter};
+
+mod common;
+
+const AUTHORIZATION: &str = "Basic bWVAZXhhbXBsZS5jb206YXBwLXBhc3N3b3Jk";
+const CALENDAR_PATH: &str = "/dav/calendars/me/work/";
+
+fn ical_date_time(date_time: chrono::DateTime<chrono::Utc>) -> String {
+    date_time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn standup_ics(summary: &str) -> String {
+    let start = chrono::Utc::now() + chrono::Duration::minutes(5);
+    format!(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:standup\r\nDTSTART:{}\r\nDTEND:{}\r\nSUMMARY:{}\r\nDESCRIPTION:Join: https://zoom.us/j/95428352872\r\nATTENDEE;PARTSTAT=NEEDS-ACTION:mailto:me@example.com\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+        ical_date_time(start),
+        ical_date_time(start + chrono::Duration::minutes(15)),
+        summary
+    )
+}
+
+/// サーバーで展開済みの繰り返し予定
+fn weekly_ics() -> String {
+    let first = chrono::Utc::now() + chrono::Duration::days(1);
+    let instances: Vec<String> = [first, first + chrono::Duration::weeks(1)]
+        .iter()
+        .map(|start| {
+            format!(
+                "BEGIN:VEVENT\r\nUID:weekly\r\nRECURRENCE-ID:{0}\r\nDTSTART:{0}\r\nDURATION:PT1H\r\nSUMMARY:Weekly sync\r\nEND:VEVENT\r\n",
+                ical_date_time(*start)
+            )
+        })
+        .collect();
+    format!(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\n{}END:VCALENDAR\r\n",
+        instances.concat()
+    )
+}
+
+fn multistatus(responses: &[String], sync_token: Option<&str>) -> String {
+    format!(
+        r#"<?xml version="1.0"?><d:multistatus xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">{}{}</d:multistatus>"#,
+        responses.concat(),
+        sync_token
+            .map(|token| format!("<d:sync-token>{}</d:sync-token>", token))
+            .unwrap_or_default()
+    )
+}
+
+fn prop_response(href: &str, prop: &str) -> String {
+    format!(
+        "<d:response><d:href>{}</d:href><d:propstat><d:prop>{}</d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>",
+        href, prop
+    )
+}
+
+fn calendar_data_response(name: &str, ics: &str) -> String {
+    prop_response(
+        &format!("{}{}", CALENDAR_PATH, name),
+        &format!(
+            "<d:getetag>\"1\"</d:getetag><c:calendar-data>{}</c:calendar-data>",
+            ics
+        ),
+    )
+}
+
+fn handle(method: &str, path: &str, body: &str) -> (StatusCode, String) {
+    match (method, path) {
+        ("PROPFIND", "/dav/") if body.contains("current-user-principal") => (
+            StatusCode::MULTI_STATUS,
+            multistatus(
+                &[prop_response(
+                    "/dav/",
+                    "<d:current-user-principal><d:href>/dav/principals/me/</d:href></d:current-user-principal>",
+                )],
+                None,
+            ),
+        ),
+        ("PROPFIND", "/dav/principals/me/") => (
+            StatusCode::MULTI_STATUS,
+            multistatus(
+                &[prop_response(
+                    "/dav/principals/me/",
+                    "<c:calendar-home-set><d:href>/dav/calendars/me/</d:href></c:calendar-home-set>",
+                )],
+                None,
+            ),
+        ),
+        ("PROPFIND", "/dav/calendars/me/") => (
+            StatusCode::MULTI_STATUS,
+            multistatus(
+                &[
+                    prop_response(
+                        "/dav/calendars/me/",
+                        "<d:resourcetype><d:collection/></d:resourcetype>",
+                    ),
+                    prop_response(
+                        CALENDAR_PATH,
+                        r#"<d:resourcetype><d:collection/><c:calendar/></d:resourcetype><c:supported-calendar-component-set><c:comp name="VEVENT"/></c:supported-calendar-component-set>"#,
+                    ),
+                    prop_response(
+                        "/dav/calendars/me/tasks/",
+                        r#"<d:resourcetype><d:collection/><c:calendar/></d:resourcetype><c:supported-calendar-component-set><c:comp name="VTODO"/></c:supported-calendar-component-set>"#,
+                    ),
+                ],
+                None,
+            ),
+        ),
+        ("PROPFIND", CALENDAR_PATH) => (
+            StatusCode::MULTI_STATUS,
+            multistatus(
+                &[prop_response(
+                    CALENDAR_PATH,
+                    "<d:sync-token>http://radicale/sync/1</d:sync-token>",
+                )],
+                None,
+            ),
+        ),
+        ("REPORT", CALENDAR_PATH) if body.contains("calendar-query") => (
+            StatusCode::MULTI_STATUS,
+            multistatus(
+                &[
+                    calendar_data_response("standup.ics", &standup_ics("Daily standup")),
+                    calendar_data_response("weekly.ics", &weekly_ics()),
+                ],
+                None,
+            ),
+        ),
+        ("REPORT", CALENDAR_PATH)
+            if body.contains("sync-collection") && body.contains("http://radicale/sync/1") =>
+        {
+            (
+                StatusCode::MULTI_STATUS,
+                multistatus(
+                    &[
+                        prop_response(
+                            &format!("{}standup.ics", CALENDAR_PATH),
+                            "<d:getetag>\"2\"</d:getetag>",
+                        ),
+                        format!(
+                            "<d:response><d:href>{}weekly.ics</d:href><d:status>HTTP/1.1 404 Not Found</d:status></d:response>",
+                            CALENDAR_PATH
+                        ),
+                    ],
+                    Some("http://radicale/sync/2"),
+                ),
+            )
+        }
+        ("REPORT", CALENDAR_PATH) if body.contains("sync-collection") => (
+            StatusCode::FORBIDDEN,
+            r#"<d:error xmlns:d="DAV:"><d:valid-sync-token/></d:error>"#.to_string(),
+        ),
+        ("REPORT", CALENDAR_PATH) if body.contains("calendar-multiget") => (
+            StatusCode::MULTI_STATUS,
+            multistatus(
+                &[calendar_data_response(
+                    "standup.ics",
+                    &standup_ics("Daily standup (moved)"),
+                )],
+                None,
+            ),
+        ),
+        _ => (StatusCode::NOT_FOUND, "".to_string()),
+    }
+}
+
+fn spawn_fake_caldav(reports: Arc<Mutex<Vec<String>>>) -> SocketAddr {
+    let routes = warp::method()
+        .and(warp::path::full())
+        .and(warp::header::optional::<String>("authorization"))
+        .and(warp::body::bytes())
+        .map(
+            move |method: warp::http::Method,
+                  path: warp::path::FullPath,
+                  authorization: Option<String>,
+                  body: warp::hyper::body::Bytes| {
+                if authorization.as_deref() != Some(AUTHORIZATION) {
+                    return warp::reply::with_status(String::new(), StatusCode::UNAUTHORIZED);
+                }
+                let body = String::from_utf8_lossy(&body);
+                if let Some(report) = ["calendar-query", "sync-collection", "calendar-multiget"]
+                    .into_iter()
+                    .find(|report| body.contains(report))
+                {
+                    reports.lock().unwrap().push(report.to_string());
+                }
+                let (status, body) = handle(method.as_str(), path.as_str(), &body);
+                warp::reply::with_status(body, status)
+            },
+        );
+
+    let (addr, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    addr
+}
+
+fn stored_ids() -> Vec<String> {
+    let mut ids: Vec<String> = repository::event::find_many(EventFindMany::default())
+        .unwrap()
+        .into_iter()
+        .map(|event| event.id)
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[tokio::test]
+async fn discover_query_and_sync_collection() {
+    let reports = Arc::new(Mutex::new(vec![]));
+    let addr = spawn_fake_caldav(reports.clone());
+    let _env = setup_env(addr);
+    std::env::set_var("CALDAV_URL", format!("http://{}/dav/", addr));
+    std::env::set_var("CALDAV_USERNAME", "me@example.com");
+    std::env::set_var("CALDAV_PASSWORD", "wrong-password");
+
+    let window = SyncWindow::from_env(&Env::new());
+
+    // 認証に失敗した場合は何も保存しない
+    let provider = CalDavProvider::from_env(&Env::new()).unwrap();
+    assert!(matches!(
+        sync_changed_events(&provider, window).await,
+        Err(provider::Error::CalDav(caldav::Error::Unauthorized))
+    ));
+    assert!(stored_ids().is_empty());
+
+    std::env::set_var("CALDAV_PASSWORD", "app-password");
+    let provider = CalDavProvider::from_env(&Env::new()).unwrap();
+
+    // 予定を扱わないカレンダーは除く
+    assert_eq!(
+        provider
+            .discover_calendars()
+            .await
+            .unwrap()
+            .iter()
+            .map(|url| url.path().to_string())
+            .collect::<Vec<_>>(),
+        vec![CALENDAR_PATH]
+    );
+
+    // sync-token がないため全件同期する。展開されたインスタンスは別のイベントとして保存する
+    sync_changed_events(&provider, window).await.unwrap();
+    let standup_id = format!("caldav:{}standup.ics", CALENDAR_PATH);
+    let weekly_id = format!("caldav:{}weekly.ics", CALENDAR_PATH);
+    let ids = stored_ids();
+    assert_eq!(ids.len(), 3);
+    assert_eq!(ids[0], standup_id);
+    assert!(ids[1].starts_with(&format!("{}#", weekly_id)));
+    assert!(ids[2].starts_with(&format!("{}#", weekly_id)));
+
+    let events = repository::event::find_many(EventFindMany::default()).unwrap();
+    let standup = events.iter().find(|event| event.id == standup_id).unwrap();
+    assert_eq!(standup.provider, "caldav");
+    assert_eq!(
+        standup.zoom_link.as_deref(),
+        Some("https://zoom.us/j/95428352872")
+    );
+    assert!(events
+        .iter()
+        .filter(|event| event.id != standup_id)
+        .all(|event| event.recurring_event_id.as_deref() == Some(weekly_id.as_str())));
+    let attendees = repository::attendee::find_by_event_id(&standup_id).unwrap();
+    assert!(attendees[0].is_self);
+    assert_eq!(attendees[0].response_status.as_deref(), Some("needsAction"));
+
+    // Google の予定と同じく、既定の通知設定で通知対象になる
+    assert_eq!(
+        filter_upcoming_events(events)
+            .into_iter()
+            .map(|event| event.id)
+            .collect::<Vec<_>>(),
+        vec![standup_id.clone()]
+    );
+
+    // sync-collection で変更されたリソースだけを取得し、削除されたリソースのインスタンスを消す
+    reports.lock().unwrap().clear();
+    sync_changed_events(&provider, window).await.unwrap();
+    assert_eq!(stored_ids(), vec![standup_id.clone()]);
+    let events = repository::event::find_many(EventFindMany::default()).unwrap();
+    assert_eq!(events[0].summary.as_deref(), Some("Daily standup (moved)"));
+    assert_eq!(
+        reports.lock().unwrap().clone(),
+        vec!["sync-collection", "calendar-multiget"]
+    );
+    let sync_token = repository::sync_state::find(&provider.calendar_id())
+        .unwrap()
+        .and_then(|sync_state| sync_state.sync_token)
+        .unwrap();
+    assert!(sync_token.contains("http://radicale/sync/2"));
+
+    // sync-token が失効していれば全件同期し直す
+    repository::sync_state::upsert(SyncState {
+        calendar_id: provider.calendar_id(),
+        sync_token: Some(sync_token.replace("sync/2", "sync/expired")),
+        updated_at: chrono::Utc::now(),
+    })
+    .unwrap();
+    sync_changed_events(&provider, window).await.unwrap();
+    assert_eq!(stored_ids().len(), 3);
+}
@@ -0,0 +1,589 @@
+#![allow(dead_code)]
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+};
+
+use calendar_notice::repository::{
+    self,
+    models::{Event, EventFindMany, OAuthToken},
+};
+use serde_json::json;
+use warp::{
+    http::{Response, StatusCode},
+    Filter,
+};
+
+pub const ACCESS_TOKEN: &str = "fake-access-token";
+
+pub fn event_json(
+    id: &str,
+    summary: &str,
+    start: chrono::DateTime<chrono::Utc>,
+    description: &str,
+) -> serde_json::Value {
+    json!({
+        "kind": "calendar#event",
+        "etag": "\"etag\"",
+        "id": id,
+        "status": "confirmed",
+        "created": "2024-08-01T00:00:00.000Z",
+        "updated": "2024-08-01T00:00:00.000Z",
+        "summary": summary,
+        "description": description,
+        "creator": { "email": "me@example.com" },
+        "organizer": { "email": "me@example.com" },
+        "start": { "dateTime": start.to_rfc3339(), "timeZone": "Asia/Tokyo" },
+        "end": { "dateTime": (start + chrono::Duration::minutes(30)).to_rfc3339(), "timeZone": "Asia/Tokyo" },
+        "sequence": 0,
+        "eventType": "default"
+    })
+}
+
+pub fn events_json(items: Vec<serde_json::Value>, next_sync_token: &str) -> serde_json::Value {
+    json!({
+        "kind": "calendar#events",
+        "etag": "\"etag\"",
+        "summary": "me@example.com",
+        "updated": "2024-08-01T00:00:00.000Z",
+        "timeZone": "Asia/Tokyo",
+        "nextSyncToken": next_sync_token,
+        "items": items
+    })
+}
+
+pub fn token_json() -> serde_json::Value {
+    json!({
+        "access_token": ACCESS_TOKEN,
+        "expires_in": 3599,
+        "refresh_token": "fake-refresh-token",
+        "scope": "https://www.googleapis.com/auth/calendar.events",
+        "token_type": "Bearer"
+    })
+}
+
+/// 環境変数はプロセス全体で共有するため、同じテストバイナリのテストを 1 つずつ実行する
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+/**
+ * setup_env で用意したテスト環境。drop するまで他のテストの setup_env を待たせ、drop で一時ディレクトリを消す
+ */
+pub struct TestEnv {
+    pub dir: PathBuf,
+    _lock: MutexGuard<'static, ()>,
+}
+impl Drop for TestEnv {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/**
+ * 一時ディレクトリに DB と oauth_secret.json を用意し、モックサーバーを向くよう環境変数を設定する
+ */
+pub fn setup_env(addr: SocketAddr) -> TestEnv {
+    // 先に失敗したテストがあっても、環境変数は設定し直すので続けられる
+    let lock = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let dir = std::env::temp_dir().join(format!("calendar-notice-test-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let secret_path = dir.join("oauth_secret.json");
+    std::fs::write(
+        &secret_path,
+        json!({
+            "client_id": "test_client_id",
+            "client_secret": "test_client_secret",
+            "token_uri": format!("http://{}/token", addr)
+        })
+        .to_string(),
+    )
+    .unwrap();
+
+    std::env::set_var("DATABASE_URL", dir.join("db.sqlite").to_str().unwrap());
+    std::env::set_var("PORT", "8990");
+    std::env::set_var("BASE_URL", "http://localhost");
+    std::env::set_var("GOOGLE_API_BASE_URL", format!("http://{}", addr));
+    std::env::set_var("GOOGLE_OAUTH_BASE_URL", format!("http://{}", addr));
+    std::env::set_var("OAUTH_SECRET_PATH", secret_path.to_str().unwrap());
+    // 前のテストがテストごとに設定した値を残さない
+    std::env::remove_var("WEBHOOK_ADDRESS");
+    std::env::remove_var("MEETING_LINK_PATTERNS");
+
+    repository::run_migrations().unwrap();
+
+    TestEnv { dir, _lock: lock }
+}
+
+/**
+ * 保存済みの OAuth トークンを 1 つ作る。scope が None の場合はスコープを記録しない
+ */
+pub fn create_token(id: &str, scope: Option<&str>, created_at: chrono::DateTime<chrono::Utc>) {
+    repository::oauth_token::create(OAuthToken {
+        id: id.to_string(),
+        access_token: ACCESS_TOKEN.to_string(),
+        expires_in: Some(3599),
+        scope: scope.map(str::to_string),
+        created_at,
+        updated_at: created_at,
+        ..Default::default()
+    })
+    .unwrap();
+}
+
+/// 保存済みのイベントの ID(開始日時順)
+pub fn event_ids() -> Vec<String> {
+    repository::event::find_many(EventFindMany::default())
+        .unwrap()
+        .into_iter()
+        .map(|event| event.id)
+        .collect()
+}
+
+/// 保存済みのイベントを ID で引けるようにする
+pub fn events_by_id() -> HashMap<String, Event> {
+    repository::event::find_many(EventFindMany::default())
+        .unwrap()
+        .into_iter()
+        .map(|event| (event.id.clone(), event))
+        .collect()
+}
+
+fn serve<F>(routes: F) -> SocketAddr
+where
+    F: Filter<Error = warp::Rejection> + Clone + Send + Sync + 'static,
+    F::Extract: warp::Reply,
+{
+    let (addr, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    addr
+}
+
+/// spawn_fake_google_oauth が受け取ったリクエスト
+#[derive(Default)]
+pub struct OAuthReceived {
+    pub token_requests: Vec<HashMap<String, String>>,
+    pub authorizations: Vec<String>,
+}
+
+/**
+ * トークンエンドポイントと、会議・資料付きの予定、別の会議の予定、終日の予定を返す一覧
+ */
+pub fn spawn_fake_google_oauth(received: Arc<Mutex<OAuthReceived>>) -> SocketAddr {
+    let token_received = received.clone();
+    let token = warp::post()
+        .and(warp::path("token"))
+        .and(warp::body::form::<HashMap<String, String>>())
+        .map(move |form: HashMap<String, String>| {
+            token_received.lock().unwrap().token_requests.push(form);
+            warp::reply::json(&token_json())
+        });
+
+    let events_received = received.clone();
+    let events = warp::get()
+        .and(warp::path!(
+            "calendar" / "v3" / "calendars" / "primary" / "events"
+        ))
+        .and(warp::header::<String>("authorization"))
+        .map(move |authorization: String| {
+            events_received
+                .lock()
+                .unwrap()
+                .authorizations
+                .push(authorization);
+            let now = chrono::Utc::now();
+            let mut soon = event_json(
+                "soon",
+                "Daily standup",
+                now + chrono::Duration::minutes(5),
+                "Join: https://zoom.us/j/95428352872?pwd=abc\nAgenda: https://docs.google.com/document/d/agenda/edit\nNotes: https://example.com/notes",
+            );
+            soon["attachments"] = json!([{
+                "fileUrl": "https://docs.google.com/document/d/agenda/edit",
+                "title": "Standup agenda",
+                "mimeType": "application/vnd.google-apps.document",
+                "fileId": "agenda"
+            }]);
+            soon["location"] = json!("Office B 3F");
+            soon["attendees"] = json!([
+                { "email": "me@example.com", "self": true, "responseStatus": "needsAction" },
+                { "email": "boss@example.com", "displayName": "Boss", "organizer": true, "responseStatus": "accepted" }
+            ]);
+            soon["conferenceData"] = json!({
+                "conferenceId": "abc-defg-hij",
+                "conferenceSolution": {
+                    "key": { "type": "hangoutsMeet" },
+                    "name": "Google Meet",
+                    "iconUri": "https://example.com/meet.png"
+                },
+                "entryPoints": [
+                    { "entryPointType": "video", "uri": "https://meet.google.com/abc-defg-hij", "label": "meet.google.com/abc-defg-hij" },
+                    { "entryPointType": "phone", "uri": "tel:+1-555-0100", "label": "+1 555-0100", "pin": "123456789", "regionCode": "US" }
+                ]
+            });
+            // 終日の予定は日付だけを持つ
+            let mut holiday = event_json("holiday", "Holiday", now, "");
+            let holiday_date = (now + chrono::Duration::days(3)).date_naive();
+            holiday["start"] = json!({ "date": holiday_date.to_string() });
+            holiday["end"] = json!({ "date": holiday_date.succ_opt().unwrap().to_string() });
+            warp::reply::json(&events_json(
+                vec![
+                    soon,
+                    event_json(
+                        "later",
+                        "Planning",
+                        now + chrono::Duration::days(2),
+                        "Join: https://teams.microsoft.com/l/meetup-join/19%3ameeting_ABC",
+                    ),
+                    holiday,
+                ],
+                "sync-token-1",
+            ))
+        });
+
+    serve(token.or(events))
+}
+
+/**
+ * 2 ページに分かれた一覧。rate_limited が true の間は、2 ページ目を待機時間の上限より長い Retry-After の 429 にする
+ */
+pub fn spawn_fake_google_rate_limited(rate_limited: Arc<AtomicBool>) -> SocketAddr {
+    let events = warp::get()
+        .and(warp::path!(
+            "calendar" / "v3" / "calendars" / "primary" / "events"
+        ))
+        .and(warp::query::<HashMap<String, String>>())
+        .map(move |query: HashMap<String, String>| {
+            let start = chrono::Utc::now() + chrono::Duration::hours(1);
+            match query.get("pageToken") {
+                None => {
+                    let mut page = events_json(vec![event_json("first", "First", start, "")], "");
+                    page["nextPageToken"] = "page-2".into();
+                    page.as_object_mut().unwrap().remove("nextSyncToken");
+                    Response::builder().status(200).body(page.to_string())
+                }
+                // 待機時間の上限より長い Retry-After で、リトライせずに諦めさせる
+                Some(_) if rate_limited.load(Ordering::SeqCst) => Response::builder()
+                    .status(429)
+                    .header("Retry-After", "120")
+                    .body(String::new()),
+                Some(_) => Response::builder().status(200).body(
+                    events_json(
+                        vec![event_json("second", "Second", start, "")],
+                        "sync-token",
+                    )
+                    .to_string(),
+                ),
+            }
+        });
+
+    serve(events)
+}
+
+/**
+ * 全件同期では kept と removed を、sync-token-1 での差分同期では removed のキャンセルを返す一覧。
+ * 受け取った syncToken を sync_tokens に記録する
+ */
+pub fn spawn_fake_google_changes(sync_tokens: Arc<Mutex<Vec<Option<String>>>>) -> SocketAddr {
+    let events = warp::get()
+        .and(warp::path!(
+            "calendar" / "v3" / "calendars" / "primary" / "events"
+        ))
+        .and(warp::query::<HashMap<String, String>>())
+        .map(move |query: HashMap<String, String>| {
+            let sync_token = query.get("syncToken").cloned();
+            sync_tokens.lock().unwrap().push(sync_token.clone());
+
+            let start = chrono::Utc::now() + chrono::Duration::hours(1);
+            let response = match sync_token.as_deref() {
+                Some("sync-token-1") => events_json(
+                    vec![
+                        json!({ "kind": "calendar#event", "id": "removed", "status": "cancelled" }),
+                    ],
+                    "sync-token-2",
+                ),
+                _ => events_json(
+                    vec![
+                        event_json("kept", "Kept", start, ""),
+                        event_json("removed", "Will be cancelled", start, ""),
+                    ],
+                    "sync-token-1",
+                ),
+            };
+            warp::reply::json(&response)
+        });
+
+    serve(events)
+}
+
+/**
+ * events.watch と、差分同期で kept の名前の変更と removed のキャンセルを返す一覧。watch の回数を watch_count に数える
+ */
+pub fn spawn_fake_google_watch(watch_count: Arc<AtomicUsize>) -> SocketAddr {
+    let watch = warp::post()
+        .and(warp::path!(
+            "calendar" / "v3" / "calendars" / "primary" / "events" / "watch"
+        ))
+        .and(warp::body::json::<serde_json::Value>())
+        .map(move |body: serde_json::Value| {
+            watch_count.fetch_add(1, Ordering::SeqCst);
+            let expiration = chrono::Utc::now() + chrono::Duration::days(7);
+            warp::reply::json(&json!({
+                "kind": "api#channel",
+                "id": body["id"],
+                "resourceId": "resource-1",
+                "resourceUri": "https://www.googleapis.com/calendar/v3/calendars/primary/events",
+                "expiration": expiration.timestamp_millis().to_string()
+            }))
+        });
+
+    let events = warp::get()
+        .and(warp::path!(
+            "calendar" / "v3" / "calendars" / "primary" / "events"
+        ))
+        .and(warp::query::<HashMap<String, String>>())
+        .map(|query: HashMap<String, String>| {
+            let start = chrono::Utc::now() + chrono::Duration::hours(1);
+            let response = match query.get("syncToken").map(String::as_str) {
+                Some("sync-token-1") => events_json(
+                    vec![
+                        event_json("kept", "Renamed", start, ""),
+                        json!({ "kind": "calendar#event", "id": "removed", "status": "cancelled" }),
+                    ],
+                    "sync-token-2",
+                ),
+                _ => events_json(
+                    vec![
+                        event_json("kept", "Original", start, ""),
+                        event_json("removed", "Will be cancelled", start, ""),
+                    ],
+                    "sync-token-1",
+                ),
+            };
+            warp::reply::json(&response)
+        });
+
+    serve(watch.or(events))
+}
+
+fn instance_json(id: &str, start: chrono::DateTime<chrono::Utc>) -> serde_json::Value {
+    let mut instance = event_json(id, "Weekly sync", start, "");
+    instance["recurringEventId"] = "weekly".into();
+    instance["originalStartTime"] =
+        json!({ "dateTime": start.to_rfc3339(), "timeZone": "Asia/Tokyo" });
+    instance
+}
+
+/**
+ * 全件同期では繰り返し予定の 2 つのインスタンスと単発の予定を、差分同期では 3 つ目のインスタンスを返す一覧
+ */
+pub fn spawn_fake_google_series() -> SocketAddr {
+    let events = warp::get()
+        .and(warp::path!(
+            "calendar" / "v3" / "calendars" / "primary" / "events"
+        ))
+        .and(warp::query::<HashMap<String, String>>())
+        .map(|query: HashMap<String, String>| {
+            let start = chrono::Utc::now() + chrono::Duration::days(1);
+            let response = match query.get("syncToken").map(String::as_str) {
+                Some("sync-token-1") => events_json(
+                    vec![instance_json(
+                        "weekly_3",
+                        start + chrono::Duration::weeks(2),
+                    )],
+                    "sync-token-2",
+                ),
+                _ => events_json(
+                    vec![
+                        instance_json("weekly_1", start),
+                        instance_json("weekly_2", start + chrono::Duration::weeks(1)),
+                        event_json("single", "One-off", start, ""),
+                    ],
+                    "sync-token-1",
+                ),
+            };
+            warp::reply::json(&response)
+        });
+
+    serve(events)
+}
+
+fn meeting_json(self_status: &str) -> serde_json::Value {
+    let mut meeting = event_json(
+        "meeting",
+        "Design review",
+        chrono::Utc::now() + chrono::Duration::days(1),
+        "",
+    );
+    meeting["attendees"] = json!([
+        { "email": "me@example.com", "self": true, "responseStatus": self_status },
+        { "email": "boss@example.com", "organizer": true, "responseStatus": "accepted", "comment": "Required" }
+    ]);
+    meeting
+}
+
+/**
+ * 参加者のいる meeting と、参加者のいない solo を返す一覧・取得と、meeting への PATCH。PATCH の本文を patches に記録する
+ */
+pub fn spawn_fake_google_rsvp(patches: Arc<Mutex<Vec<serde_json::Value>>>) -> SocketAddr {
+    let list = warp::get()
+        .and(warp::path!(
+            "calendar" / "v3" / "calendars" / "primary" / "events"
+        ))
+        .map(|| {
+            let solo = event_json(
+                "solo",
+                "Focus",
+                chrono::Utc::now() + chrono::Duration::days(1),
+                "",
+            );
+            warp::reply::json(&events_json(
+                vec![meeting_json("needsAction"), solo],
+                "sync-token-1",
+            ))
+        });
+
+    let get = warp::get()
+        .and(warp::path!(
+            "calendar" / "v3" / "calendars" / "primary" / "events" / String
+        ))
+        .map(|id: String| match id.as_str() {
+            "meeting" => warp::reply::json(&meeting_json("needsAction")),
+            _ => warp::reply::json(&event_json(&id, "Focus", chrono::Utc::now(), "")),
+        });
+
+    let patch = warp::patch()
+        .and(warp::path!(
+            "calendar" / "v3" / "calendars" / "primary" / "events" / "meeting"
+        ))
+        .and(warp::body::json::<serde_json::Value>())
+        .map(move |body: serde_json::Value| {
+            patches.lock().unwrap().push(body.clone());
+            let mut meeting = meeting_json("needsAction");
+            meeting["attendees"] = body["attendees"].clone();
+            warp::reply::json(&meeting)
+        });
+
+    serve(list.or(get).or(patch))
+}
+
+/// spawn_fake_google_create が受け取ったリクエスト
+#[derive(Default)]
+pub struct CreateReceived {
+    pub quick_add_texts: Vec<String>,
+    pub inserts: Vec<(HashMap<String, String>, serde_json::Value)>,
+}
+
+/**
+ * quickAdd と events.insert、カレンダーのタイムゾーンを返す calendars.get。
+ * quickAdd の文章が "Server error" の場合は 503 を返す
+ */
+pub fn spawn_fake_google_create(received: Arc<Mutex<CreateReceived>>) -> SocketAddr {
+    let quick_add_received = received.clone();
+    let quick_add = warp::post()
+        .and(warp::path!(
+            "calendar" / "v3" / "calendars" / "primary" / "events" / "quickAdd"
+        ))
+        .and(warp::query::<HashMap<String, String>>())
+        .map(move |query: HashMap<String, String>| {
+            let text = query["text"].clone();
+            quick_add_received
+                .lock()
+                .unwrap()
+                .quick_add_texts
+                .push(text.clone());
+            if text == "Server error" {
+                return warp::reply::with_status(
+                    warp::reply::json(&json!({})),
+                    StatusCode::SERVICE_UNAVAILABLE,
+                );
+            }
+            // オフセットもタイムゾーンもない日時は、カレンダーのタイムゾーンで読む
+            let mut event = event_json("quick", &text, chrono::Utc::now(), "");
+            event["start"] = json!({ "dateTime": "2026-10-20T12:30:00" });
+            event["end"] = json!({ "dateTime": "2026-10-20T13:30:00" });
+            warp::reply::with_status(warp::reply::json(&event), StatusCode::OK)
+        });
+
+    let calendar = warp::get()
+        .and(warp::path!("calendar" / "v3" / "calendars" / "primary"))
+        .map(|| warp::reply::json(&json!({ "id": "me@example.com", "timeZone": "Asia/Tokyo" })));
+
+    let insert = warp::post()
+        .and(warp::path!(
+            "calendar" / "v3" / "calendars" / "primary" / "events"
+        ))
+        .and(warp::query::<HashMap<String, String>>())
+        .and(warp::body::json::<serde_json::Value>())
+        .map(
+            move |query: HashMap<String, String>, body: serde_json::Value| {
+                received.lock().unwrap().inserts.push((query, body.clone()));
+                let mut event = event_json("inserted", "", chrono::Utc::now(), "");
+                event["summary"] = body["summary"].clone();
+                event["start"] = body["start"].clone();
+                event["end"] = body["end"].clone();
+                event["hangoutLink"] = "https://meet.google.com/abc-defg-hij".into();
+                event["conferenceData"] = json!({
+                    "createRequest": { "status": { "statusCode": "success" } },
+                    "entryPoints": [
+                        { "entryPointType": "video", "uri": "https://meet.google.com/abc-defg-hij" }
+                    ]
+                });
+                warp::reply::json(&event)
+            },
+        );
+
+    serve(quick_add.or(insert).or(calendar))
+}
+
+/// spawn_fake_google_event_types の予定の基準の日時
+pub fn event_types_start() -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::parse_from_rfc3339("2026-10-20T09:00:00+09:00")
+        .unwrap()
+        .to_utc()
+}
+
+/**
+ * 不在・集中時間・勤務場所の予定と、不在の間と後に始まる予定を返す一覧
+ */
+pub fn spawn_fake_google_event_types() -> SocketAddr {
+    let events = warp::get()
+        .and(warp::path!(
+            "calendar" / "v3" / "calendars" / "primary" / "events"
+        ))
+        .map(|| {
+            let start = event_types_start();
+            let mut absence = event_json("absence", "通院", start, "");
+            absence["eventType"] = "outOfOffice".into();
+            absence["end"] = json!({
+                "dateTime": (start + chrono::Duration::hours(3)).to_rfc3339(),
+                "timeZone": "Asia/Tokyo"
+            });
+            let mut focus = event_json("focus", "集中", start + chrono::Duration::hours(4), "");
+            focus["eventType"] = "focusTime".into();
+            let mut working_location = event_json("working_location", "Office", start, "");
+            working_location["eventType"] = "workingLocation".into();
+            working_location["workingLocationProperties"] = json!({
+                "type": "officeLocation",
+                "officeLocation": { "label": "Office B" }
+            });
+            warp::reply::json(&events_json(
+                vec![
+                    absence,
+                    focus,
+                    working_location,
+                    event_json("during", "1on1", start + chrono::Duration::hours(1), ""),
+                    event_json("after", "Review", start + chrono::Duration::hours(3), ""),
+                ],
+                "sync-token-1",
+            ))
+        });
+
+    serve(events)
+}
@@ -0,0 +1,432 @@
+//! Google Calendar API と OAuth トークンエンドポイントを warp のモックサーバーで置き換えた結合テスト。
+//! 環境変数を共有するため、各テストは setup_env の中で 1 つずつ実行する
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+
+use calendar_notice::{
+    env::Env,
+    google_calendar::{
+        self, create::NewEvent, rsvp::ResponseStatus, sync_changes_with_latest_token,
+        sync_with_latest_token, GoogleProvider, SyncWindow,
+    },
+    notification::{
+        apply_notification_settings, exclude_out_of_office_events, filter_upcoming_events,
+        NotificationSettings,
+    },
+    oauth,
+    provider::{self, sync_all_changes, sync_events},
+    repository::{self, models::EventFindMany},
+};
+use common::{
+    create_token, event_ids, event_types_start, events_by_id, setup_env, spawn_fake_google_create,
+    spawn_fake_google_event_types, spawn_fake_google_oauth, spawn_fake_google_rate_limited,
+    spawn_fake_google_rsvp, spawn_fake_google_series, CreateReceived, OAuthReceived, ACCESS_TOKEN,
+};
+use serde_json::json;
+
+mod common;
+
+/// OAuth リダイレクト → トークン保存 → 同期 → 通知対象の選択 までを通しで確認する
+#[tokio::test]
+async fn oauth_redirect_to_notification() {
+    let received = Arc::new(Mutex::new(OAuthReceived::default()));
+    let addr = spawn_fake_google_oauth(received.clone());
+    let _env = setup_env(addr);
+
+    // 認可画面の URL がモックサーバーを向いている
+    let oauth_url = oauth::oauth_url(&Env::new());
+    assert!(oauth_url.starts_with(&format!("http://{}/o/oauth2/auth?", addr)));
+    assert!(oauth_url.contains("redirect_uri=http://localhost:8990/auth"));
+
+    // OAuth リダイレクトを受け取ると、トークンを取得・保存して同期する
+    let response = warp::test::request()
+        .method("GET")
+        .path("/auth?code=fake-code&state=random_state_string")
+        .reply(&oauth::routes())
+        .await;
+    assert_eq!(response.status(), 200);
+
+    {
+        let received = received.lock().unwrap();
+        assert_eq!(received.token_requests.len(), 1);
+        assert_eq!(received.token_requests[0]["code"], "fake-code");
+        assert_eq!(
+            received.token_requests[0]["grant_type"],
+            "authorization_code"
+        );
+        assert_eq!(
+            received.authorizations,
+            vec![format!("Bearer {}", ACCESS_TOKEN)]
+        );
+    }
+
+    let token = repository::oauth_token::find_latest().unwrap().unwrap();
+    assert_eq!(token.access_token, ACCESS_TOKEN);
+    assert_eq!(token.refresh_token.as_deref(), Some("fake-refresh-token"));
+
+    let events = repository::event::find_many(EventFindMany::default()).unwrap();
+    assert_eq!(
+        events.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+        vec!["soon", "later", "holiday"]
+    );
+    // 終日の予定はカレンダーのタイムゾーン(Asia/Tokyo)の 0 時から
+    assert!(!events[0].all_day);
+    assert!(events[2].all_day);
+    assert_eq!(
+        events[2].start_datetime.format("%H:%M").to_string(),
+        "15:00"
+    );
+    assert!(!events[2].notification_enabled);
+    assert_eq!(events[0].location.as_deref(), Some("Office B 3F"));
+    assert_eq!(events[1].location, None);
+    assert_eq!(
+        events[0].zoom_link.as_deref(),
+        Some("https://zoom.us/j/95428352872?pwd=abc")
+    );
+    assert_eq!(
+        events[1].teams_link.as_deref(),
+        Some("https://teams.microsoft.com/l/meetup-join/19%3ameeting_ABC")
+    );
+
+    let attendees = repository::attendee::find_by_event_id("soon").unwrap();
+    assert_eq!(
+        attendees
+            .iter()
+            .map(|a| (a.email.as_str(), a.organizer, a.is_self))
+            .collect::<Vec<_>>(),
+        vec![
+            ("boss@example.com", true, false),
+            ("me@example.com", false, true)
+        ]
+    );
+    assert_eq!(
+        repository::attendee::find_needs_action_event_ids().unwrap(),
+        vec!["soon"]
+    );
+
+    let entry_points = repository::conference_entry_point::find_by_event_id("soon").unwrap();
+    assert_eq!(
+        entry_points
+            .iter()
+            .map(|e| (e.entry_point_type.as_str(), e.uri.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("video", "https://meet.google.com/abc-defg-hij"),
+            ("phone", "tel:+1-555-0100")
+        ]
+    );
+    assert_eq!(
+        entry_points[1].dial_in_text().as_deref(),
+        Some("+1 555-0100 (US) PIN: 123456789")
+    );
+    assert!(
+        repository::conference_entry_point::find_by_event_id("later")
+            .unwrap()
+            .is_empty()
+    );
+
+    // 添付ファイルを先に、説明文のリンクは会議と添付ファイルのものを除いて資料にする
+    let resources = repository::event_resource::find_by_event_id("soon").unwrap();
+    assert_eq!(
+        resources
+            .iter()
+            .map(|r| (r.source.as_str(), r.url.as_str(), r.display_title()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                "attachment",
+                "https://docs.google.com/document/d/agenda/edit",
+                "Standup agenda"
+            ),
+            (
+                "description",
+                "https://example.com/notes",
+                "https://example.com/notes"
+            )
+        ]
+    );
+    assert!(repository::event_resource::find_by_event_id("later")
+        .unwrap()
+        .is_empty());
+
+    // 10 分前通知の設定なので、5 分後に始まるイベントだけが通知対象になる
+    let upcoming = filter_upcoming_events(events);
+    assert_eq!(
+        upcoming.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+        vec!["soon"]
+    );
+}
+
+/// 2 ページ目で長い Retry-After のレート制限を受けた同期は、途中までのイベントを同期済みにせず、
+/// 同期サイクルを失敗にしないまま次回の同期で取得し直す
+#[tokio::test]
+async fn rate_limited_page_defers_sync() {
+    let rate_limited = Arc::new(AtomicBool::new(true));
+    let addr = spawn_fake_google_rate_limited(rate_limited.clone());
+    let _env = setup_env(addr);
+
+    create_token("token", None, chrono::Utc::now());
+    let window = SyncWindow::from_env(&Env::new());
+
+    // 一覧の取得はレート制限で失敗し、1 ページ目だけを保存しない
+    let result = sync_events(&GoogleProvider::default(), window).await;
+    assert!(matches!(result, Err(provider::Error::RateLimited)));
+    assert!(event_ids().is_empty());
+    assert!(repository::sync_state::find("primary").unwrap().is_none());
+
+    // 同期サイクルは失敗にせず、次回に回す
+    assert!(sync_all_changes(window).await.is_ok());
+    assert!(event_ids().is_empty());
+    assert!(repository::sync_state::find("primary").unwrap().is_none());
+
+    // レート制限が解けると、次回の同期ですべてのページを取得する
+    rate_limited.store(false, Ordering::SeqCst);
+    assert!(sync_all_changes(window).await.is_ok());
+    assert_eq!(event_ids(), vec!["first", "second"]);
+    assert_eq!(
+        repository::sync_state::find("primary")
+            .unwrap()
+            .unwrap()
+            .sync_token
+            .as_deref(),
+        Some("sync-token")
+    );
+}
+
+/// 繰り返し予定のシリーズに通知設定を適用し、以降の同期で追加されたインスタンスが引き継ぐ
+#[tokio::test]
+async fn series_settings_are_inherited() {
+    let addr = spawn_fake_google_series();
+    let _env = setup_env(addr);
+
+    create_token("token", None, chrono::Utc::now());
+
+    let window = SyncWindow::from_env(&Env::new());
+    sync_with_latest_token(window).await.unwrap();
+    let events = events_by_id();
+    assert_eq!(
+        events["weekly_1"].recurring_event_id.as_deref(),
+        Some("weekly")
+    );
+    assert!(events["weekly_1"].original_start_time.is_some());
+    assert_eq!(events["single"].recurring_event_id, None);
+
+    // 1 つのインスタンスからシリーズ全体の通知をオフにし、5 分前通知にする
+    apply_notification_settings(
+        &events["weekly_1"],
+        NotificationSettings {
+            enabled: Some(false),
+            sec_from_start: Some(300),
+        },
+        true,
+    )
+    .unwrap();
+    let events = events_by_id();
+    for id in ["weekly_1", "weekly_2"] {
+        assert!(!events[id].notification_enabled);
+        assert_eq!(events[id].notification_sec_from_start, 300);
+    }
+    assert!(events["single"].notification_enabled);
+
+    // 差分同期で追加されたインスタンスもシリーズの設定を引き継ぐ
+    sync_changes_with_latest_token(window).await.unwrap();
+    let events = events_by_id();
+    assert!(!events["weekly_3"].notification_enabled);
+    assert_eq!(events["weekly_3"].notification_sec_from_start, 300);
+
+    // series が false の場合はそのインスタンスだけを変更する
+    apply_notification_settings(
+        &events["weekly_2"],
+        NotificationSettings {
+            enabled: Some(true),
+            ..Default::default()
+        },
+        false,
+    )
+    .unwrap();
+    let events = events_by_id();
+    assert!(events["weekly_2"].notification_enabled);
+    assert!(!events["weekly_3"].notification_enabled);
+}
+
+/// 不在・集中時間・勤務場所の予定を保存し、種類ごとの既定の通知設定と不在中の予定の除外を確認する
+#[tokio::test]
+async fn event_types_are_handled() {
+    let addr = spawn_fake_google_event_types();
+    let _env = setup_env(addr);
+
+    create_token("token", None, chrono::Utc::now());
+
+    sync_with_latest_token(SyncWindow::from_env(&Env::new()))
+        .await
+        .unwrap();
+    let events = events_by_id();
+
+    assert_eq!(events["absence"].event_type.as_deref(), Some("outOfOffice"));
+    assert!(!events["absence"].notification_enabled);
+    assert!(!events["working_location"].notification_enabled);
+    assert_eq!(
+        events["working_location"].location.as_deref(),
+        Some("Office B")
+    );
+    assert!(events["focus"].notification_enabled);
+    assert_eq!(events["focus"].notification_sec_from_start, 60);
+    assert!(events["during"].notification_enabled);
+    assert_eq!(events["during"].notification_sec_from_start, 600);
+
+    // 不在の期間に始まる予定は通知しない
+    let out_of_office = repository::event::find_out_of_office(
+        event_types_start(),
+        event_types_start() + chrono::Duration::days(1),
+    )
+    .unwrap();
+    assert_eq!(
+        out_of_office
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect::<Vec<_>>(),
+        vec!["absence"]
+    );
+    let mut notified = exclude_out_of_office_events(events.into_values().collect(), &out_of_office)
+        .into_iter()
+        .map(|e| e.id)
+        .collect::<Vec<_>>();
+    notified.sort();
+    assert_eq!(notified, vec!["absence", "after", "focus"]);
+}
+
+/// 出欠の回答で attendees を PATCH し、保存済みの参加者と通知設定が更新される
+#[tokio::test]
+async fn decline_patches_attendees_and_mutes_event() {
+    let patches = Arc::new(Mutex::new(vec![]));
+    let addr = spawn_fake_google_rsvp(patches.clone());
+    let _env = setup_env(addr);
+
+    // 読み取り専用のトークンでは回答せず、再認可を促す
+    let now = chrono::Utc::now();
+    create_token(
+        "readonly",
+        Some("https://www.googleapis.com/auth/calendar.readonly"),
+        now - chrono::Duration::seconds(10),
+    );
+    sync_with_latest_token(SyncWindow::from_env(&Env::new()))
+        .await
+        .unwrap();
+    assert!(matches!(
+        provider::respond("meeting", ResponseStatus::Declined).await,
+        Err(provider::Error::InsufficientScope)
+    ));
+    assert!(patches.lock().unwrap().is_empty());
+
+    create_token(
+        "events",
+        Some("https://www.googleapis.com/auth/calendar.events"),
+        now,
+    );
+
+    // 参加者に自分がいない予定には回答できない
+    assert!(matches!(
+        provider::respond("solo", ResponseStatus::Accepted).await,
+        Err(provider::Error::Google(google_calendar::Error::NotAttendee))
+    ));
+
+    provider::respond("meeting", ResponseStatus::Declined)
+        .await
+        .unwrap();
+
+    // 自分の responseStatus だけを書き換え、他の参加者の値は保持して送る
+    assert_eq!(
+        patches.lock().unwrap().clone(),
+        vec![json!({
+            "attendees": [
+                { "email": "me@example.com", "self": true, "responseStatus": "declined" },
+                { "email": "boss@example.com", "organizer": true, "responseStatus": "accepted", "comment": "Required" }
+            ]
+        })]
+    );
+
+    let attendees = repository::attendee::find_self_many(vec!["meeting".to_string()]).unwrap();
+    assert_eq!(attendees[0].response_status.as_deref(), Some("declined"));
+
+    let events = repository::event::find_many(EventFindMany::default()).unwrap();
+    let meeting = events.iter().find(|e| e.id == "meeting").unwrap();
+    assert!(!meeting.notification_enabled);
+}
+
+/// quickAdd と events.insert をモックサーバーに送り、作成したイベントがすぐに保存される
+#[tokio::test]
+async fn create_events_and_save_locally() {
+    let received = Arc::new(Mutex::new(CreateReceived::default()));
+    let addr = spawn_fake_google_create(received.clone());
+    let _env = setup_env(addr);
+
+    create_token(
+        "token",
+        Some("https://www.googleapis.com/auth/calendar.events"),
+        chrono::Utc::now(),
+    );
+
+    // 作成は再送すると重複するため、サーバーエラーでも 1 回しか送らない
+    assert!(provider::quick_add("Server error").await.is_err());
+
+    provider::quick_add("Lunch with Ken tomorrow 12:30")
+        .await
+        .unwrap();
+
+    let start = chrono::DateTime::parse_from_rfc3339("2026-10-20T12:30:00+09:00").unwrap();
+    provider::create(&NewEvent {
+        summary: "Design review".to_string(),
+        start,
+        duration: chrono::Duration::minutes(60),
+        with_meet: true,
+    })
+    .await
+    .unwrap();
+
+    {
+        let received = received.lock().unwrap();
+        assert_eq!(
+            received.quick_add_texts,
+            vec!["Server error", "Lunch with Ken tomorrow 12:30"]
+        );
+        let (query, body) = &received.inserts[0];
+        assert_eq!(query["conferenceDataVersion"], "1");
+        assert_eq!(body["end"]["dateTime"], "2026-10-20T13:30:00+09:00");
+        assert_eq!(body["reminders"]["useDefault"], true);
+        assert_eq!(
+            body["conferenceData"]["createRequest"]["conferenceSolutionKey"]["type"],
+            "hangoutsMeet"
+        );
+    }
+
+    // 同期を待たずに、既定の通知設定で保存されている
+    let events = repository::event::find_many(EventFindMany {
+        ids_in: Some(vec!["quick".to_string(), "inserted".to_string()]),
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(events.len(), 2);
+    for event in &events {
+        assert!(event.notification_enabled);
+        assert_eq!(event.notification_sec_from_start, 600);
+    }
+    let quick = events.iter().find(|e| e.id == "quick").unwrap();
+    assert_eq!(
+        quick.start_datetime,
+        chrono::DateTime::parse_from_rfc3339("2026-10-20T03:30:00Z").unwrap()
+    );
+    let inserted = events.iter().find(|e| e.id == "inserted").unwrap();
+    assert_eq!(
+        inserted.hangout_link.as_deref(),
+        Some("https://meet.google.com/abc-defg-hij")
+    );
+    assert_eq!(
+        repository::conference_entry_point::find_by_event_id("inserted")
+            .unwrap()
+            .len(),
+        1
+    );
+}
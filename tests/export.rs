@@ -0,0 +1,94 @@
+//! 保存済みの予定を warp のルートから .ics として取得し、期間の絞り込みと通知設定・参加用リンクの書き出しを確認する
+
+use calendar_notice::{
+    export, ical,
+    repository::{self, models::Event},
+    timezone::DisplayTimezone,
+};
+use common::setup_env;
+
+mod common;
+
+fn event(id: &str, start: chrono::DateTime<chrono::Utc>) -> Event {
+    Event {
+        id: id.to_string(),
+        summary: Some(id.to_string()),
+        status: Some("confirmed".to_string()),
+        start_datetime: start,
+        end_datetime: start + chrono::Duration::minutes(30),
+        notification_enabled: true,
+        notification_sec_from_start: 300,
+        provider: "google".to_string(),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn export_stored_events() {
+    let _env = setup_env(([127, 0, 0, 1], 0).into());
+    let routes = export::routes(DisplayTimezone::Named(chrono_tz::Asia::Tokyo));
+
+    let start: chrono::DateTime<chrono::Utc> = "2026-10-20T01:00:00Z".parse().unwrap();
+    repository::event::create_many(vec![
+        Event {
+            teams_link: Some(
+                "https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc%40thread.v2/0"
+                    .to_string(),
+            ),
+            ..event("review", start)
+        },
+        Event {
+            notification_enabled: false,
+            ..event("muted", start + chrono::Duration::hours(2))
+        },
+        event("next-week", start + chrono::Duration::days(7)),
+    ])
+    .unwrap();
+
+    let response = warp::test::request()
+        .path("/export.ics?from=2026-10-20&to=2026-10-21")
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/calendar; charset=utf-8"
+    );
+
+    let calendars = ical::parse(std::str::from_utf8(response.body()).unwrap()).unwrap();
+    let vevents: Vec<&ical::Component> = calendars[0].components_named("VEVENT").collect();
+    assert_eq!(
+        vevents
+            .iter()
+            .map(|vevent| vevent.text("UID").unwrap())
+            .collect::<Vec<_>>(),
+        vec!["review", "muted"]
+    );
+    assert_eq!(
+        vevents[0].text("URL").as_deref(),
+        Some("https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc%40thread.v2/0")
+    );
+    let alarm = vevents[0].components_named("VALARM").next().unwrap();
+    assert_eq!(alarm.text("TRIGGER").as_deref(), Some("-PT300S"));
+    assert_eq!(vevents[1].components_named("VALARM").count(), 0);
+
+    // 期間を指定しなければ全件
+    let response = warp::test::request()
+        .path("/export.ics")
+        .reply(&routes)
+        .await;
+    let calendars = ical::parse(std::str::from_utf8(response.body()).unwrap()).unwrap();
+    assert_eq!(calendars[0].components_named("VEVENT").count(), 3);
+
+    let response = warp::test::request()
+        .path("/export.ics?from=tomorrow")
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), 400);
+
+    let response = warp::test::request()
+        .path("/export.ics?from=2026-10-21&to=2026-10-20")
+        .reply(&routes)
+        .await;
+    assert_eq!(response.status(), 400);
+}
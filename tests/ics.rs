@@ -0,0 +1,181 @@
+//! iCal フィードを warp で配信し、繰り返しの展開・VALARM の通知設定・ETag による再取得の省略と、
+//! .ics ファイルの更新日時での再読み込みを確認する
+
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use calendar_notice::{
+    env::Env,
+    ics::IcsProvider,
+    notification::filter_upcoming_events,
+    provider::{sync_changed_events, sync_events, SyncWindow},
+    repository::{self, models::EventFindMany},
+};
+use common::setup_env;
+use warp::{http::StatusCode, Filter};
+
+mod common;
+
+fn ical_date_time(date_time: chrono::DateTime<chrono::Utc>) -> String {
+    date_time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// 昨日から毎日 5 分後の時刻に始まるスタンドアップ。明日の回は EXDATE で除く
+fn team_ics(with_review: bool) -> String {
+    let today = chrono::Utc::now() + chrono::Duration::minutes(5);
+    let review = format!(
+        "BEGIN:VEVENT\r\nUID:review\r\nDTSTART:{}\r\nDURATION:PT1H\r\nSUMMARY:Review\r\nDESCRIPTION:https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc%40thread.v2/0\r\nEND:VEVENT\r\n",
+        ical_date_time(today + chrono::Duration::days(2))
+    );
+    format!(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nBEGIN:VEVENT\r\nUID:standup\r\nDTSTART:{}\r\nDURATION:PT15M\r\nRRULE:FREQ=DAILY;COUNT=5\r\nEXDATE:{}\r\nSUMMARY:Standup\r\nDESCRIPTION:Join: https://zoom.us/j/95428352872\r\nBEGIN:VALARM\r\nACTION:DISPLAY\r\nTRIGGER:-PT15M\r\nEND:VALARM\r\nEND:VEVENT\r\n{}END:VCALENDAR\r\n",
+        ical_date_time(today - chrono::Duration::days(1)),
+        ical_date_time(today + chrono::Duration::days(1)),
+        if with_review { review } else { String::new() }
+    )
+}
+
+/// (ETag, 本文) を配信し、返したステータスを記録する
+fn spawn_feed(feed: Arc<Mutex<(String, String)>>, statuses: Arc<Mutex<Vec<u16>>>) -> SocketAddr {
+    let routes = warp::path("team.ics")
+        .and(warp::header::optional::<String>("if-none-match"))
+        .map(move |if_none_match: Option<String>| {
+            let (etag, body) = feed.lock().unwrap().clone();
+            let status = if if_none_match.as_ref() == Some(&etag) {
+                StatusCode::NOT_MODIFIED
+            } else {
+                StatusCode::OK
+            };
+            statuses.lock().unwrap().push(status.as_u16());
+            let body = if status == StatusCode::OK {
+                body
+            } else {
+                String::new()
+            };
+            warp::reply::with_header(warp::reply::with_status(body, status), "ETag", etag)
+        });
+
+    let (addr, server) = warp::serve(routes).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    addr
+}
+
+fn stored_ids(prefix: &str) -> Vec<String> {
+    let mut ids: Vec<String> = repository::event::find_many(EventFindMany::default())
+        .unwrap()
+        .into_iter()
+        .map(|event| event.id)
+        .filter(|id| id.starts_with(prefix))
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[tokio::test]
+async fn import_feed_and_file() {
+    let feed = Arc::new(Mutex::new(("\"1\"".to_string(), team_ics(true))));
+    let statuses = Arc::new(Mutex::new(vec![]));
+    let addr = spawn_feed(feed.clone(), statuses.clone());
+    let env = setup_env(addr);
+    let holidays_path = env.dir.join("holidays.ics");
+    let holiday = |summary: &str| {
+        format!(
+            "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:holiday\r\nDTSTART;VALUE=DATE:{}\r\nSUMMARY:{}\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n",
+            (chrono::Utc::now() + chrono::Duration::days(3)).format("%Y%m%d"),
+            summary
+        )
+    };
+    std::fs::write(&holidays_path, holiday("Holiday")).unwrap();
+    std::env::set_var(
+        "ICS_SOURCES",
+        format!(
+            "http://{}/team.ics; {}",
+            addr,
+            holidays_path.to_str().unwrap()
+        ),
+    );
+
+    let env = Env::new();
+    let window = SyncWindow::from_env(&env);
+    let providers = IcsProvider::from_env(&env);
+    assert_eq!(providers.len(), 2);
+    let (team, holidays) = (&providers[0], &providers[1]);
+    let team_prefix = format!("ics:http://{}/team.ics#", addr);
+
+    // 昨日の回は同期期間外、明日の回は EXDATE で除かれる
+    sync_changed_events(team, window).await.unwrap();
+    let ids = stored_ids(&team_prefix);
+    assert_eq!(ids.len(), 4);
+    assert_eq!(ids[0], format!("{}review", team_prefix));
+    assert!(ids[1..]
+        .iter()
+        .all(|id| id.starts_with(&format!("{}standup#", team_prefix))));
+
+    let events = repository::event::find_many(EventFindMany::default()).unwrap();
+    let standup = &events[0];
+    assert_eq!(standup.provider, "ics");
+    assert_eq!(
+        standup.recurring_event_id,
+        Some(format!("{}standup", team_prefix))
+    );
+    assert_eq!(
+        standup.zoom_link.as_deref(),
+        Some("https://zoom.us/j/95428352872")
+    );
+    // VALARM の 15 分前に通知する
+    assert_eq!(standup.notification_sec_from_start, 15 * 60);
+    let review = events.iter().find(|event| event.id == ids[0]).unwrap();
+    assert!(review.teams_link.is_some());
+    assert_eq!(
+        filter_upcoming_events(events.clone())
+            .into_iter()
+            .map(|event| event.id)
+            .collect::<Vec<_>>(),
+        vec![standup.id.clone()]
+    );
+
+    // ETag が変わらなければ本文を取得し直さない
+    sync_changed_events(team, window).await.unwrap();
+    assert_eq!(statuses.lock().unwrap().clone(), vec![200, 304]);
+    assert_eq!(stored_ids(&team_prefix).len(), 4);
+
+    // 全件同期でも、保存した ETag で変更を確認する
+    sync_events(team, window).await.unwrap();
+    assert_eq!(statuses.lock().unwrap().clone(), vec![200, 304, 304]);
+    assert_eq!(stored_ids(&team_prefix).len(), 4);
+
+    // フィードから無くなった予定は削除する
+    *feed.lock().unwrap() = ("\"2\"".to_string(), team_ics(false));
+    sync_changed_events(team, window).await.unwrap();
+    assert_eq!(statuses.lock().unwrap().clone(), vec![200, 304, 304, 200]);
+    assert_eq!(stored_ids(&team_prefix).len(), 3);
+    assert!(!stored_ids(&team_prefix).contains(&format!("{}review", team_prefix)));
+
+    // ファイルは更新日時が変わったときだけ読み直す
+    sync_changed_events(holidays, window).await.unwrap();
+    let holiday_id = format!("ics:{}#holiday", holidays_path.to_str().unwrap());
+    let find_holiday = || {
+        repository::event::find_many(EventFindMany {
+            ids_in: Some(vec![holiday_id.clone()]),
+            ..Default::default()
+        })
+        .unwrap()
+        .remove(0)
+    };
+    let stored = find_holiday();
+    assert!(stored.all_day);
+    assert!(!stored.notification_enabled);
+
+    std::fs::write(&holidays_path, holiday("Renamed holiday")).unwrap();
+    let file = std::fs::File::options()
+        .write(true)
+        .open(&holidays_path)
+        .unwrap();
+    file.set_modified(SystemTime::now() - Duration::from_secs(60))
+        .unwrap();
+    sync_changed_events(holidays, window).await.unwrap();
+    assert_eq!(find_holiday().summary.as_deref(), Some("Renamed holiday"));
+}
@@ -0,0 +1,169 @@
+//! 保存時に予定の場所・説明文から、会議のリンクと電話で参加するための番号を抽出することを確認する
+
+use calendar_notice::{
+    provider::{save_events, CalendarEvent},
+    repository::{self, models::EventFindMany},
+};
+use common::setup_env;
+
+mod common;
+
+/// 保存時に場所・HTML の説明文から会議のリンクを抽出し、MEETING_LINK_PATTERNS の抽出方法も使うことと、
+/// 会議のリンクを資料として扱わないことを確認する
+#[tokio::test]
+async fn save_meeting_links() {
+    let _env = setup_env(([127, 0, 0, 1], 0).into());
+    std::env::set_var(
+        "MEETING_LINK_PATTERNS",
+        r"intra=https://vc\.example\.com/room/(?P<id>\d+)",
+    );
+
+    let start = chrono::Utc::now() + chrono::Duration::hours(1);
+    let event = CalendarEvent {
+        id: "sync".to_string(),
+        summary: Some("Sync".to_string()),
+        description: Some(
+            "Webex: https://example.webex.com/meet/alice.smith
+予備: https://vc.example.com/room/42
+Chime: https://chime.aws/1234567890
+アジェンダ: https://docs.example.com/agenda"
+                .to_string(),
+        ),
+        status: Some("confirmed".to_string()),
+        start,
+        end: start + chrono::Duration::minutes(30),
+        ..Default::default()
+    };
+    save_events("google", &[event]).unwrap();
+
+    let meeting_links = repository::meeting_link::find_by_event_id("sync").unwrap();
+    assert_eq!(
+        meeting_links
+            .iter()
+            .map(|link| (
+                link.provider.as_str(),
+                link.url.as_str(),
+                link.meeting_id.as_deref()
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                "webex",
+                "https://example.webex.com/meet/alice.smith",
+                Some("alice.smith")
+            ),
+            ("intra", "https://vc.example.com/room/42", Some("42")),
+            ("chime", "https://chime.aws/1234567890", Some("1234567890")),
+        ]
+    );
+
+    let resources = repository::event_resource::find_by_event_id("sync").unwrap();
+    assert_eq!(
+        resources
+            .iter()
+            .map(|resource| resource.url.as_str())
+            .collect::<Vec<_>>(),
+        vec!["https://docs.example.com/agenda"]
+    );
+
+    // 場所と HTML の説明文にあるリンクも使う。安全なリンクは元の URL に戻す
+    let event = CalendarEvent {
+        id: "outlook".to_string(),
+        summary: Some("Outlook".to_string()),
+        location: Some("https://us02web.zoom.us/j/95428352872?pwd=abc".to_string()),
+        description: Some(
+            r#"<div><a href="https://nam02.safelinks.protection.outlook.com/?url=https%3A%2F%2Fmeet.jit.si%2FWeeklyRetro&amp;data=05">Jitsi</a><br>資料 <a href="https://www.google.com/url?q=https://docs.example.com/retro&amp;sa=D">retro</a></div>"#
+                .to_string(),
+        ),
+        status: Some("confirmed".to_string()),
+        start,
+        end: start + chrono::Duration::minutes(30),
+        ..Default::default()
+    };
+    save_events("microsoft", &[event]).unwrap();
+
+    let saved = repository::event::find_many(EventFindMany {
+        ids_in: Some(vec!["outlook".to_string()]),
+        ..Default::default()
+    })
+    .unwrap()
+    .remove(0);
+    assert_eq!(
+        saved.zoom_link.as_deref(),
+        Some("https://zoom.us/j/95428352872?pwd=abc")
+    );
+    let meeting_links = repository::meeting_link::find_by_event_id("outlook").unwrap();
+    assert_eq!(
+        meeting_links
+            .iter()
+            .map(|link| (link.provider.as_str(), link.url.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("zoom", "https://zoom.us/j/95428352872?pwd=abc"),
+            ("jitsi", "https://meet.jit.si/WeeklyRetro"),
+        ]
+    );
+    let resources = repository::event_resource::find_by_event_id("outlook").unwrap();
+    assert_eq!(
+        resources
+            .iter()
+            .map(|resource| resource.url.as_str())
+            .collect::<Vec<_>>(),
+        vec!["https://docs.example.com/retro"]
+    );
+}
+
+/// 保存時に HTML の説明文から電話で参加するための番号を抽出し、予定を保存し直すと置き換わることを確認する
+#[tokio::test]
+async fn save_dial_ins() {
+    let _env = setup_env(([127, 0, 0, 1], 0).into());
+
+    let start = chrono::Utc::now() + chrono::Duration::hours(1);
+    let event = CalendarEvent {
+        id: "zoom".to_string(),
+        summary: Some("Zoom".to_string()),
+        description: Some(
+            r#"<p>Join Zoom Meeting<br><a href="https://us02web.zoom.us/j/95428352872">https://us02web.zoom.us/j/95428352872</a></p>
+<p>Meeting ID: 954 2835 2872<br>Passcode: 314159</p>
+<p>One tap mobile<br><a href="tel:+16465588656,,95428352872#,,,,*314159#">+16465588656,,95428352872#,,,,*314159#</a> US (New York)</p>
+<p>Dial by your location<br>&nbsp;&nbsp;&nbsp;&nbsp;+1 646 558 8656 US (New York)<br>&nbsp;&nbsp;&nbsp;&nbsp;+81 3 4578 1488 Japan</p>"#
+                .to_string(),
+        ),
+        status: Some("confirmed".to_string()),
+        start,
+        end: start + chrono::Duration::minutes(30),
+        ..Default::default()
+    };
+    save_events("google", std::slice::from_ref(&event)).unwrap();
+
+    let dial_ins = repository::dial_in::find_by_event_id("zoom").unwrap();
+    assert_eq!(
+        dial_ins
+            .iter()
+            .map(|dial_in| (dial_in.country.as_deref(), dial_in.one_tap()))
+            .collect::<Vec<_>>(),
+        vec![
+            (
+                Some("US (New York)"),
+                "tel:+16465588656,,95428352872#,,,,*314159#".to_string()
+            ),
+            (
+                Some("Japan"),
+                "tel:+81345781488,,95428352872#,,,,*314159#".to_string()
+            ),
+        ]
+    );
+
+    // 説明文から番号がなくなれば消える
+    save_events(
+        "google",
+        &[CalendarEvent {
+            description: Some("https://zoom.us/j/95428352872".to_string()),
+            ..event
+        }],
+    )
+    .unwrap();
+    assert!(repository::dial_in::find_by_event_id("zoom")
+        .unwrap()
+        .is_empty());
+}
@@ -0,0 +1,327 @@
+//! Microsoft ID プラットフォームと Graph API を warp のモックに置き換え、PKCE での認可 →
+//! calendarView/delta の全件同期 → deltaLink での差分同期 → トークンのリフレッシュまでを確認する
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use calendar_notice::{
+    env::Env,
+    microsoft_graph::{
+        oauth::{self, code_challenge_of},
+        MicrosoftProvider,
+    },
+    notification::filter_upcoming_events,
+    provider::{self, sync_changed_events, CalendarProvider, SyncWindow},
+    repository::{
+        self,
+        models::{EventFindMany, OAuthTokenUpdate, SyncState},
+    },
+};
+use common::setup_env;
+use reqwest::Url;
+use serde_json::json;
+use warp::{http::StatusCode, Filter};
+
+mod common;
+
+/// モックの状態。認可画面で渡された code_challenge と、トークンエンドポイントが受けた grant_type
+#[derive(Default)]
+struct MockState {
+    code_challenge: Option<String>,
+    grant_types: Vec<String>,
+}
+
+fn graph_date_time(date_time: chrono::DateTime<chrono::Utc>) -> serde_json::Value {
+    json!({
+        // Graph は小数点以下 7 桁で返す
+        "dateTime": format!("{}.0000000", date_time.format("%Y-%m-%dT%H:%M:%S")),
+        "timeZone": "UTC"
+    })
+}
+
+fn graph_event(id: &str, subject: &str, start: chrono::DateTime<chrono::Utc>) -> serde_json::Value {
+    json!({
+        "id": id,
+        "subject": subject,
+        "body": {
+            "contentType": "text",
+            "content": "Microsoft Teams meeting\nJoin: https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc%40thread.v2/0"
+        },
+        "start": graph_date_time(start),
+        "end": graph_date_time(start + chrono::Duration::minutes(30)),
+        "isAllDay": false,
+        "isCancelled": false,
+        "showAs": "busy",
+        "type": "singleInstance",
+        "organizer": { "emailAddress": { "name": "Boss", "address": "boss@example.com" } },
+        "attendees": [
+            { "type": "required", "status": { "response": "notResponded" }, "emailAddress": { "name": "Me", "address": "me@example.com" } }
+        ],
+        "onlineMeeting": { "joinUrl": "https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc%40thread.v2/0" }
+    })
+}
+
+fn delta_page(host: &str, params: &HashMap<String, String>) -> (StatusCode, serde_json::Value) {
+    let delta_url = format!("http://{}/v1.0/me/calendarView/delta", host);
+    let soon = chrono::Utc::now() + chrono::Duration::minutes(5);
+    let tomorrow = chrono::Utc::now() + chrono::Duration::days(1);
+    if params.contains_key("startDateTime") {
+        let mut weekly = graph_event("weekly-1", "Weekly sync", tomorrow);
+        weekly["type"] = json!("occurrence");
+        weekly["seriesMasterId"] = json!("weekly");
+        weekly["originalStart"] = json!(tomorrow.to_rfc3339());
+        return (
+            StatusCode::OK,
+            json!({
+                "@odata.nextLink": format!("{}?$skiptoken=page2", delta_url),
+                "value": [graph_event("standup", "Standup", soon), weekly]
+            }),
+        );
+    }
+    match params
+        .get("$skiptoken")
+        .or(params.get("$deltatoken"))
+        .map(String::as_str)
+    {
+        Some("page2") => {
+            let mut cancelled = graph_event("cancelled", "Cancelled", tomorrow);
+            cancelled["isCancelled"] = json!(true);
+            (
+                StatusCode::OK,
+                json!({
+                    "@odata.deltaLink": format!("{}?$deltatoken=1", delta_url),
+                    "value": [cancelled]
+                }),
+            )
+        }
+        Some("1") => (
+            StatusCode::OK,
+            json!({
+                "@odata.deltaLink": format!("{}?$deltatoken=2", delta_url),
+                "value": [
+                    graph_event("standup", "Standup (moved)", soon),
+                    { "id": "weekly-1", "@removed": { "reason": "deleted" } }
+                ]
+            }),
+        ),
+        Some("2") => (
+            StatusCode::OK,
+            json!({
+                "@odata.deltaLink": format!("{}?$deltatoken=2", delta_url),
+                "value": []
+            }),
+        ),
+        _ => (
+            StatusCode::GONE,
+            json!({ "error": { "code": "SyncStateNotFound" } }),
+        ),
+    }
+}
+
+fn spawn_fake_microsoft(state: Arc<Mutex<MockState>>) -> SocketAddr {
+    let token_state = state.clone();
+    let token = warp::post()
+        .and(warp::path!("common" / "oauth2" / "v2.0" / "token"))
+        .and(warp::body::form())
+        .map(move |form: HashMap<String, String>| {
+            let mut state = token_state.lock().unwrap();
+            let grant_type = form["grant_type"].clone();
+            state.grant_types.push(grant_type.clone());
+            let valid = form.get("client_id").map(String::as_str) == Some("test-client")
+                && match grant_type.as_str() {
+                    "authorization_code" => {
+                        form.get("code").map(String::as_str) == Some("auth-code")
+                            && form
+                                .get("code_verifier")
+                                .map(|verifier| code_challenge_of(verifier))
+                                == state.code_challenge
+                    }
+                    "refresh_token" => {
+                        form.get("refresh_token").map(String::as_str) == Some("refresh-1")
+                    }
+                    _ => false,
+                };
+            if !valid {
+                return warp::reply::with_status(
+                    warp::reply::json(&json!({ "error": "invalid_grant" })),
+                    StatusCode::BAD_REQUEST,
+                );
+            }
+            warp::reply::with_status(
+                warp::reply::json(&json!({
+                    "token_type": "Bearer",
+                    "scope": "Calendars.Read User.Read",
+                    "expires_in": 3599,
+                    "access_token": format!("access-{}", state.grant_types.len()),
+                    "refresh_token": "refresh-1"
+                })),
+                StatusCode::OK,
+            )
+        });
+
+    let graph = warp::get()
+        .and(warp::path("v1.0"))
+        .and(warp::path::tail())
+        .and(warp::query::<HashMap<String, String>>())
+        .and(warp::header::<String>("host"))
+        .and(warp::header::optional::<String>("authorization"))
+        .map(
+            |tail: warp::path::Tail,
+             params: HashMap<String, String>,
+             host: String,
+             authorization: Option<String>| {
+                if !authorization.is_some_and(|value| value.starts_with("Bearer access-")) {
+                    return warp::reply::with_status(
+                        warp::reply::json(&json!({})),
+                        StatusCode::UNAUTHORIZED,
+                    );
+                }
+                let (status, body) = match tail.as_str() {
+                    "me" => (StatusCode::OK, json!({ "mail": "me@example.com" })),
+                    "me/calendarView/delta" => delta_page(&host, &params),
+                    _ => (StatusCode::NOT_FOUND, json!({})),
+                };
+                warp::reply::with_status(warp::reply::json(&body), status)
+            },
+        );
+
+    let (addr, server) = warp::serve(token.or(graph)).bind_ephemeral(([127, 0, 0, 1], 0));
+    tokio::spawn(server);
+    addr
+}
+
+fn stored_events() -> HashMap<String, repository::models::Event> {
+    repository::event::find_many(EventFindMany::default())
+        .unwrap()
+        .into_iter()
+        .map(|event| (event.id.clone(), event))
+        .collect()
+}
+
+#[tokio::test]
+async fn authorize_and_sync_with_delta() {
+    let state = Arc::new(Mutex::new(MockState::default()));
+    let addr = spawn_fake_microsoft(state.clone());
+    let _env = setup_env(addr);
+    std::env::set_var("MICROSOFT_CLIENT_ID", "test-client");
+    std::env::set_var("MICROSOFT_GRAPH_BASE_URL", format!("http://{}", addr));
+    std::env::set_var("MICROSOFT_LOGIN_BASE_URL", format!("http://{}", addr));
+
+    let env = Env::new();
+    let window = SyncWindow::from_env(&env);
+    let provider = MicrosoftProvider::from_env(&env).unwrap();
+
+    // 認可されていなければ、Microsoft の認可画面を開くよう求める
+    assert!(matches!(
+        sync_changed_events(&provider, window).await,
+        Err(provider::Error::AuthorizationRequired("microsoft"))
+    ));
+
+    let authorization_url = Url::parse(&provider.authorization_url().unwrap()).unwrap();
+    assert_eq!(authorization_url.path(), "/common/oauth2/v2.0/authorize");
+    let params: HashMap<String, String> = authorization_url.query_pairs().into_owned().collect();
+    assert_eq!(params["code_challenge_method"], "S256");
+    assert_eq!(
+        params["redirect_uri"],
+        "http://localhost:8990/auth_microsoft"
+    );
+    assert!(params["scope"].contains("Calendars.Read"));
+    state.lock().unwrap().code_challenge = Some(params["code_challenge"].clone());
+
+    // 知らない state のリダイレクトは受け付けない
+    let response = warp::test::request()
+        .path("/auth_microsoft?code=auth-code&state=unknown")
+        .reply(&oauth::routes())
+        .await;
+    assert_eq!(response.body(), "Failed to get token");
+
+    // 認可コードと code_verifier をトークンに交換し、そのまま全件同期する
+    let response = warp::test::request()
+        .path(&format!(
+            "/auth_microsoft?code=auth-code&state={}",
+            params["state"]
+        ))
+        .reply(&oauth::routes())
+        .await;
+    assert_eq!(response.body(), "Ok handle_oauth_redirect");
+    assert_eq!(
+        state.lock().unwrap().grant_types,
+        vec!["authorization_code"]
+    );
+    // Google のトークンとは区別する
+    assert!(repository::oauth_token::find_latest().unwrap().is_none());
+
+    // nextLink を辿り、キャンセルされた予定は保存しない
+    let events = stored_events();
+    let mut ids: Vec<&String> = events.keys().collect();
+    ids.sort();
+    assert_eq!(ids, vec!["standup", "weekly-1"]);
+    let standup = &events["standup"];
+    assert_eq!(standup.provider, "microsoft");
+    assert_eq!(
+        standup.teams_link.as_deref(),
+        Some("https://teams.microsoft.com/l/meetup-join/19%3ameeting_abc%40thread.v2/0")
+    );
+    assert_eq!(
+        events["weekly-1"].recurring_event_id.as_deref(),
+        Some("weekly")
+    );
+    let attendees = repository::attendee::find_by_event_id("standup").unwrap();
+    assert!(attendees[0].is_self);
+    assert_eq!(attendees[0].response_status.as_deref(), Some("needsAction"));
+    assert_eq!(
+        filter_upcoming_events(events.into_values().collect())
+            .into_iter()
+            .map(|event| event.id)
+            .collect::<Vec<_>>(),
+        vec!["standup"]
+    );
+
+    // deltaLink で変更と削除だけを取得する
+    sync_changed_events(&provider, window).await.unwrap();
+    let events = stored_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events["standup"].summary.as_deref(),
+        Some("Standup (moved)")
+    );
+
+    // 期限切れのトークンはリフレッシュしてから使う
+    let token = repository::oauth_token::find_latest_of("microsoft")
+        .unwrap()
+        .unwrap();
+    repository::oauth_token::update(
+        token.id,
+        OAuthTokenUpdate {
+            access_token: None,
+            expires_in: Some(3599),
+            refresh_token: None,
+            scope: None,
+            token_type: None,
+            updated_at: chrono::Utc::now() - chrono::Duration::hours(2),
+        },
+    )
+    .unwrap();
+    sync_changed_events(&provider, window).await.unwrap();
+    assert_eq!(
+        state.lock().unwrap().grant_types,
+        vec!["authorization_code", "refresh_token"]
+    );
+
+    // deltaLink が失効していれば全件同期し直す
+    let sync_token = repository::sync_state::find(&provider.calendar_id())
+        .unwrap()
+        .and_then(|sync_state| sync_state.sync_token)
+        .unwrap();
+    repository::sync_state::upsert(SyncState {
+        calendar_id: provider.calendar_id(),
+        sync_token: Some(sync_token.replace("deltatoken=2", "deltatoken=expired")),
+        updated_at: chrono::Utc::now(),
+    })
+    .unwrap();
+    sync_changed_events(&provider, window).await.unwrap();
+    assert_eq!(stored_events().len(), 2);
+}
@@ -0,0 +1,199 @@
+//! 同期ループと events.watch の変更通知による差分同期を確認する。
+//! 環境変数を共有するため、各テストは setup_env の中で 1 つずつ実行する
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use calendar_notice::{
+    env::Env,
+    google_calendar::{latest_valid_token, sync_with_latest_token, watch, SyncWindow},
+    repository::{
+        self,
+        models::{EventFindMany, SyncState},
+    },
+    sync::{request_and_wait, spawn_sync_loop, SyncCommand, SyncHandle, SyncStatus, SyncUpdate},
+};
+use common::{
+    create_token, event_ids, setup_env, spawn_fake_google_changes, spawn_fake_google_watch,
+};
+
+mod common;
+
+async fn ping(sync_handle: &SyncHandle, channel_id: &str, token: &str, state: &str) -> u16 {
+    warp::test::request()
+        .method("POST")
+        .path("/notifications")
+        .header("X-Goog-Channel-ID", channel_id)
+        .header("X-Goog-Channel-Token", token)
+        .header("X-Goog-Resource-State", state)
+        .header("X-Goog-Resource-ID", "resource-1")
+        .reply(&watch::routes(sync_handle.clone()))
+        .await
+        .status()
+        .as_u16()
+}
+
+/// 変更通知を登録していなくても、同期ループの定期同期が syncToken で差分同期し、削除されたイベントが消える
+#[tokio::test]
+async fn sync_now_syncs_changes() {
+    let sync_tokens = Arc::new(Mutex::new(vec![]));
+    let addr = spawn_fake_google_changes(sync_tokens.clone());
+    let _env = setup_env(addr);
+
+    create_token("token", None, chrono::Utc::now());
+
+    let sync_handle = spawn_sync_loop();
+
+    // トークンがなければ全件同期する
+    assert_eq!(
+        request_and_wait(&sync_handle, SyncCommand::SyncNow).await,
+        SyncStatus::Succeeded
+    );
+    assert_eq!(event_ids(), vec!["kept", "removed"]);
+
+    // 2 回目は保存したトークンで差分同期し、キャンセルされたイベントを消す
+    assert_eq!(
+        request_and_wait(&sync_handle, SyncCommand::SyncNow).await,
+        SyncStatus::Succeeded
+    );
+    assert_eq!(event_ids(), vec!["kept"]);
+    assert_eq!(
+        *sync_tokens.lock().unwrap(),
+        vec![None, Some("sync-token-1".to_string())]
+    );
+    assert_eq!(
+        repository::sync_state::find("primary")
+            .unwrap()
+            .unwrap()
+            .sync_token
+            .as_deref(),
+        Some("sync-token-2")
+    );
+
+    // 先に送られた要求の結果ではなく、自分の要求の結果を待つ
+    let mut sync_status_rx = sync_handle.subscribe();
+    let earlier = sync_handle.request(SyncCommand::SyncChanges).unwrap();
+    assert_eq!(
+        request_and_wait(&sync_handle, SyncCommand::SyncNow).await,
+        SyncStatus::Succeeded
+    );
+    let mut updates = vec![];
+    while let Ok(update) = sync_status_rx.try_recv() {
+        updates.push(update);
+    }
+    let later = updates[2].request_id.unwrap();
+    assert_ne!(earlier, later);
+    assert_eq!(
+        updates,
+        vec![
+            SyncUpdate {
+                request_id: Some(earlier),
+                status: SyncStatus::Started
+            },
+            SyncUpdate {
+                request_id: Some(earlier),
+                status: SyncStatus::Succeeded
+            },
+            SyncUpdate {
+                request_id: Some(later),
+                status: SyncStatus::Started
+            },
+            SyncUpdate {
+                request_id: Some(later),
+                status: SyncStatus::Succeeded
+            },
+        ]
+    );
+}
+
+/// events.watch のチャンネル登録と、偽の変更通知を受けた差分同期を確認する
+#[tokio::test]
+async fn watch_and_sync_on_ping() {
+    let watch_count = Arc::new(AtomicUsize::new(0));
+    let addr = spawn_fake_google_watch(watch_count.clone());
+    let _env = setup_env(addr);
+    std::env::set_var("WEBHOOK_ADDRESS", "https://example.com/notifications");
+
+    create_token("token", None, chrono::Utc::now());
+
+    // ポーリングの全件同期で syncToken が保存される
+    sync_with_latest_token(SyncWindow::from_env(&Env::new()))
+        .await
+        .unwrap();
+    assert_eq!(event_ids(), vec!["kept", "removed"]);
+
+    // チャンネルは有効期限内であれば登録し直さない
+    let oauth_token = latest_valid_token().await.unwrap();
+    watch::ensure_watch_channel(&oauth_token).await.unwrap();
+    watch::ensure_watch_channel(&oauth_token).await.unwrap();
+    assert_eq!(watch_count.load(Ordering::SeqCst), 1);
+
+    let channel = repository::watch_channel::find_many("primary")
+        .unwrap()
+        .pop()
+        .unwrap();
+    assert_eq!(channel.resource_id, "resource-1");
+
+    let sync_handle = spawn_sync_loop();
+    let mut sync_status_rx = sync_handle.subscribe();
+    assert_eq!(
+        ping(&sync_handle, "unknown", &channel.token, "exists").await,
+        404
+    );
+    assert_eq!(
+        ping(&sync_handle, &channel.id, "wrong-token", "exists").await,
+        403
+    );
+    assert_eq!(
+        ping(&sync_handle, &channel.id, &channel.token, "sync").await,
+        200
+    );
+    assert_eq!(event_ids(), vec!["kept", "removed"]);
+
+    // 変更通知を受けると同期ループが差分同期し、削除されたイベントが消える
+    assert_eq!(
+        ping(&sync_handle, &channel.id, &channel.token, "exists").await,
+        200
+    );
+    let statuses = tokio::time::timeout(Duration::from_secs(10), async {
+        vec![
+            sync_status_rx.recv().await.unwrap().status,
+            sync_status_rx.recv().await.unwrap().status,
+        ]
+    })
+    .await
+    .unwrap();
+    assert_eq!(statuses, vec![SyncStatus::Started, SyncStatus::Succeeded]);
+
+    let events = repository::event::find_many(EventFindMany::default()).unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].summary.as_deref(), Some("Renamed"));
+    assert_eq!(
+        repository::sync_state::find("primary")
+            .unwrap()
+            .unwrap()
+            .sync_token
+            .as_deref(),
+        Some("sync-token-2")
+    );
+
+    // 失効したトークンは None で上書きして消す
+    repository::sync_state::upsert(SyncState {
+        calendar_id: "primary".to_string(),
+        sync_token: None,
+        updated_at: chrono::Utc::now(),
+    })
+    .unwrap();
+    assert_eq!(
+        repository::sync_state::find("primary")
+            .unwrap()
+            .unwrap()
+            .sync_token,
+        None
+    );
+}
@@ -0,0 +1,75 @@
+//! 文字列で保存されていた日時をマイグレーションで変換し、オフセットが混在していても時刻順に検索できることを確認する
+
+use std::net::SocketAddr;
+
+use calendar_notice::{
+    env::Env,
+    repository::{self, models::EventFindMany},
+};
+use common::setup_env;
+use diesel::{Connection, RunQueryDsl, SqliteConnection};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+mod common;
+
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+fn utc(value: &str) -> chrono::DateTime<chrono::Utc> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .unwrap()
+        .to_utc()
+}
+
+#[test]
+fn migrate_string_datetimes() {
+    let _env = setup_env(SocketAddr::from(([127, 0, 0, 1], 0)));
+
+    // 日時を文字列で保存していた頃の状態に戻し、オフセットの異なる行を入れる
+    let mut conn = SqliteConnection::establish(&Env::new().database_url).unwrap();
+    // 日時の変換より後のマイグレーションも含めて戻す
+    while conn.revert_last_migration(MIGRATIONS).unwrap().to_string() != "20261019000006" {}
+    diesel::sql_query(
+        "INSERT INTO events (id, start_datetime, end_datetime, notification_sec_from_start) VALUES
+          ('tokyo', '2026-10-19T12:00:00+09:00', '2026-10-19T12:30:00+09:00', 600),
+          ('utc', '2026-10-19T04:00:00Z', '2026-10-19T04:30:00Z', 600),
+          ('new_york', '2026-10-19T01:00:00.000-04:00', '2026-10-19T01:30:00.000-04:00', 600)",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    diesel::sql_query(
+        "INSERT INTO oauth_tokens (id, access_token, expires_in, created_at, updated_at) VALUES
+          ('token', 'access', '3599', '2026-10-19T12:00:00+09:00', '2026-10-19T12:00:00+09:00')",
+    )
+    .execute(&mut conn)
+    .unwrap();
+    repository::run_migrations().unwrap();
+
+    let events = repository::event::find_many(EventFindMany::default()).unwrap();
+    assert_eq!(
+        events
+            .iter()
+            .map(|e| (e.id.as_str(), e.start_datetime))
+            .collect::<Vec<_>>(),
+        vec![
+            ("tokyo", utc("2026-10-19T03:00:00Z")),
+            ("utc", utc("2026-10-19T04:00:00Z")),
+            ("new_york", utc("2026-10-19T05:00:00Z")),
+        ]
+    );
+
+    // 文字列のままでは "2026-10-19T12:00:00+09:00" が 03:30Z より後と判定されていた
+    let events = repository::event::find_many(EventFindMany {
+        from: Some(utc("2026-10-19T12:30:00+09:00")),
+        to: Some(utc("2026-10-19T05:00:00Z")),
+        ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(
+        events.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+        vec!["utc"]
+    );
+
+    let token = repository::oauth_token::find_latest().unwrap().unwrap();
+    assert_eq!(token.expires_in, Some(3599));
+    assert_eq!(token.updated_at, utc("2026-10-19T03:00:00Z"));
+}